@@ -475,6 +475,8 @@ contract CkErc20Deposit {
 
     address private immutable cketh_minter_main_address;
     event ReceivedErc20(address indexed erc20_contract_address, address indexed owner, uint256 amount, bytes32 indexed principal);
+    event ReceivedErc20WithSubaccount(address indexed erc20_contract_address, address indexed owner, uint256 amount, bytes32 indexed principal, bytes32 subaccount);
+    event ReceivedEth(address indexed from, uint256 value, bytes32 indexed principal);
     
     /**
      * @dev Set cketh_minter_main_address.
@@ -500,4 +502,23 @@ contract CkErc20Deposit {
         
         emit ReceivedErc20(erc20_address, msg.sender, amount, principal);
     }
+
+    /**
+     * @dev Emits the `ReceivedErc20WithSubaccount` event if the transfer succeeds.
+     */
+    function depositWithSubaccount(address erc20_address, uint256 amount, bytes32 principal, bytes32 subaccount) public {
+        IERC20 erc20Token = IERC20(erc20_address);
+        erc20Token.safeTransferFrom(msg.sender, cketh_minter_main_address, amount);
+
+        emit ReceivedErc20WithSubaccount(erc20_address, msg.sender, amount, principal, subaccount);
+    }
+
+    /**
+     * @dev Forwards the native coins to the minter main address and emits the `ReceivedEth` event.
+     */
+    function depositNative(bytes32 principal) public payable {
+        Address.sendValue(payable(cketh_minter_main_address), msg.value);
+
+        emit ReceivedEth(msg.sender, msg.value, principal);
+    }
 }
@@ -68,4 +68,30 @@ contract ContractBTest is Test {
         vm.prank(alice);
         ckErc20Deposit.deposit(address(edenToken), 10000000, x);
     }
+
+    function test_deposit_eden_with_subaccount() public {
+        bytes32 x = 0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef;
+        bytes32 subaccount = bytes32(uint256(1));
+
+        vm.prank(alice);
+        edenToken.approve(address(ckErc20Deposit), 10000000);
+
+        vm.expectEmit(true, true, true, true);
+        emit CkErc20Deposit.ReceivedErc20WithSubaccount(address(edenToken), alice, 10000000, x, subaccount);
+        vm.prank(alice);
+        ckErc20Deposit.depositWithSubaccount(address(edenToken), 10000000, x, subaccount);
+    }
+
+    function test_deposit_native() public {
+        bytes32 x = 0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef;
+        uint256 minterBalance = alice.balance;
+
+        vm.expectEmit(true, true, true, true);
+        emit CkErc20Deposit.ReceivedEth(bob, 0.5 ether, x);
+        vm.prank(bob);
+        ckErc20Deposit.depositNative{value: 0.5 ether}(x);
+
+        assertEq(alice.balance, minterBalance + 0.5 ether);
+        assertEq(address(ckErc20Deposit).balance, 0);
+    }
 }
\ No newline at end of file
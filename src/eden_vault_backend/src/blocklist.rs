@@ -128,6 +128,12 @@ const ETH_ADDRESS_BLOCKLIST: &[Address] = &[
     Address::new(hex!("ffbaC21a641Dcfe4552920138D90F3638B3c9fba")),
 ];
 
+/// Whether the address is in the static blocklist, which cannot be changed without an upgrade.
+pub fn is_statically_blocked(address: &Address) -> bool {
+    ETH_ADDRESS_BLOCKLIST.binary_search(address).is_ok()
+}
+
+/// Whether the address is in the static blocklist or was blocked at runtime.
 pub fn is_blocked(from_address: &Address) -> bool {
-    ETH_ADDRESS_BLOCKLIST.binary_search(from_address).is_ok()
+    is_statically_blocked(from_address) || crate::storage::is_address_in_blocklist(from_address)
 }
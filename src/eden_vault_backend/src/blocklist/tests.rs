@@ -1,4 +1,18 @@
-use crate::blocklist::{is_blocked, ETH_ADDRESS_BLOCKLIST};
+use crate::address::{validate_address_as_destination, AddressValidationError};
+use crate::blocklist::{is_blocked, is_statically_blocked, ETH_ADDRESS_BLOCKLIST};
+use crate::deposit::record_received_events;
+use crate::eth_logs::{ReceivedErc20Event, ReceivedEvent};
+use crate::lifecycle::init::InitArg;
+use crate::lifecycle::EthereumNetwork;
+use crate::numeric::{BlockNumber, Erc20Value, LogIndex, TransactionNonce, Wei, WeiPerGas};
+use crate::state::audit::{apply_state_transition, EventType};
+use crate::state::transactions::{
+    create_transaction, CreateTransactionError, EthWithdrawalRequest, WithdrawalRequest,
+};
+use crate::state::{read_state, State};
+use crate::tx::GasFeeEstimate;
+use crate::withdraw::CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT;
+use candid::{Nat, Principal};
 use ic_ethereum_types::Address;
 use std::str::FromStr;
 
@@ -31,3 +45,154 @@ fn should_block_blocked_addresses() {
         assert!(is_blocked(address));
     }
 }
+
+#[test]
+fn should_block_and_unblock_address_at_runtime() {
+    let address = Address::from_str("0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34").unwrap();
+    assert!(!is_blocked(&address));
+
+    crate::storage::block_address(address);
+    assert!(is_blocked(&address));
+    assert!(!is_statically_blocked(&address));
+    assert_eq!(crate::storage::blocklist(), vec![address]);
+
+    crate::storage::unblock_address(address);
+    assert!(!is_blocked(&address));
+    assert!(crate::storage::blocklist().is_empty());
+}
+
+fn runtime_blocked_address() -> Address {
+    Address::from_str("0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34").unwrap()
+}
+
+fn initial_state() -> State {
+    State::try_from(InitArg {
+        ethereum_network: Default::default(),
+        ecdsa_key_name: "test_key_1".to_string(),
+        ethereum_contract_address: None,
+        ethereum_block_height: Default::default(),
+        minimum_withdrawal_amount: Nat::from(10_000_000_000_000_000_u64),
+        next_transaction_nonce: Default::default(),
+        last_scraped_block_number: Default::default(),
+        admin: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+        ckerc20_token_address: "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238".to_string(),
+        ckerc20_token_symbol: "ckSepoliaUSDC".to_string(),
+        withdraw_fee_value: None,
+        network: None,
+    })
+    .expect("init args should be valid")
+}
+
+#[test]
+fn should_replay_blocklist_changes_idempotently() {
+    let address = runtime_blocked_address();
+    let blocked = EventType::BlockedAddresses {
+        addresses: vec![address],
+        reason: "sanctioned".to_string(),
+    };
+    let unblocked = EventType::UnblockedAddresses {
+        addresses: vec![address],
+        reason: "delisted".to_string(),
+    };
+    let mut state = initial_state();
+
+    apply_state_transition(&mut state, &blocked);
+    // Replaying after an upgrade applies the events again on the blocklist kept in stable memory.
+    apply_state_transition(&mut state, &blocked);
+    assert!(is_blocked(&address));
+    assert_eq!(crate::storage::blocklist(), vec![address]);
+
+    apply_state_transition(&mut state, &unblocked);
+    apply_state_transition(&mut state, &unblocked);
+    assert!(!is_blocked(&address));
+    assert!(crate::storage::blocklist().is_empty());
+
+    for event in [&blocked, &unblocked, &blocked] {
+        apply_state_transition(&mut state, event);
+    }
+    assert_eq!(crate::storage::blocklist(), vec![address]);
+}
+
+#[test]
+fn should_reject_runtime_blocked_address_as_destination() {
+    let address = runtime_blocked_address();
+    assert_eq!(
+        validate_address_as_destination(&address.to_string()),
+        Ok(address)
+    );
+
+    crate::storage::block_address(address);
+
+    assert_eq!(
+        validate_address_as_destination(&address.to_string()),
+        Err(AddressValidationError::Blocked(address))
+    );
+}
+
+#[test]
+fn should_not_mint_deposit_from_runtime_blocked_address() {
+    let address = runtime_blocked_address();
+    let event = ReceivedEvent::from(ReceivedErc20Event {
+        transaction_hash: "0xd9335910102c08a9dc16f8cc1a42a0bf8ca93666d11dc3194c6ee1bd30d19686"
+            .parse()
+            .unwrap(),
+        block_number: BlockNumber::new(5539903),
+        log_index: LogIndex::from(0x57_u32),
+        from_address: address,
+        value: Erc20Value::from(5_000_000_u64),
+        principal: "hkroy-sm7vs-yyjs7-ekppe-qqnwx-hm4zf-n7ybs-titsi-k6e3k-ucuiu-uqe"
+            .parse()
+            .unwrap(),
+        erc20_contract_address: "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
+            .parse()
+            .unwrap(),
+        subaccount: None,
+        block_hash: None,
+    });
+    crate::state::STATE.with(|s| *s.borrow_mut() = Some(initial_state()));
+    crate::storage::block_address(address);
+
+    record_received_events("ckERC20", vec![event.clone()], vec![]);
+
+    read_state(|s| {
+        assert!(s.events_to_mint.is_empty());
+        assert!(s.invalid_events.contains_key(&event.source()));
+    });
+}
+
+#[test]
+fn should_not_create_transaction_to_runtime_blocked_address() {
+    let address = runtime_blocked_address();
+    let request = WithdrawalRequest::from(EthWithdrawalRequest {
+        withdrawal_amount: Wei::from(1_100_000_000_000_000_u64),
+        destination: address,
+        ledger_burn_index: Nat::from(131_u64),
+        from: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+        from_subaccount: None,
+        created_at: Some(1_699_527_697_000_000_000),
+    });
+    let create = || {
+        create_transaction(
+            &request,
+            TransactionNonce::ZERO,
+            GasFeeEstimate {
+                base_fee_per_gas: WeiPerGas::from(0x3e8_u64),
+                max_priority_fee_per_gas: WeiPerGas::from(0x3e8_u64),
+            },
+            CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
+            EthereumNetwork::Sepolia.chain_id(),
+        )
+    };
+    assert!(create().is_ok());
+
+    // The destination is blocked after the withdrawal request was accepted.
+    crate::storage::block_address(address);
+
+    assert_eq!(
+        create(),
+        Err(CreateTransactionError::BlockedDestination {
+            withdrawal_id: Nat::from(131_u64),
+            destination: address,
+        })
+    );
+}
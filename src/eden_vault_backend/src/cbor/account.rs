@@ -0,0 +1,63 @@
+use candid::Principal;
+use icrc_ledger_types::icrc1::account::Account;
+use minicbor::bytes::ByteArray;
+use minicbor::decode::{Decoder, Error};
+use minicbor::encode::{Encoder, Write};
+use minicbor::{Decode, Encode};
+
+#[derive(Decode, Encode)]
+struct CborAccount {
+    #[cbor(n(0), with = "crate::cbor::principal")]
+    owner: Principal,
+    #[n(1)]
+    subaccount: Option<ByteArray<32>>,
+}
+
+pub fn decode<Ctx>(d: &mut Decoder<'_>, ctx: &mut Ctx) -> Result<Account, Error> {
+    let CborAccount { owner, subaccount } = CborAccount::decode(d, ctx)?;
+    Ok(Account {
+        owner,
+        subaccount: subaccount.map(|subaccount| *subaccount),
+    })
+}
+
+pub fn encode<Ctx, W: Write>(
+    v: &Account,
+    e: &mut Encoder<W>,
+    ctx: &mut Ctx,
+) -> Result<(), minicbor::encode::Error<W::Error>> {
+    CborAccount {
+        owner: v.owner,
+        subaccount: v.subaccount.map(ByteArray::from),
+    }
+    .encode(e, ctx)
+}
+
+pub mod option {
+    use super::*;
+
+    #[derive(Decode, Encode)]
+    #[cbor(transparent)]
+    struct CborOptAccount(#[cbor(n(0), with = "crate::cbor::account")] pub Account);
+
+    pub fn decode<Ctx>(d: &mut Decoder<'_>, ctx: &mut Ctx) -> Result<Option<Account>, Error> {
+        Ok(Option::<CborOptAccount>::decode(d, ctx)?.map(|a| a.0))
+    }
+
+    pub fn encode<Ctx, W: Write>(
+        v: &Option<Account>,
+        e: &mut Encoder<W>,
+        ctx: &mut Ctx,
+    ) -> Result<(), minicbor::encode::Error<W::Error>> {
+        (*v).map(CborOptAccount).encode(e, ctx)
+    }
+
+    /// Value of a field missing from the encoding, used with `has_nil`.
+    pub fn nil() -> Option<Option<Account>> {
+        Some(None)
+    }
+
+    pub fn is_nil(v: &Option<Account>) -> bool {
+        v.is_none()
+    }
+}
@@ -1,5 +1,6 @@
 use crate::checked_amount::CheckedAmountOf;
 use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
 use ethnum::{u256, U256};
 use minicbor::{Decode, Encode};
 use phantom_newtype::Id;
@@ -51,6 +52,24 @@ struct OptPrincipalContainer {
     pub value: Option<Principal>,
 }
 
+#[derive(Eq, PartialEq, Debug, Decode, Encode)]
+struct VecPrincipalContainer {
+    #[cbor(n(0), with = "crate::cbor::principal::vec")]
+    pub value: Vec<Principal>,
+}
+
+#[derive(Eq, PartialEq, Debug, Decode, Encode)]
+struct AccountContainer {
+    #[cbor(n(0), with = "crate::cbor::account")]
+    pub value: Account,
+}
+
+#[derive(Eq, PartialEq, Debug, Decode, Encode)]
+struct OptAccountContainer {
+    #[cbor(n(0), with = "crate::cbor::account::option", has_nil)]
+    pub value: Option<Account>,
+}
+
 #[derive(Eq, PartialEq, Debug, Decode, Encode)]
 struct U256NewtypeContainer {
     #[cbor(n(0))]
@@ -119,4 +138,37 @@ proptest! {
             value: p.map(|principal| Principal::from_slice(&principal)),
         })?;
     }
+
+    #[test]
+    fn vec_principal_encoding_roundtrip(p in pvec(pvec(any::<u8>(), 0..30), 0..10)) {
+        check_roundtrip(&VecPrincipalContainer {
+            value: p.iter().map(|principal| Principal::from_slice(principal)).collect(),
+        })?;
+    }
+
+    #[test]
+    fn account_encoding_roundtrip(
+        p in pvec(any::<u8>(), 0..30),
+        subaccount in proptest::option::of(any::<[u8; 32]>()),
+    ) {
+        check_roundtrip(&AccountContainer {
+            value: Account {
+                owner: Principal::from_slice(&p),
+                subaccount,
+            },
+        })?;
+    }
+
+    #[test]
+    fn opt_account_encoding_roundtrip(p in proptest::option::of(pvec(any::<u8>(), 0..30))) {
+        check_roundtrip(&OptAccountContainer {
+            value: p.map(|principal| Account::from(Principal::from_slice(&principal))),
+        })?;
+    }
+}
+
+#[test]
+fn should_decode_missing_opt_account_as_none() {
+    let decoded: OptAccountContainer = minicbor::decode(&[0x80]).expect("decoding should succeed");
+    assert_eq!(decoded, OptAccountContainer { value: None });
 }
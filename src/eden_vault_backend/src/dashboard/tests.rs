@@ -100,7 +100,7 @@ fn should_display_supported_erc20_tokens() {
     let usdt = ckusdt();
     let dashboard = {
         let mut state = initial_state();
-        state.ethereum_network = EthereumNetwork::Mainnet;
+        state.evm_network = EthereumNetwork::Mainnet.into();
         state.record_add_ckerc20_token(usdc.clone());
         state.record_add_ckerc20_token(usdt.clone());
         DashboardTemplate::from_state(&state)
@@ -961,7 +961,7 @@ fn initial_state() -> State {
 
 fn initial_state_with_usdc_support() -> State {
     let mut state = initial_state();
-    state.ethereum_network = EthereumNetwork::Mainnet;
+    state.evm_network = EthereumNetwork::Mainnet.into();
     state.record_add_ckerc20_token(ckusdc());
     state
 }
@@ -1005,7 +1005,7 @@ fn received_erc20_event() -> ReceivedErc20Event {
 
 pub fn ckusdc() -> CkErc20Token {
     CkErc20Token {
-        erc20_ethereum_network: EthereumNetwork::Mainnet,
+        erc20_chain_id: EthereumNetwork::Mainnet.chain_id(),
         erc20_contract_address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
             .parse()
             .unwrap(),
@@ -1016,7 +1016,7 @@ pub fn ckusdc() -> CkErc20Token {
 
 pub fn ckusdt() -> CkErc20Token {
     CkErc20Token {
-        erc20_ethereum_network: EthereumNetwork::Mainnet,
+        erc20_chain_id: EthereumNetwork::Mainnet.chain_id(),
         erc20_contract_address: "0xdac17f958d2ee523a2206206994597c13d831ec7"
             .parse()
             .unwrap(),
@@ -1144,7 +1144,7 @@ fn ckerc20_withdrawal_flow(
         nonce,
         gas_fee,
         GasAmount::from(65_000_u32),
-        EthereumNetwork::Sepolia,
+        EthereumNetwork::Sepolia.chain_id(),
     )
     .unwrap();
     let dummy_signature = Eip1559Signature {
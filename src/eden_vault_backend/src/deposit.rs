@@ -1,7 +1,7 @@
-use crate::eth_logs::{report_transaction_error, ReceivedEvent, ReceivedEventError};
-use crate::eth_rpc::{BlockSpec, HttpOutcallError};
-use crate::eth_rpc_client::EthRpcClient;
-use crate::guard::TimerGuard;
+use crate::eth_logs::{report_transaction_error, EventSource, ReceivedEvent, ReceivedEventError};
+use crate::eth_rpc::{BlockSpec, BlockTag, Hash, HttpOutcallError, LogEntry, MAX_PAYLOAD_SIZE};
+use crate::eth_rpc_client::{EthRpcClient, MultiCallError};
+use crate::guard::{TimerGuard, TimerGuardError};
 use crate::logs::{DEBUG, INFO};
 use crate::numeric::BlockNumber;
 use crate::state::{
@@ -12,20 +12,34 @@ use ic_canister_log::log;
 use ic_ethereum_types::Address;
 use scopeguard::ScopeGuard;
 use std::cmp::{min, Ordering};
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::Duration;
 
+pub(crate) const RECEIVED_ETH_EVENT_TOPIC: [u8; 32] =
+    hex!("257e057bb61920d8d0ed2cb7b720ac7f9c513cd1110bc9fa543079154f45f435");
 pub(crate) const RECEIVED_ERC20_EVENT_TOPIC: [u8; 32] =
     hex!("4d69d0bd4287b7f66c548f90154dc81bc98f65a1b362775df5ae171a2ccd262b");
+pub(crate) const RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC: [u8; 32] =
+    hex!("67a41ea6545cbed2c1a471aa538745d7eddb5fef74274cfb21264f86896fb32a");
 
 async fn mint() {
     let _guard = match TimerGuard::new(TaskType::Mint) {
         Ok(guard) => guard,
+        Err(TimerGuardError::Paused(_)) => {
+            log!(DEBUG, "[mint]: skipping, deposits are paused");
+            return;
+        }
         Err(_) => return,
     };
 
     let events = read_state(|s| (s.events_to_mint()));
+    let events = verify_canonical_deposits(events).await;
 
     for event in events {
+        // The deposit may have been processed while verifying the other ones.
+        if !read_state(|s| s.events_to_mint.contains_key(&event.source())) {
+            continue;
+        }
         // Ensure that even if we were to panic in the callback, after having contacted the ledger to mint the tokens,
         // this event will not be processed again.
         let prevent_double_minting_guard = scopeguard::guard(event.clone(), |event| {
@@ -39,23 +53,29 @@ async fn mint() {
             });
         });
         let token_symbol = match &event {
+            ReceivedEvent::Eth(_) => {
+                read_state(|s| s.evm_network().native_token_symbol.clone())
+            }
             ReceivedEvent::Erc20(event) => read_state(|s| {
-                assert!(s.ckerc20_tokens.0 == event.erc20_contract_address, "Failed to mint ckERC20: {event:?} Unsupported ERC20 contract address. (This should have already been filtered out by process_event)");
-                s.ckerc20_tokens.1.to_string()
+                s.ckerc20_tokens.get(&event.erc20_contract_address).unwrap_or_else(|| panic!("Failed to mint ckERC20: {event:?} Unsupported ERC20 contract address. (This should have already been filtered out by process_event)")).to_string()
             }),
         };
 
         let principal = event.principal();
-        let amount = event.raw_value();
 
         mutate_state(|s| {
             process_event(
                 s,
                 match &event {
+                    ReceivedEvent::Eth(event) => EventType::MintedCkEth {
+                        event_source: event.source(),
+                        principal,
+                        amount: event.value,
+                    },
                     ReceivedEvent::Erc20(event) => EventType::MintedCkErc20 {
                         event_source: event.source(),
                         principal,
-                        amount,
+                        amount: event.value,
                     },
                 },
             )
@@ -71,15 +91,108 @@ async fn mint() {
     }
 }
 
+/// Verifies the deposits that have enough confirmations against the canonical chain
+/// and returns the ones that can be minted.
+/// A deposit whose transaction is no longer in the block it was seen in is reorged, and the block now
+/// including its transaction, or the original block if there is none, is scraped again as a skipped block.
+/// The log found there is recorded as a new deposit and verified in turn.
+async fn verify_canonical_deposits(events: Vec<ReceivedEvent>) -> Vec<ReceivedEvent> {
+    let last_observed_block_number = match read_state(|s| s.last_observed_block_number) {
+        Some(block_number) => block_number,
+        None => return vec![],
+    };
+    let network = read_state(|s| s.evm_network().clone());
+    let client = read_state(EthRpcClient::from_state);
+    // Several deposits can be made by the same transaction.
+    let mut canonical_blocks: BTreeMap<Hash, Option<(Hash, BlockNumber)>> = BTreeMap::new();
+    let mut verified = Vec::with_capacity(events.len());
+    for event in events {
+        if !network.is_deposit_confirmed(event.block_number(), last_observed_block_number) {
+            continue;
+        }
+        let block_hash = match event.block_hash() {
+            Some(block_hash) => block_hash,
+            // Deposits accepted before block hashes were recorded cannot be verified.
+            None => {
+                verified.push(event);
+                continue;
+            }
+        };
+        let canonical_block = match canonical_blocks.get(&event.transaction_hash()) {
+            Some(canonical_block) => *canonical_block,
+            None => match client
+                .eth_get_transaction_receipt(event.transaction_hash())
+                .await
+            {
+                Ok(receipt) => {
+                    let canonical_block =
+                        receipt.map(|receipt| (receipt.block_hash, receipt.block_number));
+                    canonical_blocks.insert(event.transaction_hash(), canonical_block);
+                    canonical_block
+                }
+                Err(e) => {
+                    log!(
+                        INFO,
+                        "[verify_canonical_deposits]: failed to verify deposit {}: {e:?}. Will retry later.",
+                        event.source()
+                    );
+                    continue;
+                }
+            },
+        };
+        let canonical_block_hash = canonical_block.map(|(block_hash, _)| block_hash);
+        if canonical_block_hash == Some(block_hash) {
+            verified.push(event);
+            continue;
+        }
+        let requeued_block_number = canonical_block
+            .map(|(_, block_number)| block_number)
+            .unwrap_or(event.block_number());
+        log!(
+            INFO,
+            "[verify_canonical_deposits]: deposit {event:?} is no longer in block {block_hash}, its transaction is in block {canonical_block_hash:?}. Scraping block {requeued_block_number} again.",
+        );
+        mutate_state(|s| {
+            if !s.events_to_mint.contains_key(&event.source()) {
+                return;
+            }
+            process_event(
+                s,
+                EventType::ReorgedDeposit {
+                    event_source: event.source(),
+                    canonical_block_hash,
+                },
+            );
+            let Some(helper_contract_address) = s.erc20_helper_contract_address else {
+                return;
+            };
+            let already_skipped = s
+                .skipped_blocks
+                .get(&helper_contract_address)
+                .is_some_and(|blocks| blocks.contains(&requeued_block_number));
+            if !already_skipped {
+                process_event(
+                    s,
+                    EventType::SkippedBlockForContract {
+                        contract_address: helper_contract_address,
+                        block_number: requeued_block_number,
+                    },
+                );
+            }
+        });
+    }
+    verified
+}
+
 /// Scraps Ethereum logs between `from` and `min(from + MAX_BLOCK_SPREAD, to)` since certain RPC providers
 /// require that the number of blocks queried is no greater than MAX_BLOCK_SPREAD.
 /// Returns the last block number that was scraped (which is `min(from + MAX_BLOCK_SPREAD, to)`) if there
 /// was no error when querying the providers, otherwise returns `None`.
 async fn scrape_logs_range_inclusive<F>(
-    topic: &[u8; 32],
+    topics: &[[u8; 32]],
     topic_name: &str,
     helper_contract_address: Address,
-    token_contract_address: Address,
+    token_contract_addresses: &[Address],
     from: BlockNumber,
     to: BlockNumber,
     max_block_spread: u16,
@@ -103,9 +216,9 @@ where
 
             let (transaction_events, errors) = loop {
                 match crate::eth_logs::last_received_events(
-                    topic,
+                    topics,
                     helper_contract_address,
-                    token_contract_address,
+                    token_contract_addresses,
                     from,
                     last_block_number,
                 )
@@ -122,13 +235,20 @@ where
                         ) {
                             if from == last_block_number {
                                 mutate_state(|s| {
-                                    process_event(
-                                        s,
-                                        EventType::SkippedBlockForContract {
-                                            contract_address: helper_contract_address,
-                                            block_number: last_block_number,
-                                        },
-                                    );
+                                    // Both ETH and ERC-20 logs are emitted by the same contract.
+                                    let already_skipped = s
+                                        .skipped_blocks
+                                        .get(&helper_contract_address)
+                                        .is_some_and(|blocks| blocks.contains(&last_block_number));
+                                    if !already_skipped {
+                                        process_event(
+                                            s,
+                                            EventType::SkippedBlockForContract {
+                                                contract_address: helper_contract_address,
+                                                block_number: last_block_number,
+                                            },
+                                        );
+                                    }
                                 });
                                 update_last_scraped_block_number(last_block_number);
                                 return Some(last_block_number);
@@ -149,50 +269,7 @@ where
                 };
             };
             ic_cdk::println!("XD_transaction_events: {:?}", transaction_events);
-            for event in transaction_events {
-                log!(
-                    INFO,
-                    "Received event {event:?}; will mint {} {topic_name} to {}",
-                    event.value(),
-                    event.principal()
-                );
-                if crate::blocklist::is_blocked(&event.from_address()) {
-                    log!(
-                        INFO,
-                        "Received event from a blocked address: {} for {} {topic_name}",
-                        event.from_address(),
-                        event.value(),
-                    );
-                    mutate_state(|s| {
-                        process_event(
-                            s,
-                            EventType::InvalidDeposit {
-                                event_source: event.source(),
-                                reason: format!("blocked address {}", event.from_address()),
-                            },
-                        )
-                    });
-                } else {
-                    mutate_state(|s| process_event(s, event.into_deposit()));
-                }
-            }
-            if read_state(State::has_events_to_mint) {
-                ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(mint()));
-            }
-            for error in errors {
-                if let ReceivedEventError::InvalidEventSource { source, error } = &error {
-                    mutate_state(|s| {
-                        process_event(
-                            s,
-                            EventType::InvalidDeposit {
-                                event_source: *source,
-                                reason: error.to_string(),
-                            },
-                        )
-                    });
-                }
-                report_transaction_error(error);
-            }
+            record_received_events(topic_name, transaction_events, errors);
             update_last_scraped_block_number(last_block_number);
             Some(last_block_number)
         }
@@ -205,11 +282,60 @@ where
     }
 }
 
+/// Records the deposits found in final blocks, either to be minted or as invalid.
+pub(crate) fn record_received_events(
+    topic_name: &str,
+    events: Vec<ReceivedEvent>,
+    errors: Vec<ReceivedEventError>,
+) {
+    for event in events {
+        log!(
+            INFO,
+            "Received event {event:?}; will mint {} {topic_name} to {}",
+            event.value(),
+            event.principal()
+        );
+        if crate::blocklist::is_blocked(&event.from_address()) {
+            log!(
+                INFO,
+                "Received event from a blocked address: {} for {} {topic_name}",
+                event.from_address(),
+                event.value(),
+            );
+            mutate_state(|s| {
+                process_event(
+                    s,
+                    EventType::InvalidDeposit {
+                        event_source: event.source(),
+                        reason: format!("blocked address {}", event.from_address()),
+                    },
+                )
+            });
+        } else {
+            mutate_state(|s| process_event(s, event.into_deposit()));
+        }
+    }
+    for error in errors {
+        if let ReceivedEventError::InvalidEventSource { source, error } = &error {
+            mutate_state(|s| {
+                process_event(
+                    s,
+                    EventType::InvalidDeposit {
+                        event_source: *source,
+                        reason: error.to_string(),
+                    },
+                )
+            });
+        }
+        report_transaction_error(error);
+    }
+}
+
 async fn scrape_contract_logs<F>(
-    topic: &[u8; 32],
+    topics: &[[u8; 32]],
     topic_name: &str,
     helper_contract_address: Option<Address>,
-    token_contract_address: Address,
+    token_contract_addresses: Vec<Address>,
     last_block_number: BlockNumber,
     mut last_scraped_block_number: BlockNumber,
     max_block_spread: u16,
@@ -233,10 +359,10 @@ async fn scrape_contract_logs<F>(
             .checked_increment()
             .unwrap_or(BlockNumber::MAX);
         last_scraped_block_number = match scrape_logs_range_inclusive(
-            topic,
+            topics,
             topic_name,
             helper_contract_address,
-            token_contract_address,
+            &token_contract_addresses,
             next_block_to_query,
             last_block_number,
             max_block_spread,
@@ -253,12 +379,16 @@ async fn scrape_contract_logs<F>(
 }
 
 async fn scrape_erc20_logs(last_block_number: BlockNumber, max_block_spread: u16) {
-    let token_contract_address = read_state(|s| s.ckerc20_tokens.0);
+    let token_contract_addresses: Vec<_> =
+        read_state(|s| s.ckerc20_tokens.keys().copied().collect());
     scrape_contract_logs(
-        &RECEIVED_ERC20_EVENT_TOPIC,
+        &[
+            RECEIVED_ERC20_EVENT_TOPIC,
+            RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC,
+        ],
         "ERC-20",
         read_state(|s| s.erc20_helper_contract_address),
-        token_contract_address,
+        token_contract_addresses,
         last_block_number,
         read_state(|s| s.last_erc20_scraped_block_number),
         max_block_spread,
@@ -269,9 +399,28 @@ async fn scrape_erc20_logs(last_block_number: BlockNumber, max_block_spread: u16
     .await
 }
 
+/// Native coin deposits are emitted by the same helper contract as the ERC-20 ones.
+async fn scrape_eth_logs(last_block_number: BlockNumber, max_block_spread: u16) {
+    scrape_contract_logs(
+        &[RECEIVED_ETH_EVENT_TOPIC],
+        "ETH",
+        read_state(|s| s.erc20_helper_contract_address),
+        vec![],
+        last_block_number,
+        read_state(|s| s.last_scraped_block_number),
+        max_block_spread,
+        &|last_block_number| mutate_state(|s| s.last_scraped_block_number = last_block_number),
+    )
+    .await
+}
+
 pub async fn scrape_logs() {
     let _guard = match TimerGuard::new(TaskType::ScrapEthLogs) {
         Ok(guard) => guard,
+        Err(TimerGuardError::Paused(_)) => {
+            log!(DEBUG, "[scrape_logs]: skipping, deposits are paused");
+            return;
+        }
         Err(_) => return,
     };
     let last_block_number = match update_last_observed_block_number().await {
@@ -285,7 +434,479 @@ pub async fn scrape_logs() {
         }
     };
     let max_block_spread = read_state(|s| s.max_block_spread_for_logs_scraping());
+    scrape_eth_logs(last_block_number, max_block_spread).await;
     scrape_erc20_logs(last_block_number, max_block_spread).await;
+    mutate_state(|s| {
+        for event_source in s.stale_pending_deposits() {
+            s.record_dropped_pending_deposit(event_source);
+        }
+    });
+    // Deposits waiting for confirmations are retried until they are minted or reorged.
+    if read_state(State::has_events_to_mint) {
+        ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(mint()));
+    }
+}
+
+/// Scrapes the logs of the blocks that are not final yet, so that the deposits they contain are shown
+/// as pending credits until [`scrape_logs`] accepts them once final.
+pub async fn scrape_pending_deposits() {
+    if !read_state(State::optimistic_deposits_enabled) {
+        return;
+    }
+    let _guard = match TimerGuard::new(TaskType::ScrapPendingDeposits) {
+        Ok(guard) => guard,
+        Err(TimerGuardError::Paused(_)) => {
+            log!(DEBUG, "[scrape_pending_deposits]: skipping, deposits are paused");
+            return;
+        }
+        Err(_) => return,
+    };
+    let helper_contract_address = match read_state(|s| s.erc20_helper_contract_address) {
+        Some(address) => address,
+        None => return,
+    };
+    let latest_block_number = match read_state(EthRpcClient::from_state)
+        .eth_get_block_by_number(BlockSpec::Tag(BlockTag::Latest))
+        .await
+    {
+        Ok(block) => block.number,
+        Err(e) => {
+            log!(INFO, "[scrape_pending_deposits]: failed to get the latest block number: {e:?}");
+            return;
+        }
+    };
+    if !read_state(|s| s.should_scrape_pending_deposits(latest_block_number)) {
+        return;
+    }
+    let max_block_spread = read_state(|s| s.max_block_spread_for_logs_scraping());
+    scrape_pending_logs(
+        &[RECEIVED_ETH_EVENT_TOPIC],
+        helper_contract_address,
+        &[],
+        read_state(|s| s.last_scraped_block_number),
+        latest_block_number,
+        max_block_spread,
+        |event| matches!(event, ReceivedEvent::Eth(_)),
+    )
+    .await;
+    let token_contract_addresses: Vec<_> =
+        read_state(|s| s.ckerc20_tokens.keys().copied().collect());
+    scrape_pending_logs(
+        &[
+            RECEIVED_ERC20_EVENT_TOPIC,
+            RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC,
+        ],
+        helper_contract_address,
+        &token_contract_addresses,
+        read_state(|s| s.last_erc20_scraped_block_number),
+        latest_block_number,
+        max_block_spread,
+        |event| matches!(event, ReceivedEvent::Erc20(_)),
+    )
+    .await;
+    mutate_state(|s| s.last_pending_deposits_head = Some(latest_block_number));
+}
+
+/// Records the deposits found in the blocks following `last_scraped_block_number` as pending,
+/// and drops the pending deposits of these blocks whose log disappeared.
+/// Pending deposits are only kept in memory, they are not recorded in the event log.
+async fn scrape_pending_logs<F>(
+    topics: &[[u8; 32]],
+    helper_contract_address: Address,
+    token_contract_addresses: &[Address],
+    last_scraped_block_number: BlockNumber,
+    latest_block_number: BlockNumber,
+    max_block_spread: u16,
+    is_scraped: F,
+) where
+    F: Fn(&ReceivedEvent) -> bool,
+{
+    let from = match last_scraped_block_number.checked_increment() {
+        Some(from) if from <= latest_block_number => from,
+        _ => return,
+    };
+    let to = min(
+        from.checked_add(BlockNumber::from(max_block_spread))
+            .unwrap_or(BlockNumber::MAX),
+        latest_block_number,
+    );
+    // Invalid deposits are only recorded once their block is final.
+    let events = match crate::eth_logs::last_received_events(
+        topics,
+        helper_contract_address,
+        token_contract_addresses,
+        from,
+        to,
+    )
+    .await
+    {
+        Ok((events, _errors)) => events,
+        Err(e) => {
+            log!(
+                DEBUG,
+                "[scrape_pending_logs]: failed to get logs from block {from} to block {to}: {e:?}"
+            );
+            return;
+        }
+    };
+    let found: BTreeSet<EventSource> = events.iter().map(ReceivedEvent::source).collect();
+    mutate_state(|s| {
+        for event in events {
+            if s.is_known_deposit(&event.source())
+                || crate::blocklist::is_blocked(&event.from_address())
+            {
+                continue;
+            }
+            log!(DEBUG, "[scrape_pending_logs]: pending deposit {event:?}");
+            s.record_pending_deposit(&event);
+        }
+        let disappeared: Vec<_> = s
+            .pending_deposits
+            .values()
+            .filter(|event| {
+                is_scraped(event)
+                    && from <= event.block_number()
+                    && event.block_number() <= to
+                    && !found.contains(&event.source())
+            })
+            .map(ReceivedEvent::source)
+            .collect();
+        for event_source in disappeared {
+            log!(DEBUG, "[scrape_pending_logs]: dropping pending deposit {event_source}");
+            s.record_dropped_pending_deposit(event_source);
+        }
+    });
+}
+
+/// Records the deposits, and the invalid ones, that were not recorded yet,
+/// e.g. when scraping blocks for the second time, and returns their sources.
+fn record_new_received_events(
+    topic_name: &str,
+    events: Vec<ReceivedEvent>,
+    errors: Vec<ReceivedEventError>,
+) -> Vec<EventSource> {
+    let (events, errors): (Vec<_>, Vec<_>) = read_state(|s| {
+        let events = events
+            .into_iter()
+            .filter(|event| s.is_new_deposit(&event.source()))
+            .collect();
+        let errors = errors
+            .into_iter()
+            .filter(|error| match error {
+                ReceivedEventError::InvalidEventSource { source, .. } => s.is_new_deposit(source),
+                ReceivedEventError::PendingLogEntry => true,
+            })
+            .collect();
+        (events, errors)
+    });
+    let sources = events
+        .iter()
+        .map(ReceivedEvent::source)
+        .chain(errors.iter().filter_map(|error| match error {
+            ReceivedEventError::InvalidEventSource { source, .. } => Some(*source),
+            ReceivedEventError::PendingLogEntry => None,
+        }))
+        .collect();
+    record_received_events(topic_name, events, errors);
+    sources
+}
+
+/// Retries the blocks that were skipped because their logs did not fit in a single response.
+/// A skipped block is only retried once both the native coin and ERC-20 deposits were scraped past it,
+/// and it is removed from the skipped blocks once all of its deposits are recorded.
+pub async fn retry_skipped_blocks() {
+    let _guard = match TimerGuard::new(TaskType::RetrySkippedBlocks) {
+        Ok(guard) => guard,
+        Err(TimerGuardError::Paused(_)) => {
+            log!(DEBUG, "[retry_skipped_blocks]: skipping, deposits are paused");
+            return;
+        }
+        Err(_) => return,
+    };
+    let (skipped_blocks, token_contract_addresses) = read_state(|s| {
+        let last_scraped_block_number =
+            min(s.last_scraped_block_number, s.last_erc20_scraped_block_number);
+        let skipped_blocks: Vec<(Address, BlockNumber)> = s
+            .skipped_blocks
+            .iter()
+            .flat_map(|(contract_address, blocks)| {
+                blocks
+                    .iter()
+                    .filter(|block_number| **block_number <= last_scraped_block_number)
+                    .map(|block_number| (*contract_address, *block_number))
+            })
+            .collect();
+        let token_contract_addresses: Vec<Address> = s.ckerc20_tokens.keys().copied().collect();
+        (skipped_blocks, token_contract_addresses)
+    });
+    for (contract_address, block_number) in skipped_blocks {
+        match scrape_skipped_block(contract_address, &token_contract_addresses, block_number).await
+        {
+            Ok(()) => {
+                log!(
+                    INFO,
+                    "[retry_skipped_blocks]: scraped skipped block {block_number} of contract {contract_address}"
+                );
+                mutate_state(|s| {
+                    process_event(
+                        s,
+                        EventType::RetriedSkippedBlock {
+                            contract_address,
+                            block_number,
+                        },
+                    )
+                });
+            }
+            Err(e) => log!(
+                INFO,
+                "[retry_skipped_blocks]: failed to scrape skipped block {block_number} of contract {contract_address}: {e}. Will retry later."
+            ),
+        }
+    }
+}
+
+/// Scrapes the logs of a single block, querying each event signature and ERC-20 token separately
+/// so that every response is smaller than the one that did not fit, and allowing the largest response.
+/// When a query still fails, the logs are taken from the receipts of the block instead.
+/// Nothing is recorded unless all the queries succeed.
+async fn scrape_skipped_block(
+    contract_address: Address,
+    token_contract_addresses: &[Address],
+    block_number: BlockNumber,
+) -> Result<(), String> {
+    let mut queries = vec![("ETH", RECEIVED_ETH_EVENT_TOPIC, vec![])];
+    for topic in [
+        RECEIVED_ERC20_EVENT_TOPIC,
+        RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC,
+    ] {
+        for token in token_contract_addresses {
+            queries.push(("ERC-20", topic, vec![*token]));
+        }
+    }
+    let mut received = Vec::with_capacity(queries.len());
+    let mut block_logs: Option<Vec<LogEntry>> = None;
+    for (topic_name, topic, tokens) in queries {
+        let (events, errors) = match crate::eth_logs::last_received_events_with_response_size(
+            &[topic],
+            contract_address,
+            &tokens,
+            block_number,
+            block_number,
+            MAX_PAYLOAD_SIZE,
+        )
+        .await
+        {
+            Ok(received) => received,
+            Err(e) => {
+                log!(
+                    INFO,
+                    "[scrape_skipped_block]: failed to get {topic_name} logs of block {block_number}: {e:?}. Falling back to the block receipts."
+                );
+                let logs = match &block_logs {
+                    Some(logs) => logs.clone(),
+                    None => {
+                        let logs = get_block_logs(block_number).await?;
+                        block_logs = Some(logs.clone());
+                        logs
+                    }
+                };
+                crate::eth_logs::parse_logs(crate::eth_logs::filter_logs(
+                    logs,
+                    &[topic],
+                    contract_address,
+                    &tokens,
+                ))
+            }
+        };
+        received.push((topic_name, events, errors));
+    }
+    for (topic_name, events, errors) in received {
+        record_new_received_events(topic_name, events, errors);
+    }
+    Ok(())
+}
+
+/// Logs of every transaction of the block, taken from its receipts.
+async fn get_block_logs(block_number: BlockNumber) -> Result<Vec<LogEntry>, String> {
+    let receipts = read_state(EthRpcClient::from_state)
+        .eth_get_block_receipts(block_number)
+        .await
+        .map_err(|e| format!("failed to get the receipts of block {block_number}: {e:?}"))?;
+    Ok(receipts
+        .into_iter()
+        .flat_map(|receipt| receipt.logs)
+        .collect())
+}
+
+/// Scrapes again the deposits of the helper contract in the blocks of the rescrape requested by the admin,
+/// recording the ones that were missed. The progress is reported in [`State::rescrape`].
+pub async fn rescrape_logs() {
+    let _guard = match TimerGuard::new(TaskType::Rescrape) {
+        Ok(guard) => guard,
+        Err(TimerGuardError::Paused(_)) => {
+            log!(DEBUG, "[rescrape_logs]: skipping, deposits are paused");
+            return;
+        }
+        Err(_) => return,
+    };
+    let (from, to) = match read_state(|s| s.rescrape.clone()) {
+        Some(rescrape) if rescrape.is_running() => (rescrape.from, rescrape.to),
+        _ => return,
+    };
+    // Ensure that the rescrape ends even if we were to panic in a callback,
+    // so that the admin can request another one. The error is cleared once all the blocks are rescraped.
+    let mut outcome = scopeguard::guard(
+        Some("rescrape interrupted by a panic".to_string()),
+        |error| {
+            mutate_state(|s| {
+                if let Some(rescrape) = s.rescrape.as_mut() {
+                    rescrape.error = error;
+                    rescrape.done = true;
+                }
+            })
+        },
+    );
+    let helper_contract_address = match read_state(|s| s.erc20_helper_contract_address) {
+        Some(address) => address,
+        None => {
+            *outcome = Some("no helper contract address".to_string());
+            return;
+        }
+    };
+    let token_contract_addresses: Vec<Address> =
+        read_state(|s| s.ckerc20_tokens.keys().copied().collect());
+    let max_block_spread = read_state(|s| s.max_block_spread_for_logs_scraping());
+
+    let mut start = from;
+    while start <= to {
+        let mut end = min(
+            start
+                .checked_add(BlockNumber::from(max_block_spread))
+                .unwrap_or(BlockNumber::MAX),
+            to,
+        );
+        let received = loop {
+            match fetch_received_events(
+                helper_contract_address,
+                &token_contract_addresses,
+                start,
+                end,
+            )
+            .await
+            {
+                Ok(received) => break Some(received),
+                Err(e)
+                    if e.has_http_outcall_error_matching(
+                        HttpOutcallError::is_response_too_large,
+                    ) =>
+                {
+                    if start == end {
+                        break None;
+                    }
+                    end = start
+                        .checked_add(
+                            end.checked_sub(start)
+                                .expect("end is greater or equal than start")
+                                .div_by_two(),
+                        )
+                        .expect("must be less than end");
+                }
+                Err(e) => {
+                    let error = format!("failed to get logs from block {start} to block {end}: {e:?}");
+                    log!(INFO, "[rescrape_logs]: {error}");
+                    *outcome = Some(error);
+                    return;
+                }
+            }
+        };
+        match received {
+            Some(received) => {
+                for (topic_name, events, errors) in received {
+                    let sources = record_new_received_events(topic_name, events, errors);
+                    mutate_state(|s| {
+                        let (invalid, valid): (Vec<_>, Vec<_>) = sources
+                            .into_iter()
+                            .partition(|source| s.invalid_events.contains_key(source));
+                        if let Some(rescrape) = s.rescrape.as_mut() {
+                            rescrape.new_deposits.extend(valid);
+                            rescrape.new_invalid_deposits.extend(invalid);
+                        }
+                    });
+                }
+            }
+            None => {
+                log!(INFO, "[rescrape_logs]: too many logs in block {start}, skipping it");
+                mutate_state(|s| {
+                    let already_skipped = s
+                        .skipped_blocks
+                        .get(&helper_contract_address)
+                        .is_some_and(|blocks| blocks.contains(&start));
+                    if !already_skipped {
+                        process_event(
+                            s,
+                            EventType::SkippedBlockForContract {
+                                contract_address: helper_contract_address,
+                                block_number: start,
+                            },
+                        );
+                    }
+                    if let Some(rescrape) = s.rescrape.as_mut() {
+                        rescrape.skipped_blocks.push(start);
+                    }
+                });
+            }
+        }
+        mutate_state(|s| {
+            if let Some(rescrape) = s.rescrape.as_mut() {
+                rescrape.scraped_up_to = Some(end);
+            }
+        });
+        start = match end.checked_increment() {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    *outcome = None;
+    drop(outcome);
+    if read_state(State::has_events_to_mint) {
+        ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(mint()));
+    }
+}
+
+type ReceivedEvents = (&'static str, Vec<ReceivedEvent>, Vec<ReceivedEventError>);
+
+/// Fetches the native coin and ERC-20 deposits of the blocks in `[from, to]`.
+async fn fetch_received_events(
+    helper_contract_address: Address,
+    token_contract_addresses: &[Address],
+    from: BlockNumber,
+    to: BlockNumber,
+) -> Result<Vec<ReceivedEvents>, MultiCallError<Vec<LogEntry>>> {
+    let mut received = vec![];
+    let (events, errors) = crate::eth_logs::last_received_events(
+        &[RECEIVED_ETH_EVENT_TOPIC],
+        helper_contract_address,
+        &[],
+        from,
+        to,
+    )
+    .await?;
+    received.push(("ETH", events, errors));
+    if !token_contract_addresses.is_empty() {
+        let (events, errors) = crate::eth_logs::last_received_events(
+            &[
+                RECEIVED_ERC20_EVENT_TOPIC,
+                RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC,
+            ],
+            helper_contract_address,
+            token_contract_addresses,
+            from,
+            to,
+        )
+        .await?;
+        received.push(("ERC-20", events, errors));
+    }
+    Ok(received)
 }
 
 pub async fn update_last_observed_block_number() -> Option<BlockNumber> {
@@ -1,7 +1,11 @@
 use crate::eth_rpc_client::responses::TransactionReceipt;
+use crate::lifecycle::network::{RpcEndpoint, RpcHeader};
+use crate::lifecycle::upgrade::UpgradeArg;
+use crate::state::proposals::{ProposalAction, ProposalPolicy};
 use crate::state::{transactions, transactions::EthWithdrawalRequest};
 use crate::tx::{SignedEip1559TransactionRequest, TransactionPrice};
 use candid::{CandidType, Deserialize, Nat, Principal};
+use ic_cdk::api::management_canister::http_request::HttpHeader;
 use icrc_ledger_types::icrc1::account::Account;
 use minicbor::{Decode, Encode};
 use std::fmt::{Display, Formatter};
@@ -129,6 +133,67 @@ pub enum CandidBlockTag {
     Finalized,
 }
 
+/// Decision of the admin on a quarantined deposit or reimbursement.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
+#[cbor(index_only)]
+pub enum QuarantineResolution {
+    /// Process it again, as if it had never been quarantined.
+    #[cbor(n(0))]
+    Release,
+    /// It was already credited, so the minter must never credit it.
+    #[cbor(n(1))]
+    MarkProcessed,
+    /// It must never be credited.
+    #[cbor(n(2))]
+    Reject,
+}
+
+/// Flow of funds that the admin can pause during an incident.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, CandidType, Decode, Deserialize, Encode,
+)]
+#[cbor(index_only)]
+pub enum VaultFlow {
+    /// Scraping deposits and minting them.
+    #[cbor(n(0))]
+    Deposits,
+    /// Accepting withdrawal requests.
+    #[cbor(n(1))]
+    Withdrawals,
+    /// Transfers between accounts of the vault.
+    #[cbor(n(2))]
+    Transfers,
+    /// Creating, signing and sending the transactions of accepted withdrawals.
+    #[cbor(n(3))]
+    Transactions,
+}
+
+/// Role granted by the admin over the admin endpoints.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug, CandidType, Decode, Deserialize, Encode,
+)]
+#[cbor(index_only)]
+pub enum Role {
+    /// Every permission, except handing over the admin.
+    #[cbor(n(0))]
+    Owner,
+    /// Runs the vault: tokens, RPC providers, scraping and pausing.
+    #[cbor(n(1))]
+    Operator,
+    /// Handles incidents: pausing, resolving quarantined funds and blocking addresses.
+    #[cbor(n(2))]
+    Compliance,
+    /// Read-only access to the quarantined funds.
+    #[cbor(n(3))]
+    Auditor,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RoleHolder {
+    pub principal: Principal,
+    pub roles: Vec<Role>,
+}
+
 impl From<EthWithdrawalRequest> for RetrieveEthRequest {
     fn from(value: EthWithdrawalRequest) -> Self {
         Self {
@@ -156,6 +221,8 @@ pub enum TxFinalizedStatus {
     Reimbursed {
         transaction_hash: String,
         reimbursed_amount: Nat,
+        /// Block of the ICRC-3 log recording an ERC-20 reimbursement.
+        /// Native coin reimbursements are not in the log: the block is the one appended next.
         reimbursed_in_block: Nat,
     },
 }
@@ -192,6 +259,23 @@ impl Display for RetrieveEthStatus {
 pub struct WithdrawalArg {
     pub amount: Nat,
     pub recipient: String,
+    pub from_subaccount: Option<[u8; 32]>,
+}
+
+#[derive(CandidType, Deserialize)]
+pub struct FundGasReserveArg {
+    pub amount: Nat,
+    pub from_subaccount: Option<[u8; 32]>,
+}
+
+/// Native coins set aside to pay the transaction fees of ERC-20 withdrawals.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GasReserve {
+    pub total: Nat,
+    /// Part of the total not committed to ERC-20 withdrawals that are not finalized yet.
+    pub available: Nat,
+    /// Fees paid by all finalized ERC-20 withdrawal transactions.
+    pub total_erc20_tx_fees: Nat,
 }
 
 #[derive(PartialEq, Debug, CandidType, Deserialize)]
@@ -201,6 +285,7 @@ pub enum WithdrawalError {
     InsufficientAllowance { allowance: Nat },
     RecipientAddressBlocked { address: String },
     TemporarilyUnavailable(String),
+    Paused,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
@@ -253,6 +338,286 @@ pub struct AddCkErc20Token {
     pub ckerc20_ledger_id: Principal,
 }
 
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RpcProviderArg {
+    pub url: String,
+    /// Headers sent with every request, e.g. an API key.
+    pub headers: Option<Vec<HttpHeader>>,
+}
+
+/// Replaces the RPC providers queried by the minter, in order.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct UpdateRpcProvidersArg {
+    pub providers: Vec<RpcProviderArg>,
+    pub consensus_threshold: Option<u8>,
+}
+
+impl From<RpcProviderArg> for RpcEndpoint {
+    fn from(provider: RpcProviderArg) -> Self {
+        Self {
+            url: provider.url,
+            headers: provider
+                .headers
+                .unwrap_or_default()
+                .into_iter()
+                .map(|header| RpcHeader {
+                    name: header.name,
+                    value: header.value,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Admins approving proposals, of which `threshold` must approve a proposal,
+/// which can then be executed `delay_seconds` after its creation.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ProposalPolicyArg {
+    pub approvers: Vec<Principal>,
+    pub threshold: u8,
+    pub delay_seconds: u64,
+}
+
+impl From<ProposalPolicyArg> for ProposalPolicy {
+    fn from(arg: ProposalPolicyArg) -> Self {
+        Self {
+            approvers: arg.approvers,
+            threshold: arg.threshold,
+            delay_seconds: arg.delay_seconds,
+        }
+    }
+}
+
+impl From<ProposalPolicy> for ProposalPolicyArg {
+    fn from(policy: ProposalPolicy) -> Self {
+        Self {
+            approvers: policy.approvers,
+            threshold: policy.threshold,
+            delay_seconds: policy.delay_seconds,
+        }
+    }
+}
+
+/// Change of sensitive configuration submitted for approval.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum ProposalActionArg {
+    /// Fees, token and helper contract addresses and limits, as set at upgrade time.
+    UpdateConfig(UpgradeArg),
+    UpdateRpcProviders(UpdateRpcProvidersArg),
+    SetAdmin(Principal),
+    /// Replaces the admins approving proposals.
+    UpdatePolicy(ProposalPolicyArg),
+    GrantRole { principal: Principal, role: Role },
+    RevokeRole { principal: Principal, role: Role },
+    AddErc20Token(AddCkErc20Token),
+}
+
+impl TryFrom<ProposalActionArg> for ProposalAction {
+    type Error = String;
+
+    fn try_from(arg: ProposalActionArg) -> Result<Self, Self::Error> {
+        Ok(match arg {
+            ProposalActionArg::UpdateConfig(upgrade_arg) => ProposalAction::UpdateConfig(upgrade_arg),
+            ProposalActionArg::UpdateRpcProviders(arg) => ProposalAction::UpdateRpcProviders {
+                providers: arg.providers.into_iter().map(RpcEndpoint::from).collect(),
+                consensus_threshold: arg.consensus_threshold,
+            },
+            ProposalActionArg::SetAdmin(admin) => ProposalAction::SetAdmin(admin),
+            ProposalActionArg::UpdatePolicy(policy) => ProposalAction::UpdatePolicy(policy.into()),
+            ProposalActionArg::GrantRole { principal, role } => {
+                ProposalAction::GrantRole { principal, role }
+            }
+            ProposalActionArg::RevokeRole { principal, role } => {
+                ProposalAction::RevokeRole { principal, role }
+            }
+            ProposalActionArg::AddErc20Token(token) => {
+                ProposalAction::AddErc20Token(crate::erc20::CkErc20Token::try_from(token)?)
+            }
+        })
+    }
+}
+
+/// Proposed change, without the values of the headers of the RPC providers.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum ProposedAction {
+    UpdateConfig(UpgradeArg),
+    UpdateRpcProviders {
+        providers: Vec<events::RpcProvider>,
+        consensus_threshold: Option<u8>,
+    },
+    SetAdmin(Principal),
+    UpdatePolicy(ProposalPolicyArg),
+    GrantRole { principal: Principal, role: Role },
+    RevokeRole { principal: Principal, role: Role },
+    AddErc20Token(CkErc20Token),
+}
+
+impl From<ProposalAction> for ProposedAction {
+    fn from(action: ProposalAction) -> Self {
+        match action {
+            ProposalAction::UpdateConfig(upgrade_arg) => ProposedAction::UpdateConfig(upgrade_arg),
+            ProposalAction::UpdateRpcProviders {
+                providers,
+                consensus_threshold,
+            } => ProposedAction::UpdateRpcProviders {
+                providers: providers.into_iter().map(events::RpcProvider::from).collect(),
+                consensus_threshold,
+            },
+            ProposalAction::SetAdmin(admin) => ProposedAction::SetAdmin(admin),
+            ProposalAction::UpdatePolicy(policy) => ProposedAction::UpdatePolicy(policy.into()),
+            ProposalAction::GrantRole { principal, role } => {
+                ProposedAction::GrantRole { principal, role }
+            }
+            ProposalAction::RevokeRole { principal, role } => {
+                ProposedAction::RevokeRole { principal, role }
+            }
+            ProposalAction::AddErc20Token(token) => ProposedAction::AddErc20Token(token.into()),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct PendingProposal {
+    pub id: u64,
+    pub proposer: Principal,
+    pub action: ProposedAction,
+    /// Approvers who approved the proposal, including its proposer.
+    pub approvals: Vec<Principal>,
+    pub approvals_required: u8,
+    /// Approvers who approved cancelling the proposal, which takes as many approvals as executing it.
+    pub cancellation_approvals: Vec<Principal>,
+    /// Time from which the proposal can be executed, in nanoseconds since the UNIX epoch.
+    pub executable_at: u64,
+}
+
+/// Health of a JSON-RPC provider since the last upgrade.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ProviderHealthInfo {
+    /// URL of the provider, without its headers.
+    pub provider: String,
+    /// Demoted providers are only queried when there are not enough healthy ones.
+    pub healthy: bool,
+    pub successes: u64,
+    pub failures: u64,
+    pub disagreements: u64,
+    pub consecutive_failures: u32,
+    pub average_latency_ns: Option<u64>,
+    pub last_error: Option<String>,
+    pub demoted_until: Option<u64>,
+}
+
+/// Number of providers that must agree on the result of a JSON-RPC method.
+/// Every provider that is not demoted is queried.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RpcMethodThreshold {
+    pub method: String,
+    pub threshold: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ProviderHealthReport {
+    pub providers: Vec<ProviderHealthInfo>,
+    pub thresholds: Vec<RpcMethodThreshold>,
+}
+
+/// Deposit quarantined while being minted, waiting for the admin to resolve it.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct QuarantinedDepositInfo {
+    pub event_source: events::EventSource,
+    /// Unknown for deposits quarantined before the vault kept them.
+    pub deposit: Option<QuarantinedDepositDetails>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct QuarantinedDepositDetails {
+    pub block_number: Nat,
+    pub from_address: String,
+    pub value: Nat,
+    pub principal: Principal,
+    pub subaccount: Option<[u8; 32]>,
+    /// Not set for native coin deposits.
+    pub erc20_contract_address: Option<String>,
+}
+
+/// Reimbursement quarantined while being credited, waiting for the admin to resolve it.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct QuarantinedReimbursementInfo {
+    pub index: events::ReimbursementIndex,
+    /// Unknown for reimbursements quarantined before the vault kept them.
+    pub reimbursement: Option<QuarantinedReimbursementDetails>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct QuarantinedReimbursementDetails {
+    pub to: Principal,
+    pub to_subaccount: Option<[u8; 32]>,
+    pub reimbursed_amount: Nat,
+    /// Not set for native coin reimbursements.
+    pub erc20_contract_address: Option<String>,
+    /// Hash of the failed withdrawal transaction.
+    pub transaction_hash: Option<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ResolveQuarantinedDepositArg {
+    pub event_source: events::EventSource,
+    pub resolution: QuarantineResolution,
+    pub reason: String,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ResolveQuarantinedReimbursementArg {
+    pub index: events::ReimbursementIndex,
+    pub resolution: QuarantineResolution,
+    pub reason: String,
+}
+
+/// Blocks of the helper contract to scrape again, both bounds included.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RescrapeBlocksArg {
+    pub from: Nat,
+    pub to: Nat,
+}
+
+/// Progress of the last rescrape requested by the admin.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RescrapeStatus {
+    pub from: Nat,
+    pub to: Nat,
+    pub scraped_up_to: Option<Nat>,
+    /// Deposits that were missed by the previous scrapes, accepted for minting.
+    pub new_deposits: Vec<events::EventSource>,
+    pub new_invalid_deposits: Vec<events::EventSource>,
+    /// Blocks whose logs did not fit in a single response, retried with the other skipped blocks.
+    pub skipped_blocks: Vec<Nat>,
+    pub error: Option<String>,
+    pub done: bool,
+}
+
+/// Balance of an account, distinguishing the funds reserved by pending flows.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct Erc20BalanceDetails {
+    pub total: Nat,
+    /// Part of the total that is not held by a pending withdrawal.
+    pub available: Nat,
+    /// Deposits not included in the total yet, because they are not final or not minted yet.
+    pub pending: Nat,
+}
+
+/// Native coin balance of an account, with the deposits that are not spendable yet.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct EthBalanceDetails {
+    pub total: Nat,
+    /// Deposits not included in the total yet, because they are not final or not minted yet.
+    pub pending: Nat,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct StandardRecord {
+    pub name: String,
+    pub url: String,
+}
+
 pub mod events {
     use crate::lifecycle::init::InitArg;
     use crate::lifecycle::upgrade::UpgradeArg;
@@ -285,6 +650,26 @@ pub mod events {
         pub log_index: Nat,
     }
 
+    /// RPC provider without the values of its headers, which may contain credentials.
+    #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+    pub struct RpcProvider {
+        pub url: String,
+        pub header_names: Vec<String>,
+    }
+
+    impl From<crate::lifecycle::network::RpcEndpoint> for RpcProvider {
+        fn from(endpoint: crate::lifecycle::network::RpcEndpoint) -> Self {
+            Self {
+                url: endpoint.url,
+                header_names: endpoint
+                    .headers
+                    .into_iter()
+                    .map(|header| header.name)
+                    .collect(),
+            }
+        }
+    }
+
     #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
     pub enum ReimbursementIndex {
         CkEth {
@@ -334,6 +719,15 @@ pub mod events {
     pub enum EventPayload {
         Init(InitArg),
         Upgrade(UpgradeArg),
+        AcceptedDeposit {
+            transaction_hash: String,
+            block_number: Nat,
+            log_index: Nat,
+            from_address: String,
+            value: Nat,
+            principal: Principal,
+            block_hash: Option<String>,
+        },
         AcceptedErc20Deposit {
             transaction_hash: String,
             block_number: Nat,
@@ -342,6 +736,8 @@ pub mod events {
             value: Nat,
             principal: Principal,
             erc20_contract_address: String,
+            subaccount: Option<[u8; 32]>,
+            block_hash: Option<String>,
         },
         InvalidDeposit {
             event_source: EventSource,
@@ -374,6 +770,14 @@ pub mod events {
             contract_address: Option<String>,
             block_number: Nat,
         },
+        AcceptedEthWithdrawalRequest {
+            withdrawal_amount: Nat,
+            destination: String,
+            withdrawal_id: Nat,
+            from: Principal,
+            from_subaccount: Option<[u8; 32]>,
+            created_at: Option<u64>,
+        },
         AcceptedErc20WithdrawalRequest {
             max_transaction_fee: Nat,
             withdrawal_amount: Nat,
@@ -381,16 +785,121 @@ pub mod events {
             from: Principal,
             from_subaccount: Option<[u8; 32]>,
             created_at: u64,
-            withdrawal_id: Nat
+            withdrawal_id: Nat,
+            withdrawal_fee: Option<Nat>,
+            erc20_contract_address: Option<String>,
+            fee_recipient: Option<Principal>,
+            fee_recipient_subaccount: Option<[u8; 32]>,
+        },
+        AddedCkErc20Token {
+            chain_id: Nat,
+            address: String,
+            erc20_token_symbol: String,
+            erc20_ledger_id: Principal,
         },
         MintedCkErc20 {
             event_source: EventSource,
             principal: Principal,
             amount: Nat,
         },
+        MintedCkEth {
+            event_source: EventSource,
+            principal: Principal,
+            amount: Nat,
+        },
         QuarantinedDeposit {
             event_source: EventSource,
         },
+        ReorgedDeposit {
+            event_source: EventSource,
+            canonical_block_hash: Option<String>,
+        },
+        RetriedSkippedBlock {
+            contract_address: String,
+            block_number: Nat,
+        },
+        UpdatedPause {
+            flow: super::VaultFlow,
+            paused: bool,
+        },
+        GrantedRole {
+            principal: Principal,
+            role: super::Role,
+        },
+        RevokedRole {
+            principal: Principal,
+            role: super::Role,
+        },
+        UpdatedAdmin {
+            admin: Principal,
+        },
+        CreatedProposal {
+            id: u64,
+            proposer: Principal,
+            action: super::ProposedAction,
+            executable_at: u64,
+        },
+        ApprovedProposal {
+            id: u64,
+            approver: Principal,
+        },
+        ApprovedProposalCancellation {
+            id: u64,
+            approver: Principal,
+        },
+        ExecutedProposal {
+            id: u64,
+            executed_by: Principal,
+        },
+        CancelledProposal {
+            id: u64,
+            cancelled_by: Principal,
+        },
+        BlockedAddresses {
+            addresses: Vec<String>,
+            reason: String,
+        },
+        UnblockedAddresses {
+            addresses: Vec<String>,
+            reason: String,
+        },
+        FundedGasReserve {
+            from: Principal,
+            from_subaccount: Option<[u8; 32]>,
+            amount: Nat,
+        },
+        RejectedWithdrawalRequest {
+            withdrawal_id: Nat,
+            destination: String,
+        },
+        ResolvedQuarantinedDeposit {
+            event_source: EventSource,
+            resolution: super::QuarantineResolution,
+            reason: String,
+        },
+        ResolvedQuarantinedReimbursement {
+            index: ReimbursementIndex,
+            resolution: super::QuarantineResolution,
+            reason: String,
+        },
+        CancelledWithdrawalRequest {
+            withdrawal_id: Nat,
+            refunded_fee: Option<Nat>,
+        },
+        ReimbursedEthWithdrawal {
+            withdrawal_id: Nat,
+            /// Not recorded in the ICRC-3 log, only the block appended next at that time.
+            reimbursed_in_block: Nat,
+            reimbursed_amount: Nat,
+            transaction_hash: Option<String>,
+        },
+        ReimbursedErc20Withdrawal {
+            withdrawal_id: Nat,
+            burn_in_block: Nat,
+            reimbursed_in_block: Nat,
+            reimbursed_amount: Nat,
+            transaction_hash: Option<String>,
+        },
         QuarantinedReimbursement {
             index: ReimbursementIndex,
         },
@@ -398,6 +907,41 @@ pub mod events {
             from: Principal,
             to: Principal,
             amount: Nat,
+            from_subaccount: Option<[u8; 32]>,
+            to_subaccount: Option<[u8; 32]>,
+            token: Option<String>,
+            memo: Option<ByteBuf>,
+            created_at_time: Option<u64>,
+            block_index: Option<u64>,
+        },
+        Erc20Approved {
+            owner: Principal,
+            spender: Principal,
+            amount: Nat,
+            expires_at: Option<u64>,
+            owner_subaccount: Option<[u8; 32]>,
+            spender_subaccount: Option<[u8; 32]>,
+            token: Option<String>,
+            memo: Option<ByteBuf>,
+            created_at_time: Option<u64>,
+            block_index: Option<u64>,
+        },
+        Erc20TransferFromCompleted {
+            spender: Principal,
+            from: Principal,
+            to: Principal,
+            amount: Nat,
+            spender_subaccount: Option<[u8; 32]>,
+            from_subaccount: Option<[u8; 32]>,
+            to_subaccount: Option<[u8; 32]>,
+            token: Option<String>,
+            memo: Option<ByteBuf>,
+            created_at_time: Option<u64>,
+            block_index: Option<u64>,
+        },
+        UpdatedRpcProviders {
+            providers: Vec<RpcProvider>,
+            consensus_threshold: Option<u8>,
         },
     }
 }
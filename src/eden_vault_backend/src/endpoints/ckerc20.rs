@@ -5,6 +5,9 @@ use candid::{CandidType, Deserialize, Nat, Principal};
 pub struct WithdrawErc20Arg {
     pub amount: Nat,
     pub recipient: String,
+    pub from_subaccount: Option<[u8; 32]>,
+    /// The ERC-20 token to withdraw, the vault default token if not set.
+    pub erc20_contract_address: Option<String>,
 }
 
 #[derive(CandidType, Debug, Clone, PartialEq, Eq)]
@@ -13,8 +16,10 @@ pub struct RetrieveErc20Request {
     pub withdrawal_amount: Nat,
     pub destination: String,
     pub from: Principal,
+    pub from_subaccount: Option<[u8; 32]>,
     pub created_at: u64,
     pub id: Nat,
+    pub erc20_contract_address: Option<String>,
 }
 
 impl From<Erc20WithdrawalRequest> for RetrieveErc20Request {
@@ -24,8 +29,12 @@ impl From<Erc20WithdrawalRequest> for RetrieveErc20Request {
             destination: request.destination.to_string(),
             max_transaction_fee: request.max_transaction_fee.try_into().unwrap(),
             from: request.from,
+            from_subaccount: request.from_subaccount.map(|subaccount| subaccount.to_bytes()),
             created_at: request.created_at,
             id: request.id.try_into().unwrap(),
+            erc20_contract_address: request
+                .erc20_contract_address
+                .map(|address| address.to_string()),
         }
     }
 }
@@ -50,6 +59,48 @@ pub enum WithdrawErc20Error {
         available: Nat,
         required: Nat,
     },
+    Paused,
+    AmountTooLow {
+        min_withdrawal_amount: Nat,
+    },
+    LimitExceeded {
+        limit: Nat,
+        window: WithdrawalLimitWindow,
+        remaining: Nat,
+    },
+}
+
+/// Window over which a withdrawal limit applies.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum WithdrawalLimitWindow {
+    /// A single withdrawal.
+    PerWithdrawal,
+    /// Withdrawals of the caller over the last 24 hours.
+    PrincipalRolling24h,
+    /// Withdrawals of every principal over the last 24 hours.
+    GlobalRolling24h,
+}
+
+/// What the caller can still withdraw under a limit.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct RemainingWithdrawalLimit {
+    pub window: WithdrawalLimitWindow,
+    pub limit: Nat,
+    pub remaining: Nat,
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct CancelledWithdrawal {
+    pub id: Nat,
+    pub refunded_amount: Nat,
+    pub refunded_fee: Option<Nat>,
+}
+
+#[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub enum CancelWithdrawalError {
+    NotFound,
+    NotOwner,
+    AlreadyProcessing { status: crate::endpoints::RetrieveEthStatus },
 }
 
 #[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
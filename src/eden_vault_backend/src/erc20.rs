@@ -4,7 +4,6 @@ pub mod test_fixtures;
 mod tests;
 
 use crate::endpoints::AddCkErc20Token;
-use crate::lifecycle::EthereumNetwork;
 use candid::Principal;
 use ic_ethereum_types::Address;
 use minicbor::{Decode, Encode};
@@ -16,8 +15,9 @@ pub const MAX_CK_TOKEN_SYMBOL_NUM_BYTES: usize = 20;
 
 #[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
 pub struct CkErc20Token {
+    /// Encoded like the chain id variants of `EthereumNetwork` used by earlier versions.
     #[n(0)]
-    pub erc20_ethereum_network: EthereumNetwork,
+    pub erc20_chain_id: u64,
     #[n(1)]
     pub erc20_contract_address: Address,
     #[n(2)]
@@ -61,17 +61,15 @@ impl TryFrom<AddCkErc20Token> for CkErc20Token {
     type Error = String;
 
     fn try_from(value: AddCkErc20Token) -> Result<Self, Self::Error> {
-        let erc20_ethereum_network = EthereumNetwork::try_from(
-            value
-                .chain_id
-                .0
-                .to_u64()
-                .ok_or("ERROR: chain_id does not fit in a u64")?,
-        )?;
+        let erc20_chain_id = value
+            .chain_id
+            .0
+            .to_u64()
+            .ok_or("ERROR: chain_id does not fit in a u64")?;
         let erc20_contract_address =
             Address::from_str(&value.address).map_err(|e| format!("ERROR: {}", e))?;
         Ok(Self {
-            erc20_ethereum_network,
+            erc20_chain_id,
             erc20_contract_address,
             ckerc20_token_symbol: value.ckerc20_token_symbol.parse()?,
             ckerc20_ledger_id: value.ckerc20_ledger_id,
@@ -6,9 +6,11 @@ use crate::eth_rpc_client::{EthRpcClient, MultiCallError};
 use crate::logs::{DEBUG, INFO};
 use crate::numeric::{BlockNumber, Erc20Value, LogIndex, Wei};
 use crate::state::read_state;
+use crate::state::transactions::{to_account, Subaccount};
 use candid::Principal;
 use ic_canister_log::log;
 use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc1::account::Account;
 use minicbor::{Decode, Encode};
 use std::fmt;
 use thiserror::Error;
@@ -27,6 +29,10 @@ pub struct ReceivedEthEvent {
     pub value: Wei,
     #[cbor(n(5), with = "crate::cbor::principal")]
     pub principal: Principal,
+    /// Hash of the block containing the deposit, used to detect reorgs.
+    /// Missing for deposits accepted by earlier versions of the vault.
+    #[n(6)]
+    pub block_hash: Option<Hash>,
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Decode, Encode)]
@@ -45,13 +51,28 @@ pub struct ReceivedErc20Event {
     pub principal: Principal,
     #[n(6)]
     pub erc20_contract_address: Address,
+    /// Subaccount of `principal` credited with the deposit,
+    /// only set by the `ReceivedErc20WithSubaccount` event.
+    #[n(7)]
+    pub subaccount: Option<Subaccount>,
+    /// Hash of the block containing the deposit, used to detect reorgs.
+    /// Missing for deposits accepted by earlier versions of the vault.
+    #[n(8)]
+    pub block_hash: Option<Hash>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum ReceivedEvent {
+    Eth(ReceivedEthEvent),
     Erc20(ReceivedErc20Event),
 }
 
+impl From<ReceivedEthEvent> for ReceivedEvent {
+    fn from(event: ReceivedEthEvent) -> Self {
+        ReceivedEvent::Eth(event)
+    }
+}
+
 impl From<ReceivedErc20Event> for ReceivedEvent {
     fn from(event: ReceivedErc20Event) -> Self {
         ReceivedEvent::Erc20(event)
@@ -67,6 +88,7 @@ impl fmt::Debug for ReceivedEthEvent {
             .field("from_address", &self.from_address)
             .field("value", &self.value)
             .field("principal", &format_args!("{}", self.principal))
+            .field("block_hash", &self.block_hash)
             .finish()
     }
 }
@@ -81,6 +103,8 @@ impl fmt::Debug for ReceivedErc20Event {
             .field("value", &self.value)
             .field("principal", &format_args!("{}", self.principal))
             .field("contract_address", &self.erc20_contract_address)
+            .field("subaccount", &self.subaccount)
+            .field("block_hash", &self.block_hash)
             .finish()
     }
 }
@@ -126,52 +150,87 @@ impl ReceivedEvent {
     /// in which event appears for this transaction.
     pub fn source(&self) -> EventSource {
         match self {
+            ReceivedEvent::Eth(evt) => evt.source(),
             ReceivedEvent::Erc20(evt) => evt.source(),
         }
     }
     pub fn from_address(&self) -> Address {
         match self {
+            ReceivedEvent::Eth(evt) => evt.from_address,
             ReceivedEvent::Erc20(evt) => evt.from_address,
         }
     }
     pub fn principal(&self) -> Principal {
         match self {
+            ReceivedEvent::Eth(evt) => evt.principal,
             ReceivedEvent::Erc20(evt) => evt.principal,
         }
     }
+    /// Account credited with the deposited funds.
+    pub fn beneficiary(&self) -> Account {
+        match self {
+            ReceivedEvent::Eth(evt) => Account::from(evt.principal),
+            ReceivedEvent::Erc20(evt) => to_account(evt.principal, evt.subaccount.clone()),
+        }
+    }
     pub fn block_number(&self) -> BlockNumber {
         match self {
+            ReceivedEvent::Eth(evt) => evt.block_number,
             ReceivedEvent::Erc20(evt) => evt.block_number,
         }
     }
+    pub fn block_hash(&self) -> Option<Hash> {
+        match self {
+            ReceivedEvent::Eth(evt) => evt.block_hash,
+            ReceivedEvent::Erc20(evt) => evt.block_hash,
+        }
+    }
     pub fn log_index(&self) -> LogIndex {
         match self {
+            ReceivedEvent::Eth(evt) => evt.log_index,
             ReceivedEvent::Erc20(evt) => evt.log_index,
         }
     }
     pub fn transaction_hash(&self) -> Hash {
         match self {
+            ReceivedEvent::Eth(evt) => evt.transaction_hash,
             ReceivedEvent::Erc20(evt) => evt.transaction_hash,
         }
     }
     pub fn value(&self) -> candid::Nat {
         match self {
+            ReceivedEvent::Eth(evt) => evt.value.into(),
             ReceivedEvent::Erc20(evt) => evt.value.into(),
         }
     }
-    pub fn raw_value(&self) -> Erc20Value {
-        match self {
-            ReceivedEvent::Erc20(evt) => evt.value,
-        }
-    }
 }
 
 pub async fn last_received_events(
-    topic: &[u8; 32],
+    topics: &[[u8; 32]],
+    contract_address: Address,
+    token_contract_addresses: &[Address],
+    from: BlockNumber,
+    to: BlockNumber,
+) -> Result<(Vec<ReceivedEvent>, Vec<ReceivedEventError>), MultiCallError<Vec<LogEntry>>> {
+    last_received_events_with_response_size(
+        topics,
+        contract_address,
+        token_contract_addresses,
+        from,
+        to,
+        crate::eth_rpc_client::ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE,
+    )
+    .await
+}
+
+/// Same as [`last_received_events`], starting with the given response size, in bytes.
+pub async fn last_received_events_with_response_size(
+    topics: &[[u8; 32]],
     contract_address: Address,
-    token_contract_address: Address,
+    token_contract_addresses: &[Address],
     from: BlockNumber,
     to: BlockNumber,
+    response_size_estimate: u64,
 ) -> Result<(Vec<ReceivedEvent>, Vec<ReceivedEventError>), MultiCallError<Vec<LogEntry>>> {
     use crate::eth_rpc::GetLogsParam;
 
@@ -181,29 +240,71 @@ pub async fn last_received_events(
             from, to
         ));
     }
-    let topics: Vec<_> = vec![
-        FixedSizeData(*topic).into(),
-        FixedSizeData((&token_contract_address).into()).into(),
-    ];
+    // Several event signatures match with the same disjunction semantics as token addresses.
+    let mut topics: Vec<_> = vec![topics
+        .iter()
+        .map(|topic| FixedSizeData(*topic))
+        .collect::<Vec<_>>()
+        .into()];
     // We add token contract addresses as additional topics to match.
     // It has a disjunction semantics, so it will match if event matches any one of these addresses.
+    if !token_contract_addresses.is_empty() {
+        topics.push(
+            token_contract_addresses
+                .iter()
+                .map(|address| FixedSizeData(address.into()))
+                .collect::<Vec<_>>()
+                .into(),
+        );
+    }
 
-    let result = read_state(EthRpcClient::from_state)
-        .eth_get_logs(GetLogsParam {
-            from_block: from.into(),
-            to_block: to.into(),
-            address: vec![contract_address],
-            topics,
+    let result =
+        read_state(|s| EthRpcClient::from_state_with_logs_response_size(s, response_size_estimate))
+            .eth_get_logs(GetLogsParam {
+                from_block: from.into(),
+                to_block: to.into(),
+                address: vec![contract_address],
+                topics,
+            })
+            .await?;
+
+    Ok(parse_logs(result))
+}
+
+/// Keeps the logs emitted by the contract that match one of the event signatures and,
+/// when token contract addresses are given, one of these tokens,
+/// as `eth_getLogs` would for the same filter.
+pub fn filter_logs(
+    logs: impl IntoIterator<Item = LogEntry>,
+    topics: &[[u8; 32]],
+    contract_address: Address,
+    token_contract_addresses: &[Address],
+) -> Vec<LogEntry> {
+    logs.into_iter()
+        .filter(|log| {
+            log.address == contract_address
+                && log
+                    .topics
+                    .first()
+                    .is_some_and(|signature| topics.contains(&signature.0))
+                && (token_contract_addresses.is_empty()
+                    || log.topics.get(1).is_some_and(|token| {
+                        token_contract_addresses
+                            .iter()
+                            .any(|address| token.0 == <[u8; 32]>::from(address))
+                    }))
         })
-        .await?;
+        .collect()
+}
 
-    let (ok, not_ok): (Vec<_>, Vec<_>) = result
+pub fn parse_logs(logs: Vec<LogEntry>) -> (Vec<ReceivedEvent>, Vec<ReceivedEventError>) {
+    let (ok, not_ok): (Vec<_>, Vec<_>) = logs
         .into_iter()
         .map(ReceivedEvent::try_from)
         .partition(Result::is_ok);
     let valid_transactions: Vec<ReceivedEvent> = ok.into_iter().map(Result::unwrap).collect();
     let errors: Vec<ReceivedEventError> = not_ok.into_iter().map(Result::unwrap_err).collect();
-    Ok((valid_transactions, errors))
+    (valid_transactions, errors)
 }
 
 pub fn report_transaction_error(error: ReceivedEventError) {
@@ -244,7 +345,7 @@ impl TryFrom<LogEntry> for ReceivedEvent {
     type Error = ReceivedEventError;
 
     fn try_from(entry: LogEntry) -> Result<Self, Self::Error> {
-        let _block_hash = entry
+        let block_hash = entry
             .block_hash
             .ok_or(ReceivedEventError::PendingLogEntry)?;
         let block_number = entry
@@ -292,20 +393,52 @@ impl TryFrom<LogEntry> for ReceivedEvent {
             })
         };
 
-        // We have only one non-indexed data field for both ETH and ERC20 events.
-        let value_bytes: [u8; 32] = entry.data.0.clone().try_into().map_err(|data| {
-            ReceivedEventError::InvalidEventSource {
-                source: event_source,
-                error: EventSourceError::InvalidEvent(format!(
-                    "Invalid data length; expected 32-byte value, got {}",
-                    hex::encode(data)
-                )),
+        // Non-indexed data fields are 32-byte words: the value, followed by the subaccount
+        // for events carrying one.
+        let parse_data = |expected_words: usize| -> Result<Vec<[u8; 32]>, ReceivedEventError> {
+            if entry.data.0.len() != 32 * expected_words {
+                return Err(ReceivedEventError::InvalidEventSource {
+                    source: event_source,
+                    error: EventSourceError::InvalidEvent(format!(
+                        "Invalid data length; expected {expected_words} 32-byte values, got {}",
+                        hex::encode(&entry.data.0)
+                    )),
+                });
             }
-        })?;
+            Ok(entry
+                .data
+                .0
+                .chunks_exact(32)
+                .map(|word| <[u8; 32]>::try_from(word).unwrap())
+                .collect())
+        };
 
         // We either have 3 indexed topics for ETH events: (hash, from_address, principal),
         // or 4 indexed topics for ERC20 events: (hash, erc20_contract_address, from_address, principal)
         match entry.topics[0] {
+            FixedSizeData(crate::deposit::RECEIVED_ETH_EVENT_TOPIC) => {
+                if entry.topics.len() != 3 {
+                    return Err(ReceivedEventError::InvalidEventSource {
+                        source: event_source,
+                        error: EventSourceError::InvalidEvent(format!(
+                            "Expected 3 topics for ReceivedEth event, got {}",
+                            entry.topics.len()
+                        )),
+                    });
+                };
+                let from_address = parse_address(&entry.topics[1])?;
+                let principal = parse_principal(&entry.topics[2])?;
+                Ok(ReceivedEthEvent {
+                    transaction_hash,
+                    block_number,
+                    log_index,
+                    from_address,
+                    value: Wei::from_be_bytes(parse_data(1)?[0]),
+                    principal,
+                    block_hash: Some(block_hash),
+                }
+                .into())
+            }
             FixedSizeData(crate::deposit::RECEIVED_ERC20_EVENT_TOPIC) => {
                 if entry.topics.len() != 4 {
                     return Err(ReceivedEventError::InvalidEventSource {
@@ -324,9 +457,39 @@ impl TryFrom<LogEntry> for ReceivedEvent {
                     block_number,
                     log_index,
                     from_address,
-                    value: Erc20Value::from_be_bytes(value_bytes),
+                    value: Erc20Value::from_be_bytes(parse_data(1)?[0]),
+                    principal,
+                    erc20_contract_address,
+                    subaccount: None,
+                    block_hash: Some(block_hash),
+                }
+                .into())
+            }
+            FixedSizeData(crate::deposit::RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC) => {
+                if entry.topics.len() != 4 {
+                    return Err(ReceivedEventError::InvalidEventSource {
+                        source: event_source,
+                        error: EventSourceError::InvalidEvent(format!(
+                            "Expected 4 topics for ReceivedErc20WithSubaccount event, got {}",
+                            entry.topics.len()
+                        )),
+                    });
+                };
+                let erc20_contract_address = parse_address(&entry.topics[1])?;
+                let from_address = parse_address(&entry.topics[2])?;
+                let principal = parse_principal(&entry.topics[3])?;
+                let [value, subaccount] = <[[u8; 32]; 2]>::try_from(parse_data(2)?).unwrap();
+                Ok(ReceivedErc20Event {
+                    transaction_hash,
+                    block_number,
+                    log_index,
+                    from_address,
+                    value: Erc20Value::from_be_bytes(value),
                     principal,
                     erc20_contract_address,
+                    // The default subaccount is represented as `None`.
+                    subaccount: Subaccount::from_icrc(Some(subaccount)),
+                    block_hash: Some(block_hash),
                 }
                 .into())
             }
@@ -117,3 +117,88 @@ mod parse_principal_from_slice {
         principal_bytes
     }
 }
+
+mod filter_logs {
+    use crate::deposit::{RECEIVED_ERC20_EVENT_TOPIC, RECEIVED_ETH_EVENT_TOPIC};
+    use crate::eth_logs::filter_logs;
+    use crate::eth_rpc::{FixedSizeData, LogEntry};
+    use ic_ethereum_types::Address;
+    use std::str::FromStr;
+
+    const HELPER_CONTRACT: &str = "0xb44b5e756a894775fc32eddf3314bb1b1944dc34";
+    const TOKEN: &str = "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238";
+
+    fn log_entry(address: &str, topics: &[[u8; 32]]) -> LogEntry {
+        let mut log: LogEntry = serde_json::from_str(
+            r#"{
+            "address": "0xb44b5e756a894775fc32eddf3314bb1b1944dc34",
+            "topics": [],
+            "data": "0x000000000000000000000000000000000000000000000000002386f26fc10000",
+            "blockNumber": "0x3ca487",
+            "transactionHash": "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3",
+            "transactionIndex": "0x22",
+            "blockHash": "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c",
+            "logIndex": "0x27",
+            "removed": false
+        }"#,
+        )
+        .unwrap();
+        log.address = Address::from_str(address).unwrap();
+        log.topics = topics.iter().map(|topic| FixedSizeData(*topic)).collect();
+        log
+    }
+
+    fn token_topic(token: &str) -> [u8; 32] {
+        <[u8; 32]>::from(&Address::from_str(token).unwrap())
+    }
+
+    #[test]
+    fn should_keep_logs_of_helper_contract_with_matching_signature() {
+        let eth_deposit = log_entry(HELPER_CONTRACT, &[RECEIVED_ETH_EVENT_TOPIC]);
+        let erc20_deposit = log_entry(
+            HELPER_CONTRACT,
+            &[RECEIVED_ERC20_EVENT_TOPIC, token_topic(TOKEN)],
+        );
+        let other_contract = log_entry(
+            "0x7e41257f7b5c3dd3313ef02b1f4c864fe95bec2b",
+            &[RECEIVED_ETH_EVENT_TOPIC],
+        );
+        let no_topic = log_entry(HELPER_CONTRACT, &[]);
+
+        assert_eq!(
+            filter_logs(
+                vec![eth_deposit.clone(), erc20_deposit, other_contract, no_topic],
+                &[RECEIVED_ETH_EVENT_TOPIC],
+                Address::from_str(HELPER_CONTRACT).unwrap(),
+                &[],
+            ),
+            vec![eth_deposit]
+        );
+    }
+
+    #[test]
+    fn should_keep_logs_of_given_tokens() {
+        let supported = log_entry(
+            HELPER_CONTRACT,
+            &[RECEIVED_ERC20_EVENT_TOPIC, token_topic(TOKEN)],
+        );
+        let unsupported = log_entry(
+            HELPER_CONTRACT,
+            &[
+                RECEIVED_ERC20_EVENT_TOPIC,
+                token_topic("0x7e41257f7b5c3dd3313ef02b1f4c864fe95bec2b"),
+            ],
+        );
+        let missing_token = log_entry(HELPER_CONTRACT, &[RECEIVED_ERC20_EVENT_TOPIC]);
+
+        assert_eq!(
+            filter_logs(
+                vec![supported.clone(), unsupported, missing_token],
+                &[RECEIVED_ERC20_EVENT_TOPIC],
+                Address::from_str(HELPER_CONTRACT).unwrap(),
+                &[Address::from_str(TOKEN).unwrap()],
+            ),
+            vec![supported]
+        );
+    }
+}
@@ -2,7 +2,7 @@
 //! interface.
 
 use crate::endpoints::CandidBlockTag;
-use crate::eth_rpc_client::responses::TransactionReceipt;
+use crate::eth_rpc_client::responses::{BlockReceipt, TransactionReceipt};
 use crate::eth_rpc_client::SingleCallError;
 use crate::eth_rpc_error::{sanitize_send_raw_transaction_result, Parser};
 use crate::logs::{DEBUG, TRACE_HTTP};
@@ -498,6 +498,8 @@ pub enum ResponseTransform {
     FeeHistory,
     #[n(4)]
     SendRawTransaction,
+    #[n(5)]
+    BlockReceipts,
 }
 
 impl ResponseTransform {
@@ -541,6 +543,7 @@ impl ResponseTransform {
             Self::SendRawTransaction => {
                 sanitize_send_raw_transaction_result(body_bytes, Parser::new())
             }
+            Self::BlockReceipts => redact_response::<Vec<BlockReceipt>>(body_bytes),
         }
     }
 }
@@ -657,9 +660,11 @@ impl<T: HttpResponsePayload> HttpResponsePayload for Option<T> {}
 
 impl HttpResponsePayload for TransactionCount {}
 
-/// Calls a JSON-RPC method on an Ethereum node at the specified URL.
+/// Calls a JSON-RPC method on an Ethereum node at the specified URL,
+/// sending `headers` in addition to the content type.
 pub async fn call<I, O>(
     url: impl Into<String>,
+    headers: Vec<HttpHeader>,
     method: impl Into<String>,
     params: I,
     mut response_size_estimate: ResponseSizeEstimate,
@@ -701,10 +706,12 @@ where
             url: url.clone(),
             max_response_bytes: Some(effective_size_estimate),
             method: HttpMethod::POST,
-            headers: vec![HttpHeader {
+            headers: std::iter::once(HttpHeader {
                 name: "Content-Type".to_string(),
                 value: "application/json".to_string(),
-            }],
+            })
+            .chain(headers.iter().cloned())
+            .collect(),
             body: Some(payload.as_bytes().to_vec()),
             transform: Some(TransformContext::from_name(
                 "cleanup_response".to_owned(),
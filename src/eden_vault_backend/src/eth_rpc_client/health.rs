@@ -0,0 +1,201 @@
+//! Health of the JSON-RPC providers, as observed by the minter.
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// Number of consecutive failed calls after which a provider is demoted.
+pub const MAX_CONSECUTIVE_FAILURES: u32 = 3;
+/// Time during which a demoted provider is only queried when there are not enough healthy providers.
+pub const DEMOTION_PERIOD_NS: u64 = 10 * 60 * 1_000_000_000;
+
+/// JSON-RPC methods called by the minter, each with its own consensus requirements.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum RpcMethod {
+    GetLogs,
+    GetBlockByNumber,
+    GetTransactionReceipt,
+    GetTransactionCount,
+    FeeHistory,
+    SendRawTransaction,
+    GetBlockReceipts,
+}
+
+impl RpcMethod {
+    pub const ALL: [RpcMethod; 7] = [
+        RpcMethod::GetLogs,
+        RpcMethod::GetBlockByNumber,
+        RpcMethod::GetTransactionReceipt,
+        RpcMethod::GetTransactionCount,
+        RpcMethod::FeeHistory,
+        RpcMethod::SendRawTransaction,
+        RpcMethod::GetBlockReceipts,
+    ];
+
+    /// Number of providers that must agree, given the number of healthy providers.
+    ///
+    /// Calls that mint, burn or finalize funds require a majority of the healthy providers and never
+    /// fewer than the configured threshold, even if demoted providers have to be queried to reach it.
+    /// Fee estimates only need two providers to agree, and sending a transaction needs a single one.
+    pub fn threshold(&self, configured_threshold: u8, healthy: usize, total: usize) -> usize {
+        let threshold = match self {
+            RpcMethod::GetLogs
+            | RpcMethod::GetBlockByNumber
+            | RpcMethod::GetTransactionReceipt
+            | RpcMethod::GetTransactionCount
+            | RpcMethod::GetBlockReceipts => {
+                (healthy / 2 + 1).max(usize::from(configured_threshold))
+            }
+            RpcMethod::FeeHistory => healthy.max(2),
+            RpcMethod::SendRawTransaction => 1,
+        };
+        threshold.clamp(1, total.max(1))
+    }
+}
+
+impl Display for RpcMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcMethod::GetLogs => write!(f, "eth_getLogs"),
+            RpcMethod::GetBlockByNumber => write!(f, "eth_getBlockByNumber"),
+            RpcMethod::GetTransactionReceipt => write!(f, "eth_getTransactionReceipt"),
+            RpcMethod::GetTransactionCount => write!(f, "eth_getTransactionCount"),
+            RpcMethod::FeeHistory => write!(f, "eth_feeHistory"),
+            RpcMethod::SendRawTransaction => write!(f, "eth_sendRawTransaction"),
+            RpcMethod::GetBlockReceipts => write!(f, "eth_getBlockReceipts"),
+        }
+    }
+}
+
+/// Statistics of a provider since the last upgrade.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ProviderStats {
+    pub successes: u64,
+    pub failures: u64,
+    /// Number of successful responses that differed from the one of a majority of the providers.
+    pub disagreements: u64,
+    pub consecutive_failures: u32,
+    /// Sum of the latencies of the calls made directly to the provider, in nanoseconds.
+    /// Calls going through the EVM RPC canister are not timed.
+    pub total_latency_ns: u64,
+    pub timed_calls: u64,
+    pub last_error: Option<String>,
+    /// Time until which the provider is demoted, in nanoseconds since the UNIX epoch.
+    pub demoted_until_ns: Option<u64>,
+}
+
+impl ProviderStats {
+    pub fn average_latency_ns(&self) -> Option<u64> {
+        (self.timed_calls > 0).then(|| self.total_latency_ns / self.timed_calls)
+    }
+
+    fn record_latency(&mut self, latency_ns: Option<u64>) {
+        if let Some(latency_ns) = latency_ns {
+            self.total_latency_ns = self.total_latency_ns.saturating_add(latency_ns);
+            self.timed_calls += 1;
+        }
+    }
+
+    pub fn is_demoted(&self, now_ns: u64) -> bool {
+        self.demoted_until_ns
+            .map(|until| now_ns < until)
+            .unwrap_or(false)
+    }
+
+    fn demote(&mut self, now_ns: u64) {
+        self.demoted_until_ns = Some(now_ns.saturating_add(DEMOTION_PERIOD_NS));
+    }
+}
+
+/// Statistics of every provider, keyed by provider id.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ProviderHealth {
+    stats: BTreeMap<String, ProviderStats>,
+}
+
+impl ProviderHealth {
+    pub fn get(&self, provider: &str) -> Option<&ProviderStats> {
+        self.stats.get(provider)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &ProviderStats)> {
+        self.stats.iter()
+    }
+
+    pub fn is_healthy(&self, provider: &str, now_ns: u64) -> bool {
+        !self
+            .stats
+            .get(provider)
+            .map(|stats| stats.is_demoted(now_ns))
+            .unwrap_or(false)
+    }
+
+    pub fn record_success(&mut self, provider: &str, latency_ns: Option<u64>) {
+        let stats = self.stats.entry(provider.to_string()).or_default();
+        stats.successes += 1;
+        stats.consecutive_failures = 0;
+        stats.record_latency(latency_ns);
+    }
+
+    pub fn record_failure(
+        &mut self,
+        provider: &str,
+        error: String,
+        latency_ns: Option<u64>,
+        now_ns: u64,
+    ) {
+        let stats = self.stats.entry(provider.to_string()).or_default();
+        stats.failures += 1;
+        stats.consecutive_failures += 1;
+        stats.record_latency(latency_ns);
+        stats.last_error = Some(error);
+        if stats.consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+            stats.demote(now_ns);
+        }
+    }
+
+    /// Records that the provider responded differently than a majority of the providers,
+    /// which demotes it right away.
+    pub fn record_disagreement(&mut self, provider: &str, now_ns: u64) {
+        let stats = self.stats.entry(provider.to_string()).or_default();
+        stats.disagreements += 1;
+        stats.last_error = Some("response differs from the majority of providers".to_string());
+        stats.demote(now_ns);
+    }
+
+    /// Selects `count` providers among `candidates`, keeping their order but preferring healthy ones.
+    /// Demoted providers are only selected when there are not enough healthy ones,
+    /// starting with those whose demotion ends first.
+    pub fn select<P, F: Fn(&P) -> String>(
+        &self,
+        candidates: Vec<P>,
+        count: usize,
+        id: F,
+        now_ns: u64,
+    ) -> Vec<P> {
+        let (mut selected, mut demoted): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|provider| self.is_healthy(&id(provider), now_ns));
+        selected.truncate(count);
+        demoted.sort_by_key(|provider| {
+            self.stats
+                .get(&id(provider))
+                .and_then(|stats| stats.demoted_until_ns)
+        });
+        let missing = count.saturating_sub(selected.len());
+        selected.extend(demoted.into_iter().take(missing));
+        selected
+    }
+
+    pub fn count_healthy<'a>(
+        &self,
+        providers: impl IntoIterator<Item = &'a str>,
+        now_ns: u64,
+    ) -> usize {
+        providers
+            .into_iter()
+            .filter(|provider| self.is_healthy(provider, now_ns))
+            .count()
+    }
+}
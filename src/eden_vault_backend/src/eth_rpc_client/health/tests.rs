@@ -0,0 +1,142 @@
+use crate::eth_rpc_client::health::{
+    ProviderHealth, RpcMethod, DEMOTION_PERIOD_NS, MAX_CONSECUTIVE_FAILURES,
+};
+
+const NOW: u64 = 1_700_000_000_000_000_000;
+
+mod threshold {
+    use super::*;
+
+    #[test]
+    fn should_require_majority_of_healthy_providers_for_critical_methods() {
+        for method in [
+            RpcMethod::GetLogs,
+            RpcMethod::GetBlockByNumber,
+            RpcMethod::GetTransactionReceipt,
+            RpcMethod::GetTransactionCount,
+            RpcMethod::GetBlockReceipts,
+        ] {
+            assert_eq!(method.threshold(1, 4, 4), 3);
+            assert_eq!(method.threshold(1, 3, 4), 2);
+            assert_eq!(method.threshold(1, 1, 4), 1);
+        }
+    }
+
+    #[test]
+    fn should_never_go_below_configured_threshold_for_critical_methods() {
+        assert_eq!(RpcMethod::GetLogs.threshold(3, 1, 4), 3);
+        assert_eq!(RpcMethod::GetLogs.threshold(3, 0, 4), 3);
+        assert_eq!(RpcMethod::GetLogs.threshold(4, 4, 4), 4);
+    }
+
+    #[test]
+    fn should_relax_threshold_for_fee_history_and_send_raw_transaction() {
+        assert_eq!(RpcMethod::FeeHistory.threshold(3, 1, 4), 2);
+        assert_eq!(RpcMethod::FeeHistory.threshold(3, 4, 4), 4);
+        assert_eq!(RpcMethod::SendRawTransaction.threshold(3, 4, 4), 1);
+    }
+
+    #[test]
+    fn should_not_exceed_number_of_providers() {
+        assert_eq!(RpcMethod::GetLogs.threshold(3, 2, 2), 2);
+        assert_eq!(RpcMethod::FeeHistory.threshold(1, 1, 1), 1);
+    }
+}
+
+mod demotion {
+    use super::*;
+
+    #[test]
+    fn should_demote_after_consecutive_failures() {
+        let mut health = ProviderHealth::default();
+
+        for _ in 1..MAX_CONSECUTIVE_FAILURES {
+            health.record_failure("a", "timeout".to_string(), Some(10), NOW);
+            assert!(health.is_healthy("a", NOW));
+        }
+        health.record_failure("a", "timeout".to_string(), Some(10), NOW);
+
+        assert!(!health.is_healthy("a", NOW));
+        assert!(!health.is_healthy("a", NOW + DEMOTION_PERIOD_NS - 1));
+        assert!(health.is_healthy("a", NOW + DEMOTION_PERIOD_NS));
+        let stats = health.get("a").unwrap();
+        assert_eq!(stats.failures, u64::from(MAX_CONSECUTIVE_FAILURES));
+        assert_eq!(stats.last_error, Some("timeout".to_string()));
+    }
+
+    #[test]
+    fn should_reset_consecutive_failures_on_success() {
+        let mut health = ProviderHealth::default();
+
+        for _ in 1..MAX_CONSECUTIVE_FAILURES {
+            health.record_failure("a", "timeout".to_string(), None, NOW);
+        }
+        health.record_success("a", None);
+        health.record_failure("a", "timeout".to_string(), None, NOW);
+
+        assert!(health.is_healthy("a", NOW));
+        assert_eq!(health.get("a").unwrap().consecutive_failures, 1);
+    }
+
+    #[test]
+    fn should_demote_immediately_on_disagreement() {
+        let mut health = ProviderHealth::default();
+
+        health.record_disagreement("a", NOW);
+
+        assert!(!health.is_healthy("a", NOW));
+        assert_eq!(health.get("a").unwrap().disagreements, 1);
+    }
+
+    #[test]
+    fn should_average_only_timed_calls() {
+        let mut health = ProviderHealth::default();
+
+        health.record_success("a", Some(100));
+        health.record_success("a", None);
+        health.record_failure("a", "timeout".to_string(), Some(300), NOW);
+
+        assert_eq!(health.get("a").unwrap().average_latency_ns(), Some(200));
+        assert_eq!(health.get("b").and_then(|s| s.average_latency_ns()), None);
+    }
+}
+
+mod select {
+    use super::*;
+
+    fn id(provider: &&str) -> String {
+        provider.to_string()
+    }
+
+    #[test]
+    fn should_keep_order_when_all_healthy() {
+        let health = ProviderHealth::default();
+
+        let selected = health.select(vec!["a", "b", "c", "d"], 3, id, NOW);
+
+        assert_eq!(selected, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn should_skip_demoted_providers() {
+        let mut health = ProviderHealth::default();
+        health.record_disagreement("a", NOW);
+
+        let selected = health.select(vec!["a", "b", "c", "d"], 3, id, NOW);
+
+        assert_eq!(selected, vec!["b", "c", "d"]);
+        assert_eq!(health.count_healthy(["a", "b", "c", "d"], NOW), 3);
+    }
+
+    #[test]
+    fn should_fall_back_to_providers_demoted_first() {
+        let mut health = ProviderHealth::default();
+        health.record_disagreement("c", NOW + 1);
+        health.record_disagreement("a", NOW + 2);
+        health.record_disagreement("b", NOW);
+
+        let selected = health.select(vec!["a", "b", "c", "d"], 3, id, NOW + 2);
+
+        assert_eq!(selected, vec!["d", "b", "c"]);
+    }
+}
@@ -1,16 +1,17 @@
 use crate::eth_rpc::{
     self, Block, BlockSpec, BlockTag, Data, FeeHistory, FeeHistoryParams, FixedSizeData,
     GetLogsParam, Hash, HttpOutcallError, HttpResponsePayload, LogEntry, Quantity,
-    ResponseSizeEstimate, SendRawTransactionResult, Topic, HEADER_SIZE_LIMIT,
+    ResponseSizeEstimate, SendRawTransactionResult, Topic, HEADER_SIZE_LIMIT, MAX_PAYLOAD_SIZE,
 };
+use crate::eth_rpc_client::health::{ProviderHealth, RpcMethod};
 use crate::eth_rpc_client::providers::{RpcNodeProvider, MAINNET_PROVIDERS, SEPOLIA_PROVIDERS};
 use crate::eth_rpc_client::requests::GetTransactionCountParams;
-use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
+use crate::eth_rpc_client::responses::{BlockReceipt, TransactionReceipt, TransactionStatus};
+use crate::lifecycle::network::EvmNetwork;
 use crate::lifecycle::EthereumNetwork;
 use crate::logs::{PrintProxySink, DEBUG, INFO, TRACE_HTTP};
 use crate::numeric::{BlockNumber, GasAmount, LogIndex, TransactionCount, Wei, WeiPerGas};
-use crate::state::State;
-use candid::CandidType;
+use crate::state::{mutate_state, State};
 use evm_rpc_client::{
     Block as EvmBlock, BlockTag as EvmBlockTag, ConsensusStrategy, EvmRpcClient,
     FeeHistory as EvmFeeHistory, FeeHistoryArgs as EvmFeeHistoryArgs,
@@ -24,13 +25,12 @@ use ic_canister_log::log;
 use ic_ethereum_types::Address;
 use num_traits::ToPrimitive;
 use providers::{BSC_PROVIDERS, BSC_TESTNET_PROVIDERS, LOCAL_PROVIDERS};
-use serde::Deserialize;
 use serde::{de::DeserializeOwned, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::convert::Infallible;
 use std::fmt::{Debug, Display};
-use minicbor::{Decode, Encode};
 
+pub mod health;
 mod providers;
 pub mod requests;
 pub mod responses;
@@ -39,126 +39,113 @@ pub mod responses;
 mod tests;
 
 // We expect most of the calls to contain zero events.
-const ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE: u64 = 100;
+pub const ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE: u64 = 100;
 // DEV: 1
 const TOTAL_NUMBER_OF_PROVIDERS: u8 = 2;
 
 #[derive(Debug)]
 pub struct EthRpcClient {
     evm_rpc_client: Option<EvmRpcClient<IcRuntime, PrintProxySink>>,
-    chain: EthereumNetwork,
+    network: EvmNetwork,
+    /// Health of the providers when the client was created.
+    health: ProviderHealth,
+    now_ns: u64,
+    /// Expected size of the responses to `eth_getLogs`, in bytes.
+    logs_response_size_estimate: u64,
 }
 
-#[derive(
-    Copy, Clone, Eq, PartialEq, Hash, Debug, Default, CandidType, Decode, Deserialize, Encode,
-)]
-#[cbor(index_only)]
-pub enum CustomEvmRpc {
-    #[n(31337)]
-    #[default]
-    Local,
-    #[n(97)]
-    BSCTestnet,
-    #[n(56)]
-    BSC
-}
-
-impl CustomEvmRpc {
-    pub fn chain_id(&self) -> u64 {
-        match self {
-            CustomEvmRpc::BSC => 56,
-            CustomEvmRpc::BSCTestnet => 97,
-            CustomEvmRpc::Local => 31337,
-        }
-    }
-    //EvmRpcServices
-
-    pub fn evm_rpc_service(&self, client: &mut EthRpcClient) -> evm_rpc_client::RpcServices {
-        use evm_rpc_client::RpcServices as EvmRpcServices;
-        use evm_rpc_client::RpcApi;
-        match self {
-            CustomEvmRpc::BSC => EvmRpcServices::Custom {
-                chain_id: client.chain.chain_id(),
-                services: [RpcApi {
-                    url: String::from("https://bsc-mainnet.public.blastapi.io"),
-                    headers: None,
-                }, RpcApi {
-                    url: String::from("https://bsc.blockpi.network/v1/rpc/public"),
-                    headers: None,
-                }, RpcApi {
-                    url: String::from("https://bsc.drpc.org"),
-                    headers: None,
-                }, RpcApi {
-                    url: String::from("https://bsc-rpc.publicnode.com"),
-                    headers: None,
-                }, RpcApi {
-                    url: String::from("https://binance.llamarpc.com"),
-                    headers: None,
-                }, ]
-                .to_vec(),
-            },
-            CustomEvmRpc::BSCTestnet => EvmRpcServices::Custom {
-                chain_id: client.chain.chain_id(),
-                services: [RpcApi {
-                    url: String::from("https://bsc-testnet.blockpi.network/v1/rpc/public"),
-                    headers: None,
-                }, RpcApi {
-                    url: String::from("https://bsc-testnet-rpc.publicnode.com"),
-                    headers: None,
-                }]
-                .to_vec(),
-            },
-            CustomEvmRpc::Local => EvmRpcServices::Custom {
-                chain_id: client.chain.chain_id(),
-                services: [RpcApi {
-                    url: String::from("http://127.0.0.1:8545"),
-                    headers: None,
-                }]
-                .to_vec(),
-            },
-        }
-    }
-}
-
-
 impl EthRpcClient {
-    const fn new(chain: EthereumNetwork) -> Self {
+    fn new(network: EvmNetwork) -> Self {
         Self {
             evm_rpc_client: None,
-            chain,
+            network,
+            health: ProviderHealth::default(),
+            now_ns: 0,
+            logs_response_size_estimate: ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE,
         }
     }
 
     pub fn from_state(state: &State) -> Self {
+        Self::from_state_with_logs_response_size(state, ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE)
+    }
+
+    /// Client whose calls to `eth_getLogs` start with the given response size, in bytes,
+    /// for blocks that are known to contain many logs.
+    pub fn from_state_with_logs_response_size(
+        state: &State,
+        logs_response_size_estimate: u64,
+    ) -> Self {
+        use evm_rpc_client::RpcApi;
         use evm_rpc_client::RpcServices as EvmRpcServices;
 
-        let mut client = Self::new(state.ethereum_network());
+        let mut client = Self {
+            health: state.provider_health.clone(),
+            now_ns: ic_cdk::api::time(),
+            logs_response_size_estimate: logs_response_size_estimate.min(MAX_PAYLOAD_SIZE),
+            ..Self::new(state.evm_network().clone())
+        };
         if let Some(evm_rpc_id) = state.evm_rpc_id {
             const MIN_ATTACHED_CYCLES: u128 = 500_000_000_000;
-            let providers = match client.chain {
-                EthereumNetwork::Mainnet => EvmRpcServices::EthMainnet(None),
-                EthereumNetwork::Sepolia => EvmRpcServices::EthSepolia(None),
-                EthereumNetwork::BSC => CustomEvmRpc::BSC.evm_rpc_service(&mut client),
-                EthereumNetwork::BSCTestnet => CustomEvmRpc::BSCTestnet.evm_rpc_service(&mut client),
-                EthereumNetwork::Local => CustomEvmRpc::Local.evm_rpc_service(&mut client),
-            };
-            let min_threshold = match client.chain {
-                EthereumNetwork::Mainnet => 3_u8,
-                EthereumNetwork::Sepolia => 2_u8,
-                EthereumNetwork::Local => 1_u8,
-                EthereumNetwork::BSC=> 3_u8,
-                EthereumNetwork::BSCTestnet => 2_u8,
+            let custom_endpoints = !client.network.rpc_endpoints.is_empty();
+            let (providers, total_providers, healthy_providers) = match client
+                .network
+                .known_network()
+            {
+                Some(EthereumNetwork::Mainnet) if !custom_endpoints => (
+                    EvmRpcServices::EthMainnet(None),
+                    TOTAL_NUMBER_OF_PROVIDERS,
+                    usize::from(TOTAL_NUMBER_OF_PROVIDERS),
+                ),
+                Some(EthereumNetwork::Sepolia) if !custom_endpoints => (
+                    EvmRpcServices::EthSepolia(None),
+                    TOTAL_NUMBER_OF_PROVIDERS,
+                    usize::from(TOTAL_NUMBER_OF_PROVIDERS),
+                ),
+                _ => {
+                    // Demoted providers are left out, unless they are needed to reach the configured threshold.
+                    let healthy_providers = client.healthy_providers();
+                    let services: Vec<RpcApi> = client
+                        .health
+                        .select(
+                            client.providers(),
+                            healthy_providers.max(usize::from(client.network.consensus_threshold)),
+                            RpcNodeProvider::id,
+                            client.now_ns,
+                        )
+                        .iter()
+                        .map(|provider| RpcApi {
+                            url: provider.url().to_string(),
+                            headers: Some(provider.headers()).filter(|h| !h.is_empty()),
+                        })
+                        .collect();
+                    let total_providers = u8::try_from(services.len()).unwrap_or(u8::MAX);
+                    (
+                        EvmRpcServices::Custom {
+                            chain_id: client.network.chain_id,
+                            services,
+                        },
+                        total_providers,
+                        healthy_providers,
+                    )
+                }
             };
             assert!(
-                min_threshold <= TOTAL_NUMBER_OF_PROVIDERS,
+                client.network.consensus_threshold <= total_providers,
                 "BUG: min_threshold too high"
             );
-            let threshold_strategy = EvmRpcConfig {
-                response_consensus: Some(ConsensusStrategy::Threshold {
-                    total: Some(TOTAL_NUMBER_OF_PROVIDERS),
-                    min: min_threshold,
-                }),
-                ..EvmRpcConfig::default()
+            let threshold_strategy = |method: RpcMethod| {
+                let min = method.threshold(
+                    client.network.consensus_threshold,
+                    healthy_providers,
+                    usize::from(total_providers),
+                );
+                EvmRpcConfig {
+                    response_consensus: Some(ConsensusStrategy::Threshold {
+                        total: Some(total_providers),
+                        min: u8::try_from(min).expect("BUG: threshold is at most the total"),
+                    }),
+                    ..EvmRpcConfig::default()
+                }
             };
             client.evm_rpc_client = Some(
                 EvmRpcClient::builder_for_ic(TRACE_HTTP)
@@ -166,17 +153,25 @@ impl EthRpcClient {
                     .with_evm_canister_id(evm_rpc_id)
                     .with_min_attached_cycles(MIN_ATTACHED_CYCLES)
                     .with_override_rpc_config(OverrideRpcConfig {
-                        eth_get_block_by_number: Some(threshold_strategy.clone()),
+                        eth_get_block_by_number: Some(threshold_strategy(
+                            RpcMethod::GetBlockByNumber,
+                        )),
                         eth_get_logs: Some(EvmRpcConfig {
                             response_size_estimate: Some(
-                                ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE + HEADER_SIZE_LIMIT,
+                                client.logs_response_size_estimate + HEADER_SIZE_LIMIT,
                             ),
-                            ..threshold_strategy.clone()
+                            ..threshold_strategy(RpcMethod::GetLogs)
                         }),
-                        eth_fee_history: Some(threshold_strategy.clone()),
-                        eth_get_transaction_receipt: Some(threshold_strategy.clone()),
-                        eth_get_transaction_count: Some(threshold_strategy.clone()),
-                        eth_send_raw_transaction: Some(threshold_strategy),
+                        eth_fee_history: Some(threshold_strategy(RpcMethod::FeeHistory)),
+                        eth_get_transaction_receipt: Some(threshold_strategy(
+                            RpcMethod::GetTransactionReceipt,
+                        )),
+                        eth_get_transaction_count: Some(threshold_strategy(
+                            RpcMethod::GetTransactionCount,
+                        )),
+                        eth_send_raw_transaction: Some(threshold_strategy(
+                            RpcMethod::SendRawTransaction,
+                        )),
                     })
                     .build(),
             );
@@ -184,14 +179,36 @@ impl EthRpcClient {
         client
     }
 
-    fn providers(&self) -> &[RpcNodeProvider] {
-        match self.chain {
-            EthereumNetwork::Mainnet => &MAINNET_PROVIDERS,
-            EthereumNetwork::Sepolia => &SEPOLIA_PROVIDERS,
-            EthereumNetwork::Local => &LOCAL_PROVIDERS,
-            EthereumNetwork::BSC => &BSC_PROVIDERS,
-            EthereumNetwork::BSCTestnet => &BSC_TESTNET_PROVIDERS,
-        }
+    fn providers(&self) -> Vec<RpcNodeProvider> {
+        providers_of(&self.network)
+    }
+
+    fn healthy_providers(&self) -> usize {
+        let ids = provider_ids(&self.network);
+        self.health
+            .count_healthy(ids.iter().map(String::as_str), self.now_ns)
+    }
+
+    /// Number of providers that must agree on the result of the given method.
+    pub fn threshold(&self, method: RpcMethod) -> usize {
+        method.threshold(
+            self.network.consensus_threshold,
+            self.healthy_providers(),
+            self.providers().len(),
+        )
+    }
+
+    /// Providers to query for the given method: every provider that is not demoted,
+    /// completed with demoted ones when fewer than the threshold are healthy.
+    /// The threshold is only the number of providers that must agree on the result.
+    fn providers_for(&self, method: RpcMethod) -> Vec<RpcNodeProvider> {
+        let count = match method {
+            // Providers are queried in sequence until one succeeds.
+            RpcMethod::SendRawTransaction => self.providers().len(),
+            _ => self.healthy_providers().max(self.threshold(method)),
+        };
+        self.health
+            .select(self.providers(), count, RpcNodeProvider::id, self.now_ns)
     }
 
     /// Query all providers in sequence until one returns an ok result
@@ -201,7 +218,7 @@ impl EthRpcClient {
     /// querying data that is **not** critical since the returned value comes from a single provider.
     async fn sequential_call_until_ok<I, O>(
         &self,
-        method: impl Into<String> + Clone,
+        method: RpcMethod,
         params: I,
         response_size_estimate: ResponseSizeEstimate,
     ) -> MultiCallResults<O>
@@ -210,20 +227,23 @@ impl EthRpcClient {
         O: DeserializeOwned + HttpResponsePayload + Debug,
     {
         let mut results: MultiCallResults<O> = MultiCallResults::new();
-        for provider in self.providers() {
+        for provider in self.providers_for(method) {
             log!(
                 DEBUG,
                 "[sequential_call_until_ok]: calling provider: {:?}",
                 provider
             );
+            let start_ns = ic_cdk::api::time();
             let result: Result<O, SingleCallError> = eth_rpc::call(
                 provider.url().to_string(),
-                method.clone(),
+                provider.headers(),
+                method.to_string(),
                 params.clone(),
                 response_size_estimate,
             )
             .await;
-            results.insert_once(provider.clone(), result);
+            record_call_result(&provider, &result, start_ns);
+            results.insert_once(provider, result);
             if results.has_ok_results() {
                 return results;
             }
@@ -238,7 +258,7 @@ impl EthRpcClient {
     /// e.g., ethereum logs upon which ckETH will be minted.
     async fn parallel_call<I, O>(
         &self,
-        method: impl Into<String> + Clone,
+        method: RpcMethod,
         params: I,
         response_size_estimate: ResponseSizeEstimate,
     ) -> MultiCallResults<O>
@@ -246,21 +266,28 @@ impl EthRpcClient {
         I: Serialize + Clone,
         O: DeserializeOwned + HttpResponsePayload,
     {
-        let providers = self.providers();
+        let providers = self.providers_for(method);
         let results = {
             let mut fut = Vec::with_capacity(providers.len());
-            for provider in providers {
+            for provider in &providers {
                 log!(DEBUG, "[parallel_call]: will call provider: {:?}", provider);
-                fut.push(eth_rpc::call(
+                let call = eth_rpc::call(
                     provider.url().to_string(),
-                    method.clone(),
+                    provider.headers(),
+                    method.to_string(),
                     params.clone(),
                     response_size_estimate,
-                ));
+                );
+                fut.push(async move {
+                    let start_ns = ic_cdk::api::time();
+                    let result = call.await;
+                    record_call_result(provider, &result, start_ns);
+                    result
+                });
             }
             futures::future::join_all(fut).await
         };
-        MultiCallResults::from_non_empty_iter(providers.iter().cloned().zip(results.into_iter()))
+        MultiCallResults::from_non_empty_iter(providers.into_iter().zip(results.into_iter()))
     }
 
     pub async fn eth_get_logs(
@@ -268,7 +295,7 @@ impl EthRpcClient {
         params: GetLogsParam,
     ) -> Result<Vec<LogEntry>, MultiCallError<Vec<LogEntry>>> {
         if let Some(evm_rpc_client) = &self.evm_rpc_client {
-            return evm_rpc_client
+            let result = evm_rpc_client
                 .eth_get_logs(EvmGetLogsArgs {
                     from_block: Some(into_evm_block_tag(params.from_block)),
                     to_block: Some(into_evm_block_tag(params.to_block)),
@@ -282,16 +309,43 @@ impl EthRpcClient {
                 .await
                 .reduce()
                 .into();
+            record_evm_rpc_results(&result);
+            record_disagreements_in(&result);
+            return result;
         }
 
         let results: MultiCallResults<Vec<LogEntry>> = self
             .parallel_call(
-                "eth_getLogs",
+                RpcMethod::GetLogs,
                 vec![params],
-                ResponseSizeEstimate::new(ETH_GET_LOGS_INITIAL_RESPONSE_SIZE_ESTIMATE),
+                ResponseSizeEstimate::new(self.logs_response_size_estimate),
             )
             .await;
-        results.reduce().into()
+        record_disagreements(&results);
+        results.reduce_with_threshold(self.threshold(RpcMethod::GetLogs))
+    }
+
+    /// Receipts of every transaction of the block, reduced to their logs.
+    /// Used when the logs of a block do not fit in a response to `eth_getLogs`.
+    pub async fn eth_get_block_receipts(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Vec<BlockReceipt>, MultiCallError<Vec<BlockReceipt>>> {
+        if self.evm_rpc_client.is_some() {
+            // The EVM RPC canister does not expose `eth_getBlockReceipts`.
+            return Err(MultiCallError::ConsistentEvmRpcCanisterError(
+                "eth_getBlockReceipts is not supported by the EVM RPC canister".to_string(),
+            ));
+        }
+        let results: MultiCallResults<Vec<BlockReceipt>> = self
+            .parallel_call(
+                RpcMethod::GetBlockReceipts,
+                vec![BlockSpec::Number(block_number)],
+                ResponseSizeEstimate::new(MAX_PAYLOAD_SIZE),
+            )
+            .await;
+        record_disagreements(&results);
+        results.reduce_with_threshold(self.threshold(RpcMethod::GetBlockReceipts))
     }
 
     pub async fn eth_get_block_by_number(
@@ -301,24 +355,23 @@ impl EthRpcClient {
         use crate::eth_rpc::GetBlockByNumberParams;
 
         if let Some(evm_rpc_client) = &self.evm_rpc_client {
-            return evm_rpc_client
+            let result = evm_rpc_client
                 .eth_get_block_by_number(into_evm_block_tag(block))
                 .await
                 .reduce()
                 .into();
+            record_evm_rpc_results(&result);
+            return result;
         }
 
-        let expected_block_size = match self.chain {
-            EthereumNetwork::Local => 12 * 1024,
-            EthereumNetwork::Sepolia => 12 * 1024,
-            EthereumNetwork::Mainnet => 24 * 1024,
-            EthereumNetwork::BSC => 24 * 1024,
-            EthereumNetwork::BSCTestnet => 24 * 1024,
+        let expected_block_size = match self.network.known_network() {
+            Some(EthereumNetwork::Local) | Some(EthereumNetwork::Sepolia) => 12 * 1024,
+            _ => 24 * 1024,
         };
 
         let results: MultiCallResults<Block> = self
             .parallel_call(
-                "eth_getBlockByNumber",
+                RpcMethod::GetBlockByNumber,
                 GetBlockByNumberParams {
                     block,
                     include_full_transactions: false,
@@ -326,7 +379,7 @@ impl EthRpcClient {
                 ResponseSizeEstimate::new(expected_block_size),
             )
             .await;
-        results.reduce().into()
+        results.reduce_with_threshold(self.threshold(RpcMethod::GetBlockByNumber))
     }
 
     pub async fn eth_get_transaction_receipt(
@@ -334,20 +387,24 @@ impl EthRpcClient {
         tx_hash: Hash,
     ) -> Result<Option<TransactionReceipt>, MultiCallError<Option<TransactionReceipt>>> {
         if let Some(evm_rpc_client) = &self.evm_rpc_client {
-            return evm_rpc_client
+            let result = evm_rpc_client
                 .eth_get_transaction_receipt(tx_hash.to_string())
                 .await
                 .reduce()
                 .into();
+            record_evm_rpc_results(&result);
+            record_disagreements_in(&result);
+            return result;
         }
         let results: MultiCallResults<Option<TransactionReceipt>> = self
             .parallel_call(
-                "eth_getTransactionReceipt",
+                RpcMethod::GetTransactionReceipt,
                 vec![tx_hash],
                 ResponseSizeEstimate::new(700),
             )
             .await;
-        results.reduce().into()
+        record_disagreements(&results);
+        results.reduce_with_threshold(self.threshold(RpcMethod::GetTransactionReceipt))
     }
 
     pub async fn eth_fee_history(
@@ -355,7 +412,7 @@ impl EthRpcClient {
         params: FeeHistoryParams,
     ) -> Result<FeeHistory, MultiCallError<FeeHistory>> {
         if let Some(evm_rpc_client) = &self.evm_rpc_client {
-            return evm_rpc_client
+            let result = evm_rpc_client
                 .eth_fee_history(EvmFeeHistoryArgs {
                     block_count: Nat256::from_be_bytes(params.block_count.to_be_bytes()),
                     newest_block: into_evm_block_tag(params.highest_block),
@@ -364,10 +421,12 @@ impl EthRpcClient {
                 .await
                 .reduce()
                 .into();
+            record_evm_rpc_results(&result);
+            return result;
         }
         // A typical response is slightly above 300 bytes.
         let results: MultiCallResults<FeeHistory> = self
-            .parallel_call("eth_feeHistory", params, ResponseSizeEstimate::new(512))
+            .parallel_call(RpcMethod::FeeHistory, params, ResponseSizeEstimate::new(512))
             .await;
         results.reduce().into()
     }
@@ -377,17 +436,19 @@ impl EthRpcClient {
         raw_signed_transaction_hex: String,
     ) -> Result<SendRawTransactionResult, MultiCallError<SendRawTransactionResult>> {
         if let Some(evm_rpc_client) = &self.evm_rpc_client {
-            return evm_rpc_client
+            let result = evm_rpc_client
                 .eth_send_raw_transaction(raw_signed_transaction_hex)
                 .await
                 .reduce()
                 .into();
+            record_evm_rpc_results(&result);
+            return result;
         }
         // A successful reply is under 256 bytes, but we expect most calls to end with an error
         // since we submit the same transaction from multiple nodes.
         let results: MultiCallResults<SendRawTransactionResult> = self
             .sequential_call_until_ok(
-                "eth_sendRawTransaction",
+                RpcMethod::SendRawTransaction,
                 vec![raw_signed_transaction_hex],
                 ResponseSizeEstimate::new(256),
             )
@@ -406,7 +467,10 @@ impl EthRpcClient {
                     block: EvmBlockTag::Finalized,
                 })
                 .await;
-            return ReduceWithStrategy::<Equality>::reduce(results).into();
+            let result = ReduceWithStrategy::<Equality>::reduce(results).into();
+            record_evm_rpc_results(&result);
+            record_disagreements_in(&result);
+            return result;
         }
         let results: MultiCallResults<TransactionCount> = self
             .eth_get_transaction_count(GetTransactionCountParams {
@@ -414,7 +478,8 @@ impl EthRpcClient {
                 block: BlockSpec::Tag(BlockTag::Finalized),
             })
             .await;
-        ReduceWithStrategy::<Equality>::reduce(results).into()
+        record_disagreements(&results);
+        results.reduce_with_threshold(self.threshold(RpcMethod::GetTransactionCount))
     }
 
     pub async fn eth_get_latest_transaction_count(
@@ -428,7 +493,9 @@ impl EthRpcClient {
                     block: EvmBlockTag::Latest,
                 })
                 .await;
-            return ReduceWithStrategy::<MinByKey>::reduce(results).into();
+            let result = ReduceWithStrategy::<MinByKey>::reduce(results).into();
+            record_evm_rpc_results(&result);
+            return result;
         }
         let results: MultiCallResults<TransactionCount> = self
             .eth_get_transaction_count(GetTransactionCountParams {
@@ -444,7 +511,7 @@ impl EthRpcClient {
         params: GetTransactionCountParams,
     ) -> MultiCallResults<TransactionCount> {
         self.parallel_call(
-            "eth_getTransactionCount",
+            RpcMethod::GetTransactionCount,
             params,
             ResponseSizeEstimate::new(50),
         )
@@ -452,6 +519,86 @@ impl EthRpcClient {
     }
 }
 
+fn providers_of(network: &EvmNetwork) -> Vec<RpcNodeProvider> {
+    if !network.rpc_endpoints.is_empty() {
+        return network
+            .rpc_endpoints
+            .iter()
+            .cloned()
+            .map(RpcNodeProvider::Custom)
+            .collect();
+    }
+    match network.known_network() {
+        Some(EthereumNetwork::Mainnet) => MAINNET_PROVIDERS.to_vec(),
+        Some(EthereumNetwork::Sepolia) => SEPOLIA_PROVIDERS.to_vec(),
+        Some(EthereumNetwork::Local) => LOCAL_PROVIDERS.to_vec(),
+        Some(EthereumNetwork::BSC) => BSC_PROVIDERS.to_vec(),
+        Some(EthereumNetwork::BSCTestnet) => BSC_TESTNET_PROVIDERS.to_vec(),
+        None => panic!(
+            "BUG: no RPC endpoints configured for chain id {}",
+            network.chain_id
+        ),
+    }
+}
+
+/// Identifiers of the providers of the network, in the order in which they are queried.
+pub fn provider_ids(network: &EvmNetwork) -> Vec<String> {
+    providers_of(network).iter().map(RpcNodeProvider::id).collect()
+}
+
+fn record_call_result<T>(
+    provider: &RpcNodeProvider,
+    result: &Result<T, SingleCallError>,
+    start_ns: u64,
+) {
+    let now_ns = ic_cdk::api::time();
+    let latency_ns = Some(now_ns.saturating_sub(start_ns));
+    mutate_state(|s| match result {
+        Ok(_) => s.provider_health.record_success(&provider.id(), latency_ns),
+        Err(e) => s
+            .provider_health
+            .record_failure(&provider.id(), format!("{e:?}"), latency_ns, now_ns),
+    });
+}
+
+/// The EVM RPC canister only reports the result of each provider when they are inconsistent.
+fn record_evm_rpc_results<T>(result: &Result<T, MultiCallError<T>>) {
+    if let Err(MultiCallError::InconsistentResults(results)) = result {
+        let now_ns = ic_cdk::api::time();
+        mutate_state(|s| {
+            for provider in results.ok_results.keys() {
+                s.provider_health.record_success(&provider.id(), None);
+            }
+            for (provider, error) in &results.errors {
+                s.provider_health
+                    .record_failure(&provider.id(), format!("{error:?}"), None, now_ns);
+            }
+        });
+    }
+}
+
+fn record_disagreements_in<T: PartialEq>(result: &Result<T, MultiCallError<T>>) {
+    if let Err(MultiCallError::InconsistentResults(results)) = result {
+        record_disagreements(results);
+    }
+}
+
+/// Demotes the providers whose response differs from the majority.
+/// Only meaningful for queries that all providers must answer identically.
+fn record_disagreements<T: PartialEq>(results: &MultiCallResults<T>) {
+    let minority = results.minority_providers();
+    if minority.is_empty() {
+        return;
+    }
+    let now_ns = ic_cdk::api::time();
+    mutate_state(|s| {
+        for provider in minority {
+            log!(INFO, "[record_disagreements]: demoting provider {}", provider.id());
+            s.provider_health.record_disagreement(&provider.id(), now_ns);
+        }
+    });
+}
+
 /// Aggregates responses of different providers to the same query.
 /// Guaranteed to be non-empty.
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -532,6 +679,24 @@ impl<T> MultiCallResults<T> {
 }
 
 impl<T: PartialEq> MultiCallResults<T> {
+    /// Providers whose ok result differs from the one returned by a strict majority
+    /// of the providers with an ok result. Empty if there is no such majority.
+    pub fn minority_providers(&self) -> Vec<&RpcNodeProvider> {
+        let majority = self.ok_results.values().find(|value| {
+            self.ok_results.values().filter(|other| other == value).count() * 2
+                > self.ok_results.len()
+        });
+        match majority {
+            Some(majority) => self
+                .ok_results
+                .iter()
+                .filter(|(_, value)| *value != majority)
+                .map(|(provider, _)| provider)
+                .collect(),
+            None => vec![],
+        }
+    }
+
     /// Expects all results to be ok or return the following error:
     /// * MultiCallError::ConsistentJsonRpcError: all errors are the same JSON-RPC error.
     /// * MultiCallError::ConsistentHttpOutcallError: all errors are the same HTTP outcall error.
@@ -990,6 +1155,43 @@ impl<T: Debug + PartialEq> MultiCallResults<T> {
         Ok(base_result)
     }
 
+    /// Expects at least `threshold` providers to return the same ok result,
+    /// which no other ok result matches in number. The other providers may fail or disagree.
+    pub fn reduce_with_threshold(mut self, threshold: usize) -> Result<T, MultiCallError<T>> {
+        if self.ok_results.is_empty() {
+            return Err(self.expect_error());
+        }
+        let votes = |value: &T| {
+            self.ok_results
+                .values()
+                .filter(|other| *other == value)
+                .count()
+        };
+        let (agreed_provider, agreed_votes) = self
+            .ok_results
+            .iter()
+            .map(|(provider, value)| (provider, votes(value)))
+            .max_by_key(|(_provider, votes)| *votes)
+            .expect("BUG: ok results are non-empty");
+        let agreed_value = &self.ok_results[agreed_provider];
+        let contested = self
+            .ok_results
+            .values()
+            .any(|value| value != agreed_value && votes(value) >= agreed_votes);
+        if agreed_votes < threshold.max(1) || contested {
+            log!(
+                INFO,
+                "[reduce_with_threshold]: fewer than {threshold} providers agree on the results {self:?}"
+            );
+            return Err(MultiCallError::InconsistentResults(self));
+        }
+        let agreed_provider = agreed_provider.clone();
+        Ok(self
+            .ok_results
+            .remove(&agreed_provider)
+            .expect("BUG: agreed provider has an ok result"))
+    }
+
     pub fn reduce_with_min_by_key<F: FnMut(&T) -> K, K: Ord>(
         self,
         extractor: F,
@@ -1,4 +1,6 @@
+use crate::lifecycle::network::RpcEndpoint;
 use evm_rpc_client::RpcService as EvmRpcService;
+use ic_cdk::api::management_canister::http_request::HttpHeader;
 
 pub(crate) const MAINNET_PROVIDERS: [RpcNodeProvider; 4] = [
     RpcNodeProvider::Ethereum(EthereumProvider::BlockPi),
@@ -38,6 +40,8 @@ pub(crate) enum RpcNodeProvider {
     Local(LocalService),
     BSC(BSCService),
     BSCTestnet(BSCTestnetService),
+    /// Endpoint configured at init or upgrade time, or by the admin.
+    Custom(RpcEndpoint),
 }
 
 impl RpcNodeProvider {
@@ -49,11 +53,36 @@ impl RpcNodeProvider {
             Self::Local(provider) => provider.local_evm_endpoint_url(),
             Self::BSC(provider) => provider.bsc_mainnet_endpoint_url(),
             Self::BSCTestnet(provider) => provider.bsc_testnet_endpoint_url(),
+            Self::Custom(endpoint) => &endpoint.url,
             RpcNodeProvider::EvmRpc(_) => {
                 panic!("BUG: should not need URL of provider from EVM RPC canister")
             }
         }
     }
+
+    /// Identifies the provider in the health statistics, without exposing any credentials.
+    pub(crate) fn id(&self) -> String {
+        match self {
+            RpcNodeProvider::EvmRpc(EvmRpcService::Custom(api)) => api.url.clone(),
+            RpcNodeProvider::EvmRpc(service) => format!("{:?}", service),
+            _ => self.url().to_string(),
+        }
+    }
+
+    /// Additional headers to send to the provider, e.g. to authenticate.
+    pub(crate) fn headers(&self) -> Vec<HttpHeader> {
+        match self {
+            Self::Custom(endpoint) => endpoint
+                .headers
+                .iter()
+                .map(|header| HttpHeader {
+                    name: header.name.clone(),
+                    value: header.value.clone(),
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -1,4 +1,4 @@
-use crate::eth_rpc::{Hash, HttpResponsePayload, ResponseTransform};
+use crate::eth_rpc::{Hash, HttpResponsePayload, LogEntry, ResponseTransform};
 use crate::numeric::{BlockNumber, GasAmount, Wei, WeiPerGas};
 use minicbor::{Decode, Encode};
 use serde::{Deserialize, Serialize};
@@ -46,6 +46,21 @@ impl HttpResponsePayload for TransactionReceipt {
     }
 }
 
+/// Logs of a transaction, as returned by `eth_getBlockReceipts`.
+/// The other fields of the receipt are dropped to keep the response small.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockReceipt {
+    pub transaction_hash: Hash,
+    pub logs: Vec<LogEntry>,
+}
+
+impl HttpResponsePayload for Vec<BlockReceipt> {
+    fn response_transform() -> Option<ResponseTransform> {
+        Some(ResponseTransform::BlockReceipts)
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Decode, Deserialize, Encode, Serialize)]
 #[serde(try_from = "ethnum::u256", into = "ethnum::u256")]
 pub enum TransactionStatus {
@@ -7,11 +7,12 @@ const LLAMA_NODES: RpcNodeProvider = RpcNodeProvider::Ethereum(EthereumProvider:
 mod eth_rpc_client {
     use crate::eth_rpc_client::providers::{EthereumProvider, RpcNodeProvider, SepoliaProvider};
     use crate::eth_rpc_client::{EthRpcClient, TOTAL_NUMBER_OF_PROVIDERS};
+    use crate::lifecycle::network::{EvmNetwork, RpcEndpoint, RpcHeader};
     use crate::lifecycle::EthereumNetwork;
 
     #[test]
     fn should_retrieve_sepolia_providers_in_stable_order() {
-        let client = EthRpcClient::new(EthereumNetwork::Sepolia);
+        let client = EthRpcClient::new(EvmNetwork::from(EthereumNetwork::Sepolia));
 
         let providers = client.providers();
 
@@ -28,7 +29,7 @@ mod eth_rpc_client {
 
     #[test]
     fn should_retrieve_mainnet_providers_in_stable_order() {
-        let client = EthRpcClient::new(EthereumNetwork::Mainnet);
+        let client = EthRpcClient::new(EvmNetwork::from(EthereumNetwork::Mainnet));
 
         let providers = client.providers();
 
@@ -43,18 +44,151 @@ mod eth_rpc_client {
         );
     }
 
+    #[test]
+    fn should_retrieve_configured_providers_in_stable_order() {
+        let polygon_rpc = RpcEndpoint::from("https://polygon-rpc.com".to_string());
+        let alchemy = RpcEndpoint {
+            url: "https://polygon-mainnet.g.alchemy.com/v2".to_string(),
+            headers: vec![RpcHeader {
+                name: "Authorization".to_string(),
+                value: "Bearer secret".to_string(),
+            }],
+        };
+        let client = EthRpcClient::new(EvmNetwork {
+            chain_id: 137,
+            name: "Polygon".to_string(),
+            rpc_endpoints: vec![alchemy.clone(), polygon_rpc.clone()],
+            ..EvmNetwork::from(EthereumNetwork::Mainnet)
+        });
+
+        let providers = client.providers();
+
+        assert_eq!(
+            providers,
+            &[
+                RpcNodeProvider::Custom(alchemy),
+                RpcNodeProvider::Custom(polygon_rpc)
+            ]
+        );
+        assert_eq!(providers[0].headers()[0].value, "Bearer secret");
+        assert!(providers[1].headers().is_empty());
+        assert!(!format!("{:?}", providers[0]).contains("secret"));
+    }
+
     #[test]
     fn should_query_same_number_of_providers_as_with_evm_rpc_canister() {
-        let client = EthRpcClient::new(EthereumNetwork::Sepolia);
+        let client = EthRpcClient::new(EvmNetwork::from(EthereumNetwork::Sepolia));
         assert_eq!(client.providers().len() as u8, TOTAL_NUMBER_OF_PROVIDERS);
 
-        let client = EthRpcClient::new(EthereumNetwork::Mainnet);
+        let client = EthRpcClient::new(EvmNetwork::from(EthereumNetwork::Mainnet));
         assert_eq!(client.providers().len() as u8, TOTAL_NUMBER_OF_PROVIDERS);
     }
+
+    mod providers_for {
+        use crate::eth_rpc_client::health::{ProviderHealth, RpcMethod, MAX_CONSECUTIVE_FAILURES};
+        use crate::eth_rpc_client::providers::{EthereumProvider, RpcNodeProvider};
+        use crate::eth_rpc_client::EthRpcClient;
+        use crate::lifecycle::network::EvmNetwork;
+        use crate::lifecycle::EthereumNetwork;
+
+        const NOW: u64 = 1_700_000_000_000_000_000;
+
+        fn client_with_demoted(
+            demoted: &[EthereumProvider],
+            consensus_threshold: u8,
+        ) -> EthRpcClient {
+            let mut health = ProviderHealth::default();
+            for provider in demoted {
+                for _ in 0..MAX_CONSECUTIVE_FAILURES {
+                    health.record_failure(
+                        &RpcNodeProvider::Ethereum(*provider).id(),
+                        "timeout".to_string(),
+                        None,
+                        NOW,
+                    );
+                }
+            }
+            EthRpcClient {
+                health,
+                now_ns: NOW,
+                ..EthRpcClient::new(EvmNetwork {
+                    consensus_threshold,
+                    ..EvmNetwork::from(EthereumNetwork::Mainnet)
+                })
+            }
+        }
+
+        #[test]
+        fn should_query_every_healthy_provider() {
+            let client = client_with_demoted(&[], 1);
+
+            assert_eq!(client.threshold(RpcMethod::GetLogs), 3);
+            assert_eq!(client.providers_for(RpcMethod::GetLogs), client.providers());
+        }
+
+        #[test]
+        fn should_leave_out_demoted_providers() {
+            let client = client_with_demoted(&[EthereumProvider::PublicNode], 1);
+
+            assert_eq!(client.threshold(RpcMethod::GetLogs), 2);
+            assert_eq!(
+                client.providers_for(RpcMethod::GetLogs),
+                vec![
+                    RpcNodeProvider::Ethereum(EthereumProvider::BlockPi),
+                    RpcNodeProvider::Ethereum(EthereumProvider::LlamaNodes),
+                    RpcNodeProvider::Ethereum(EthereumProvider::Alchemy),
+                ]
+            );
+        }
+
+        #[test]
+        fn should_query_demoted_providers_to_reach_threshold() {
+            let client = client_with_demoted(
+                &[
+                    EthereumProvider::BlockPi,
+                    EthereumProvider::PublicNode,
+                    EthereumProvider::LlamaNodes,
+                ],
+                2,
+            );
+
+            assert_eq!(client.threshold(RpcMethod::GetLogs), 2);
+            assert_eq!(client.providers_for(RpcMethod::GetLogs).len(), 2);
+            assert!(client
+                .providers_for(RpcMethod::GetLogs)
+                .contains(&RpcNodeProvider::Ethereum(EthereumProvider::Alchemy)));
+        }
+    }
 }
 
 mod multi_call_results {
 
+    mod minority_providers {
+        use crate::eth_rpc_client::tests::{BLOCK_PI, LLAMA_NODES, PUBLIC_NODE};
+        use crate::eth_rpc_client::MultiCallResults;
+
+        #[test]
+        fn should_find_providers_disagreeing_with_majority() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (BLOCK_PI, Ok("hello".to_string())),
+                (PUBLIC_NODE, Ok("world".to_string())),
+                (LLAMA_NODES, Ok("hello".to_string())),
+            ]);
+
+            assert_eq!(results.minority_providers(), vec![&PUBLIC_NODE]);
+        }
+
+        #[test]
+        fn should_be_empty_without_strict_majority() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (BLOCK_PI, Ok("hello".to_string())),
+                (PUBLIC_NODE, Ok("world".to_string())),
+            ]);
+
+            assert!(results.minority_providers().is_empty());
+        }
+    }
+
     mod reduce_with_equality {
         use crate::eth_rpc::HttpOutcallError;
         use crate::eth_rpc_client::tests::{BLOCK_PI, PUBLIC_NODE};
@@ -206,6 +340,82 @@ mod multi_call_results {
         }
     }
 
+    mod reduce_with_threshold {
+        use crate::eth_rpc::HttpOutcallError;
+        use crate::eth_rpc_client::tests::{BLOCK_PI, LLAMA_NODES, PUBLIC_NODE};
+        use crate::eth_rpc_client::{MultiCallError, MultiCallResults};
+        use ic_cdk::api::call::RejectionCode;
+
+        fn timeout() -> HttpOutcallError {
+            HttpOutcallError::IcError {
+                code: RejectionCode::SysTransient,
+                message: "timeout".to_string(),
+            }
+        }
+
+        #[test]
+        fn should_accept_result_of_threshold_despite_failing_provider() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (BLOCK_PI, Ok("0x01".to_string())),
+                (PUBLIC_NODE, Err(timeout().into())),
+                (LLAMA_NODES, Ok("0x01".to_string())),
+            ]);
+
+            assert_eq!(results.reduce_with_threshold(2), Ok("0x01".to_string()));
+        }
+
+        #[test]
+        fn should_accept_result_of_threshold_despite_disagreeing_provider() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (BLOCK_PI, Ok("0x01".to_string())),
+                (PUBLIC_NODE, Ok("0x02".to_string())),
+                (LLAMA_NODES, Ok("0x01".to_string())),
+            ]);
+
+            assert_eq!(results.reduce_with_threshold(2), Ok("0x01".to_string()));
+        }
+
+        #[test]
+        fn should_fail_when_fewer_providers_than_threshold_agree() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (BLOCK_PI, Ok("0x01".to_string())),
+                (PUBLIC_NODE, Err(timeout().into())),
+                (LLAMA_NODES, Ok("0x01".to_string())),
+            ]);
+
+            assert_eq!(
+                results.clone().reduce_with_threshold(3),
+                Err(MultiCallError::InconsistentResults(results))
+            );
+        }
+
+        #[test]
+        fn should_fail_when_results_are_tied() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (BLOCK_PI, Ok("0x01".to_string())),
+                (PUBLIC_NODE, Ok("0x02".to_string())),
+            ]);
+
+            assert_eq!(
+                results.clone().reduce_with_threshold(1),
+                Err(MultiCallError::InconsistentResults(results))
+            );
+        }
+
+        #[test]
+        fn should_be_consistent_error_without_ok_result() {
+            let results: MultiCallResults<String> = MultiCallResults::from_non_empty_iter(vec![
+                (BLOCK_PI, Err(timeout().into())),
+                (PUBLIC_NODE, Err(timeout().into())),
+            ]);
+
+            assert_eq!(
+                results.reduce_with_threshold(1),
+                Err(MultiCallError::ConsistentHttpOutcallError(timeout()))
+            );
+        }
+    }
+
     mod reduce_with_min_by_key {
         use crate::eth_rpc::Block;
         use crate::eth_rpc_client::tests::{BLOCK_PI, PUBLIC_NODE};
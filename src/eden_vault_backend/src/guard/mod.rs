@@ -1,8 +1,12 @@
 #[cfg(test)]
 mod tests;
 
-use crate::state::{mutate_state, State, TaskType};
+use crate::endpoints::VaultFlow;
+use crate::numeric::Erc20Value;
+use crate::state::{mutate_state, read_state, State, TaskType};
 use candid::Principal;
+use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc1::account::Account;
 use std::collections::BTreeSet;
 use std::marker::PhantomData;
 
@@ -80,9 +84,22 @@ pub fn retrieve_withdraw_guard(
     Guard::new(principal)
 }
 
+/// Fails when the admin paused the flow, see [`crate::endpoints::VaultFlow`].
+pub fn ensure_not_paused(flow: VaultFlow) -> Result<(), FlowPaused> {
+    if read_state(|s| s.is_paused(flow)) {
+        return Err(FlowPaused(flow));
+    }
+    Ok(())
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub struct FlowPaused(pub VaultFlow);
+
 #[derive(Eq, PartialEq, Debug)]
 pub enum TimerGuardError {
     AlreadyProcessing,
+    /// The flow of the task is paused.
+    Paused(VaultFlow),
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -91,8 +108,12 @@ pub struct TimerGuard {
 }
 
 impl TimerGuard {
+    /// Fails if the task is already running or if its flow is paused.
     pub fn new(task: TaskType) -> Result<Self, TimerGuardError> {
         mutate_state(|s| {
+            if let Some(flow) = task.flow().filter(|flow| s.is_paused(*flow)) {
+                return Err(TimerGuardError::Paused(flow));
+            }
             if !s.active_tasks.insert(task) {
                 return Err(TimerGuardError::AlreadyProcessing);
             }
@@ -108,3 +129,52 @@ impl Drop for TimerGuard {
         });
     }
 }
+
+/// Reserves part of an account balance for a flow that awaits before debiting it.
+/// The reservation is released when the hold is dropped, unless it was committed.
+#[must_use]
+#[derive(Eq, PartialEq, Debug)]
+pub struct BalanceHold {
+    token: Address,
+    account: Account,
+    amount: Erc20Value,
+}
+
+impl BalanceHold {
+    /// Holds `amount` of `token` on the balance of `account`.
+    /// Fails with the available balance if it does not cover the amount.
+    pub fn new(token: Address, account: Account, amount: Erc20Value) -> Result<Self, Erc20Value> {
+        mutate_state(|s| {
+            let available = s.available_erc20_balance(&token, &account);
+            if available < amount {
+                return Err(available);
+            }
+            s.erc20_holds.hold(token, account, amount);
+            Ok(Self {
+                token,
+                account,
+                amount,
+            })
+        })
+    }
+
+    /// Releases the held amount and runs `f` in the same state mutation,
+    /// so that no other call can use the funds in between.
+    pub fn commit<R>(mut self, f: impl FnOnce(&mut State) -> R) -> R {
+        mutate_state(|s| {
+            s.erc20_holds
+                .release(&self.token, &self.account, self.amount);
+            self.amount = Erc20Value::ZERO;
+            f(s)
+        })
+    }
+}
+
+impl Drop for BalanceHold {
+    fn drop(&mut self) {
+        mutate_state(|s| {
+            s.erc20_holds
+                .release(&self.token, &self.account, self.amount)
+        });
+    }
+}
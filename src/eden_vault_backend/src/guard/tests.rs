@@ -129,6 +129,116 @@ mod timer_guard {
     }
 }
 
+mod pause {
+    use crate::endpoints::VaultFlow;
+    use crate::guard::tests::init_state;
+    use crate::guard::{ensure_not_paused, FlowPaused, TimerGuard, TimerGuardError};
+    use crate::state::audit::{apply_state_transition, EventType};
+    use crate::state::{mutate_state, read_state, TaskType};
+    use strum::IntoEnumIterator;
+
+    const DEPOSIT_TASKS: [TaskType; 6] = [
+        TaskType::Mint,
+        TaskType::ScrapEthLogs,
+        TaskType::ScrapPendingDeposits,
+        TaskType::RetrySkippedBlocks,
+        TaskType::Rescrape,
+        TaskType::MintCkErc20,
+    ];
+
+    fn set_paused(flow: VaultFlow, paused: bool) {
+        mutate_state(|s| apply_state_transition(s, &EventType::UpdatedPause { flow, paused }));
+    }
+
+    #[test]
+    fn should_refuse_withdrawals_while_paused() {
+        init_state();
+        assert_eq!(ensure_not_paused(VaultFlow::Withdrawals), Ok(()));
+
+        set_paused(VaultFlow::Withdrawals, true);
+        assert_eq!(
+            ensure_not_paused(VaultFlow::Withdrawals),
+            Err(FlowPaused(VaultFlow::Withdrawals))
+        );
+        assert_eq!(ensure_not_paused(VaultFlow::Transfers), Ok(()));
+
+        set_paused(VaultFlow::Withdrawals, false);
+        assert_eq!(ensure_not_paused(VaultFlow::Withdrawals), Ok(()));
+    }
+
+    #[test]
+    fn should_refuse_transfers_while_paused() {
+        init_state();
+        set_paused(VaultFlow::Transfers, true);
+
+        assert_eq!(
+            ensure_not_paused(VaultFlow::Transfers),
+            Err(FlowPaused(VaultFlow::Transfers))
+        );
+        assert_eq!(ensure_not_paused(VaultFlow::Withdrawals), Ok(()));
+
+        set_paused(VaultFlow::Transfers, false);
+        assert_eq!(ensure_not_paused(VaultFlow::Transfers), Ok(()));
+    }
+
+    #[test]
+    fn should_stop_scraping_and_minting_deposits_while_paused() {
+        init_state();
+        set_paused(VaultFlow::Deposits, true);
+
+        for task in DEPOSIT_TASKS {
+            assert_eq!(
+                TimerGuard::new(task),
+                Err(TimerGuardError::Paused(VaultFlow::Deposits))
+            );
+            assert!(read_state(|s| !s.active_tasks.contains(&task)));
+        }
+        assert!(TimerGuard::new(TaskType::RetrieveEth).is_ok());
+
+        set_paused(VaultFlow::Deposits, false);
+        for task in DEPOSIT_TASKS {
+            assert!(TimerGuard::new(task).is_ok());
+        }
+    }
+
+    #[test]
+    fn should_stop_sending_transactions_while_paused() {
+        init_state();
+        set_paused(VaultFlow::Transactions, true);
+
+        assert_eq!(
+            TimerGuard::new(TaskType::RetrieveEth),
+            Err(TimerGuardError::Paused(VaultFlow::Transactions))
+        );
+        for task in DEPOSIT_TASKS {
+            assert!(TimerGuard::new(task).is_ok());
+        }
+
+        set_paused(VaultFlow::Transactions, false);
+        assert!(TimerGuard::new(TaskType::RetrieveEth).is_ok());
+    }
+
+    #[test]
+    fn should_only_stop_tasks_of_a_flow() {
+        init_state();
+        for flow in [
+            VaultFlow::Deposits,
+            VaultFlow::Withdrawals,
+            VaultFlow::Transfers,
+            VaultFlow::Transactions,
+        ] {
+            set_paused(flow, true);
+        }
+
+        for task in TaskType::iter() {
+            match task.flow() {
+                Some(flow) => assert_eq!(TimerGuard::new(task), Err(TimerGuardError::Paused(flow))),
+                None => assert!(TimerGuard::new(task).is_ok()),
+            }
+        }
+    }
+}
+
 fn init_state() {
     use crate::lifecycle::init::InitArg;
     use crate::state::State;
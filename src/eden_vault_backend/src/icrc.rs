@@ -0,0 +1,178 @@
+//! Helpers shared by the ICRC endpoints exposed over the vault balances.
+use crate::numeric::Erc20Value;
+use crate::state::dedup::{RecentTransfers, TransferKey, TransferKind};
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferError};
+use icrc_ledger_types::icrc2::approve::ApproveError;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromError;
+use std::time::Duration;
+
+pub mod blocks;
+
+#[cfg(test)]
+mod tests;
+
+/// Transactions older than this window (relative to the canister time) are rejected.
+pub const TRANSACTION_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+/// Tolerated clock drift between the client and the canister.
+pub const PERMITTED_DRIFT: Duration = Duration::from_secs(2 * 60);
+/// Maximum length of a memo attached to a transfer.
+pub const MAX_MEMO_LENGTH: usize = 32;
+/// Number of decimals of the ERC-20 token held by the vault.
+pub const ERC20_DECIMALS: u8 = 18;
+/// Transfers and approvals within the vault are free of charge.
+pub const ICRC_FEE: u8 = 0;
+
+/// Error code returned in `GenericError` when the memo exceeds [`MAX_MEMO_LENGTH`].
+pub const MEMO_TOO_LONG_ERROR_CODE: u64 = 1;
+/// Error code returned in `GenericError` when an account approves itself.
+pub const SELF_APPROVAL_ERROR_CODE: u64 = 2;
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum CreatedAtTimeError {
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+}
+
+/// Reasons to reject the arguments common to all ICRC transactions.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum IcrcArgError {
+    BadFee,
+    MemoTooLong { length: usize },
+    CreatedAtTime(CreatedAtTimeError),
+}
+
+/// Checks the optional `created_at_time` of a transaction against the canister time `now`,
+/// both expressed in nanoseconds since the UNIX epoch.
+pub fn validate_created_at_time(
+    created_at_time: Option<u64>,
+    now: u64,
+) -> Result<(), CreatedAtTimeError> {
+    let created_at_time = match created_at_time {
+        Some(created_at_time) => created_at_time,
+        None => return Ok(()),
+    };
+    let window = (TRANSACTION_WINDOW + PERMITTED_DRIFT).as_nanos() as u64;
+    if created_at_time.saturating_add(window) < now {
+        return Err(CreatedAtTimeError::TooOld);
+    }
+    if created_at_time > now.saturating_add(PERMITTED_DRIFT.as_nanos() as u64) {
+        return Err(CreatedAtTimeError::CreatedInFuture { ledger_time: now });
+    }
+    Ok(())
+}
+
+/// Validates the fee, memo and creation time shared by transfers and approvals.
+pub fn validate_transaction_args(
+    fee: &Option<Nat>,
+    memo: &Option<Memo>,
+    created_at_time: Option<u64>,
+    now: u64,
+) -> Result<(), IcrcArgError> {
+    if let Some(fee) = fee {
+        if fee != &Nat::from(ICRC_FEE) {
+            return Err(IcrcArgError::BadFee);
+        }
+    }
+    if let Some(memo) = memo {
+        if memo.0.len() > MAX_MEMO_LENGTH {
+            return Err(IcrcArgError::MemoTooLong {
+                length: memo.0.len(),
+            });
+        }
+    }
+    validate_created_at_time(created_at_time, now).map_err(IcrcArgError::CreatedAtTime)
+}
+
+/// Index of the block recording an earlier transfer or approval with the same arguments, of which
+/// this one is a duplicate. Only transactions with a validated `created_at_time` are deduplicated.
+pub fn find_duplicate(
+    recent_transfers: &RecentTransfers,
+    from: Account,
+    kind: TransferKind,
+    amount: Erc20Value,
+    memo: &Option<Memo>,
+    created_at_time: Option<u64>,
+) -> Option<u64> {
+    recent_transfers.find(&TransferKey {
+        created_at_time: created_at_time?,
+        from,
+        kind,
+        amount,
+        memo: memo.as_ref().map(|memo| memo.0.to_vec()),
+    })
+}
+
+fn memo_too_long_error(length: usize) -> (Nat, String) {
+    (
+        Nat::from(MEMO_TOO_LONG_ERROR_CODE),
+        format!(
+            "ERROR: memo is too long: expected at most {} bytes, but got {}",
+            MAX_MEMO_LENGTH, length
+        ),
+    )
+}
+
+impl From<IcrcArgError> for TransferError {
+    fn from(error: IcrcArgError) -> Self {
+        match error {
+            IcrcArgError::BadFee => TransferError::BadFee {
+                expected_fee: Nat::from(ICRC_FEE),
+            },
+            IcrcArgError::CreatedAtTime(CreatedAtTimeError::TooOld) => TransferError::TooOld,
+            IcrcArgError::CreatedAtTime(CreatedAtTimeError::CreatedInFuture { ledger_time }) => {
+                TransferError::CreatedInFuture { ledger_time }
+            }
+            IcrcArgError::MemoTooLong { length } => {
+                let (error_code, message) = memo_too_long_error(length);
+                TransferError::GenericError {
+                    error_code,
+                    message,
+                }
+            }
+        }
+    }
+}
+
+impl From<IcrcArgError> for ApproveError {
+    fn from(error: IcrcArgError) -> Self {
+        match error {
+            IcrcArgError::BadFee => ApproveError::BadFee {
+                expected_fee: Nat::from(ICRC_FEE),
+            },
+            IcrcArgError::CreatedAtTime(CreatedAtTimeError::TooOld) => ApproveError::TooOld,
+            IcrcArgError::CreatedAtTime(CreatedAtTimeError::CreatedInFuture { ledger_time }) => {
+                ApproveError::CreatedInFuture { ledger_time }
+            }
+            IcrcArgError::MemoTooLong { length } => {
+                let (error_code, message) = memo_too_long_error(length);
+                ApproveError::GenericError {
+                    error_code,
+                    message,
+                }
+            }
+        }
+    }
+}
+
+impl From<IcrcArgError> for TransferFromError {
+    fn from(error: IcrcArgError) -> Self {
+        match error {
+            IcrcArgError::BadFee => TransferFromError::BadFee {
+                expected_fee: Nat::from(ICRC_FEE),
+            },
+            IcrcArgError::CreatedAtTime(CreatedAtTimeError::TooOld) => TransferFromError::TooOld,
+            IcrcArgError::CreatedAtTime(CreatedAtTimeError::CreatedInFuture { ledger_time }) => {
+                TransferFromError::CreatedInFuture { ledger_time }
+            }
+            IcrcArgError::MemoTooLong { length } => {
+                let (error_code, message) = memo_too_long_error(length);
+                TransferFromError::GenericError {
+                    error_code,
+                    message,
+                }
+            }
+        }
+    }
+}
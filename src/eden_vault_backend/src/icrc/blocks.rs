@@ -0,0 +1,502 @@
+//! ICRC-3 block log recording every movement of the vault balances of the default token.
+//! Native coin balances are not part of the ICRC interface and are only recorded in the event log.
+use crate::eth_logs::ReceivedEvent;
+use crate::numeric::Erc20Value;
+use crate::state::event::EventType;
+use crate::state::transactions::{to_account, ReimbursementIndex, WithdrawalRequest};
+use crate::state::State;
+use crate::storage;
+use candid::Nat;
+use ic_certification::hash_tree::{fork, label, leaf, HashTree};
+use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc3::blocks::{
+    BlockWithId, GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate, SupportedBlockType,
+};
+use minicbor::bytes::ByteVec;
+use minicbor::{Decode, Encode};
+use num_traits::ToPrimitive;
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximum number of blocks returned by a single `icrc3_get_blocks` call.
+pub const MAX_BLOCKS_PER_RESPONSE: u64 = 100;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Decode, Encode)]
+#[cbor(transparent)]
+pub struct BlockHash(#[cbor(n(0), with = "minicbor::bytes")] pub [u8; 32]);
+
+/// A single movement of the vault balances.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub enum Operation {
+    /// Funds deposited on Ethereum credited to an account.
+    #[n(0)]
+    Mint {
+        #[cbor(n(0), with = "crate::cbor::account")]
+        to: Account,
+        #[n(1)]
+        amount: Erc20Value,
+        /// The minted token, missing for blocks recorded before operations carried their token.
+        #[n(2)]
+        token: Option<Address>,
+    },
+    /// Funds debited from an account to be withdrawn to Ethereum.
+    #[n(1)]
+    Burn {
+        #[cbor(n(0), with = "crate::cbor::account")]
+        from: Account,
+        #[n(1)]
+        amount: Erc20Value,
+        #[n(2)]
+        token: Option<Address>,
+    },
+    /// Funds moved between two accounts, possibly by a spender holding an allowance.
+    #[n(2)]
+    Transfer {
+        #[cbor(n(0), with = "crate::cbor::account")]
+        from: Account,
+        #[cbor(n(1), with = "crate::cbor::account")]
+        to: Account,
+        #[cbor(n(2), with = "crate::cbor::account::option")]
+        spender: Option<Account>,
+        #[n(3)]
+        amount: Erc20Value,
+        #[n(4)]
+        token: Option<Address>,
+        /// Memo given by the caller, missing for movements initiated by the vault.
+        #[n(5)]
+        memo: Option<ByteVec>,
+        /// Time in nanoseconds since the UNIX epoch at which the caller created the transaction.
+        #[n(6)]
+        created_at_time: Option<u64>,
+    },
+    /// Allowance granted by an account to a spender.
+    #[n(3)]
+    Approve {
+        #[cbor(n(0), with = "crate::cbor::account")]
+        from: Account,
+        #[cbor(n(1), with = "crate::cbor::account")]
+        spender: Account,
+        #[n(2)]
+        amount: Erc20Value,
+        #[n(3)]
+        expires_at: Option<u64>,
+        #[n(4)]
+        token: Option<Address>,
+        #[n(5)]
+        memo: Option<ByteVec>,
+        #[n(6)]
+        created_at_time: Option<u64>,
+    },
+}
+
+impl Operation {
+    pub fn token(&self) -> Option<Address> {
+        match self {
+            Operation::Mint { token, .. }
+            | Operation::Burn { token, .. }
+            | Operation::Transfer { token, .. }
+            | Operation::Approve { token, .. } => *token,
+        }
+    }
+
+    /// Memo and creation time given by the caller of the transaction.
+    fn caller_args(&self) -> (Option<&ByteVec>, Option<u64>) {
+        match self {
+            Operation::Transfer {
+                memo,
+                created_at_time,
+                ..
+            }
+            | Operation::Approve {
+                memo,
+                created_at_time,
+                ..
+            } => (memo.as_ref(), *created_at_time),
+            Operation::Mint { .. } | Operation::Burn { .. } => (None, None),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub struct Block {
+    /// Hash of the previous block, missing for the first block.
+    #[n(0)]
+    pub parent_hash: Option<BlockHash>,
+    /// IC time in nanoseconds at which the block was appended.
+    #[n(1)]
+    pub timestamp: u64,
+    #[n(2)]
+    pub operation: Operation,
+}
+
+impl Block {
+    /// Generic ICRC-3 representation of the block.
+    pub fn to_value(&self) -> ICRC3Value {
+        let mut tx = BTreeMap::new();
+        let block_type = match &self.operation {
+            Operation::Mint { to, amount, .. } => {
+                tx.insert("to".to_string(), account_value(to));
+                tx.insert("amt".to_string(), amount_value(amount));
+                "1mint"
+            }
+            Operation::Burn { from, amount, .. } => {
+                tx.insert("from".to_string(), account_value(from));
+                tx.insert("amt".to_string(), amount_value(amount));
+                "1burn"
+            }
+            Operation::Transfer {
+                from,
+                to,
+                spender,
+                amount,
+                ..
+            } => {
+                tx.insert("from".to_string(), account_value(from));
+                tx.insert("to".to_string(), account_value(to));
+                tx.insert("amt".to_string(), amount_value(amount));
+                match spender {
+                    Some(spender) => {
+                        tx.insert("spender".to_string(), account_value(spender));
+                        "2xfer"
+                    }
+                    None => "1xfer",
+                }
+            }
+            Operation::Approve {
+                from,
+                spender,
+                amount,
+                expires_at,
+                ..
+            } => {
+                tx.insert("from".to_string(), account_value(from));
+                tx.insert("spender".to_string(), account_value(spender));
+                tx.insert("amt".to_string(), amount_value(amount));
+                if let Some(expires_at) = expires_at {
+                    tx.insert(
+                        "expires_at".to_string(),
+                        ICRC3Value::Nat(Nat::from(*expires_at)),
+                    );
+                }
+                "2approve"
+            }
+        };
+        if let Some(token) = self.operation.token() {
+            tx.insert("token".to_string(), ICRC3Value::Text(token.to_string()));
+        }
+        let (memo, created_at_time) = self.operation.caller_args();
+        if let Some(memo) = memo {
+            tx.insert(
+                "memo".to_string(),
+                ICRC3Value::Blob(ByteBuf::from(memo.to_vec())),
+            );
+        }
+        if let Some(created_at_time) = created_at_time {
+            tx.insert("ts".to_string(), ICRC3Value::Nat(Nat::from(created_at_time)));
+        }
+
+        let mut block = BTreeMap::new();
+        if let Some(parent_hash) = &self.parent_hash {
+            block.insert(
+                "phash".to_string(),
+                ICRC3Value::Blob(ByteBuf::from(parent_hash.0.to_vec())),
+            );
+        }
+        block.insert("ts".to_string(), ICRC3Value::Nat(Nat::from(self.timestamp)));
+        block.insert("btype".to_string(), ICRC3Value::Text(block_type.to_string()));
+        block.insert("tx".to_string(), ICRC3Value::Map(tx));
+        ICRC3Value::Map(block)
+    }
+
+    pub fn hash(&self) -> BlockHash {
+        BlockHash(self.to_value().hash())
+    }
+}
+
+fn account_value(account: &Account) -> ICRC3Value {
+    let mut value = vec![ICRC3Value::Blob(ByteBuf::from(account.owner.as_slice().to_vec()))];
+    if let Some(subaccount) = account.subaccount {
+        value.push(ICRC3Value::Blob(ByteBuf::from(subaccount.to_vec())));
+    }
+    ICRC3Value::Array(value)
+}
+
+fn amount_value(amount: &Erc20Value) -> ICRC3Value {
+    ICRC3Value::Nat((*amount).into())
+}
+
+/// Movements of the vault balances caused by the given event,
+/// computed on the state *before* the event is applied.
+/// Only movements of the default token, exposed through the ICRC interface, are recorded.
+pub fn operations(state: &State, payload: &EventType) -> Vec<Operation> {
+    // Requests recorded before the vault held several tokens refer to the default token.
+    let is_default_token =
+        |token: Option<Address>| token.map_or(true, |token| token == state.default_erc20_token);
+    let token = Some(state.default_erc20_token);
+    match payload {
+        EventType::MintedCkErc20 {
+            event_source,
+            amount,
+            ..
+        } => {
+            let deposit = state
+                .events_to_mint
+                .get(event_source)
+                .unwrap_or_else(|| panic!("BUG: minting an unknown event {event_source:?}"));
+            match deposit {
+                ReceivedEvent::Erc20(event)
+                    if is_default_token(Some(event.erc20_contract_address)) =>
+                {
+                    vec![Operation::Mint {
+                        to: deposit.beneficiary(),
+                        amount: *amount,
+                        token,
+                    }]
+                }
+                _ => vec![],
+            }
+        }
+        EventType::Erc20TransferCompleted {
+            from,
+            to,
+            amount,
+            from_subaccount,
+            to_subaccount,
+            token: transferred,
+            memo,
+            created_at_time,
+            ..
+        } if is_default_token(*transferred) => vec![Operation::Transfer {
+            from: to_account(*from, from_subaccount.clone()),
+            to: to_account(*to, to_subaccount.clone()),
+            spender: None,
+            amount: *amount,
+            token,
+            memo: memo.clone(),
+            created_at_time: *created_at_time,
+        }],
+        EventType::Erc20TransferFromCompleted {
+            spender,
+            from,
+            to,
+            amount,
+            spender_subaccount,
+            from_subaccount,
+            to_subaccount,
+            token: transferred,
+            memo,
+            created_at_time,
+            ..
+        } if is_default_token(*transferred) => vec![Operation::Transfer {
+            from: to_account(*from, from_subaccount.clone()),
+            to: to_account(*to, to_subaccount.clone()),
+            spender: Some(to_account(*spender, spender_subaccount.clone())),
+            amount: *amount,
+            token,
+            memo: memo.clone(),
+            created_at_time: *created_at_time,
+        }],
+        EventType::Erc20Approved {
+            owner,
+            spender,
+            amount,
+            expires_at,
+            owner_subaccount,
+            spender_subaccount,
+            token: approved,
+            memo,
+            created_at_time,
+            ..
+        } if is_default_token(*approved) => vec![Operation::Approve {
+            from: to_account(*owner, owner_subaccount.clone()),
+            spender: to_account(*spender, spender_subaccount.clone()),
+            amount: *amount,
+            expires_at: *expires_at,
+            token,
+            memo: memo.clone(),
+            created_at_time: *created_at_time,
+        }],
+        EventType::AcceptedErc20WithdrawalRequest(request)
+            if is_default_token(request.erc20_contract_address) =>
+        {
+            let from = request.from_account();
+            let mut operations = vec![Operation::Burn {
+                from,
+                amount: request.withdrawal_amount,
+                token,
+            }];
+            if let Some(fee) = request.withdrawal_fee.filter(|fee| fee != &Erc20Value::ZERO) {
+                operations.push(Operation::Transfer {
+                    from,
+                    to: request
+                        .fee_recipient
+                        .unwrap_or_else(|| Account::from(state.admin)),
+                    spender: None,
+                    amount: fee,
+                    token,
+                    memo: None,
+                    created_at_time: None,
+                });
+            }
+            operations
+        }
+        EventType::CancelledWithdrawalRequest {
+            withdrawal_id,
+            refunded_fee,
+        } => {
+            let request = match state
+                .eth_transactions
+                .find_pending_withdrawal_request(withdrawal_id)
+            {
+                Some(WithdrawalRequest::CkErc20(request)) => request,
+                Some(WithdrawalRequest::CkEth(_)) => return vec![],
+                None => panic!("BUG: cancelling unknown withdrawal request {withdrawal_id}"),
+            };
+            if !is_default_token(request.erc20_contract_address) {
+                return vec![];
+            }
+            let to = request.from_account();
+            let mut operations = vec![Operation::Mint {
+                to,
+                amount: request.withdrawal_amount,
+                token,
+            }];
+            if let Some(fee) = refunded_fee.filter(|fee| fee != &Erc20Value::ZERO) {
+                operations.push(Operation::Transfer {
+                    from: request.fee_recipient(),
+                    to,
+                    spender: None,
+                    amount: fee,
+                    token,
+                    memo: None,
+                    created_at_time: None,
+                });
+            }
+            operations
+        }
+        // The amount is given back as for a cancellation, but the fee is kept.
+        EventType::RejectedWithdrawalRequest { withdrawal_id, .. } => operations(
+            state,
+            &EventType::CancelledWithdrawalRequest {
+                withdrawal_id: withdrawal_id.clone(),
+                refunded_fee: None,
+            },
+        ),
+        EventType::ReimbursedErc20Withdrawal { withdrawal_id, .. } => {
+            let index = ReimbursementIndex::CkErc20 {
+                withdrawal_id: withdrawal_id.clone(),
+            };
+            let request = state
+                .eth_transactions
+                .reimbursement_requests_iter()
+                .find_map(|(i, request)| (i == &index).then_some(request))
+                .unwrap_or_else(|| panic!("BUG: missing reimbursement request for {index:?}"));
+            if !is_default_token(request.erc20_contract_address) {
+                return vec![];
+            }
+            vec![Operation::Mint {
+                to: to_account(request.to, request.to_subaccount.clone()),
+                amount: request.reimbursed_amount.change_units(),
+                token,
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+pub fn supported_block_types() -> Vec<SupportedBlockType> {
+    let standard_url = |standard: &str| {
+        format!("https://github.com/dfinity/ICRC-1/tree/main/standards/{standard}")
+    };
+    ["1mint", "1burn", "1xfer"]
+        .into_iter()
+        .map(|block_type| SupportedBlockType {
+            block_type: block_type.to_string(),
+            url: standard_url("ICRC-1"),
+        })
+        .chain(["2approve", "2xfer"].into_iter().map(|block_type| {
+            SupportedBlockType {
+                block_type: block_type.to_string(),
+                url: standard_url("ICRC-2"),
+            }
+        }))
+        .collect()
+}
+
+/// Returns the requested blocks, truncated to [`MAX_BLOCKS_PER_RESPONSE`] blocks in total.
+pub fn get_blocks(requests: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let log_length = storage::total_block_count();
+    let mut blocks = vec![];
+    for GetBlocksRequest { start, length } in requests {
+        let remaining = MAX_BLOCKS_PER_RESPONSE - blocks.len() as u64;
+        let start = start.0.to_u64().unwrap_or(u64::MAX).min(log_length);
+        let length = length.0.to_u64().unwrap_or(u64::MAX).min(remaining);
+        let end = start.saturating_add(length).min(log_length);
+        for index in start..end {
+            let block = storage::get_block(index).expect("BUG: missing block within log length");
+            blocks.push(BlockWithId {
+                id: Nat::from(index),
+                block: block.to_value(),
+            });
+        }
+    }
+    GetBlocksResult {
+        log_length: Nat::from(log_length),
+        blocks,
+        archived_blocks: vec![],
+    }
+}
+
+/// Hash tree certifying the tip of the block log, as mandated by ICRC-3.
+pub fn tip_hash_tree(last_block_index: u64, last_block_hash: &BlockHash) -> HashTree {
+    fork(
+        label("last_block_hash", leaf(last_block_hash.0.to_vec())),
+        label("last_block_index", leaf(leb128(last_block_index))),
+    )
+}
+
+/// Sets the certified data of the canister to the root hash of the tip hash tree.
+pub fn certify_tip() {
+    if let Some((index, block)) = storage::last_block() {
+        let tree = tip_hash_tree(index, &block.hash());
+        ic_cdk::api::set_certified_data(&tree.digest());
+    }
+}
+
+pub fn tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = ic_cdk::api::data_certificate()?;
+    let (index, block) = storage::last_block()?;
+    let tree = tip_hash_tree(index, &block.hash());
+
+    let mut hash_tree = vec![];
+    let mut serializer = serde_cbor::Serializer::new(&mut hash_tree);
+    serializer
+        .self_describe()
+        .expect("BUG: failed to write the CBOR self-describe tag");
+    tree.serialize(&mut serializer)
+        .expect("BUG: failed to serialize the hash tree");
+
+    Some(ICRC3DataCertificate {
+        certificate: ByteBuf::from(certificate),
+        hash_tree: ByteBuf::from(hash_tree),
+    })
+}
+
+fn leb128(mut value: u64) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
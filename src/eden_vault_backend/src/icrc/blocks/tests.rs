@@ -0,0 +1,229 @@
+use crate::icrc::blocks::{leb128, Block, BlockHash, Operation};
+use crate::numeric::Erc20Value;
+use candid::{Nat, Principal};
+use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
+use icrc_ledger_types::icrc1::account::Account;
+use minicbor::bytes::ByteVec;
+
+fn account() -> Account {
+    Account::from(Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap())
+}
+
+fn other_account() -> Account {
+    Account {
+        owner: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+        subaccount: Some([7; 32]),
+    }
+}
+
+fn block_field(block: &ICRC3Value, field: &str) -> Option<ICRC3Value> {
+    match block {
+        ICRC3Value::Map(map) => map.get(field).cloned(),
+        _ => panic!("expected a map, got {block:?}"),
+    }
+}
+
+fn block_type(block: &Block) -> String {
+    match block_field(&block.to_value(), "btype") {
+        Some(ICRC3Value::Text(block_type)) => block_type,
+        other => panic!("unexpected block type {other:?}"),
+    }
+}
+
+#[test]
+fn should_encode_leb128() {
+    assert_eq!(leb128(0), vec![0x00]);
+    assert_eq!(leb128(127), vec![0x7f]);
+    assert_eq!(leb128(128), vec![0x80, 0x01]);
+    assert_eq!(leb128(624_485), vec![0xe5, 0x8e, 0x26]);
+}
+
+#[test]
+fn should_use_standard_block_types() {
+    let amount = Erc20Value::from(1_000_u64);
+    let block = |operation| Block {
+        parent_hash: None,
+        timestamp: 0,
+        operation,
+    };
+
+    assert_eq!(
+        block_type(&block(Operation::Mint {
+            to: account(),
+            amount,
+            token: None
+        })),
+        "1mint"
+    );
+    assert_eq!(
+        block_type(&block(Operation::Burn {
+            from: account(),
+            amount,
+            token: None
+        })),
+        "1burn"
+    );
+    assert_eq!(
+        block_type(&block(Operation::Transfer {
+            from: account(),
+            to: other_account(),
+            spender: None,
+            amount,
+            token: None,
+            memo: None,
+            created_at_time: None
+        })),
+        "1xfer"
+    );
+    assert_eq!(
+        block_type(&block(Operation::Transfer {
+            from: account(),
+            to: other_account(),
+            spender: Some(other_account()),
+            amount,
+            token: None,
+            memo: None,
+            created_at_time: None
+        })),
+        "2xfer"
+    );
+    assert_eq!(
+        block_type(&block(Operation::Approve {
+            from: account(),
+            spender: other_account(),
+            amount,
+            expires_at: None,
+            token: None,
+            memo: None,
+            created_at_time: None
+        })),
+        "2approve"
+    );
+}
+
+#[test]
+fn should_include_parent_hash_and_accounts() {
+    let first = Block {
+        parent_hash: None,
+        timestamp: 1,
+        operation: Operation::Mint {
+            to: other_account(),
+            amount: Erc20Value::from(10_u64),
+            token: None,
+        },
+    };
+    let second = Block {
+        parent_hash: Some(first.hash()),
+        timestamp: 2,
+        operation: Operation::Burn {
+            from: other_account(),
+            amount: Erc20Value::from(10_u64),
+            token: None,
+        },
+    };
+
+    assert_eq!(block_field(&first.to_value(), "phash"), None);
+    assert_eq!(
+        block_field(&second.to_value(), "phash"),
+        Some(ICRC3Value::Blob(first.hash().0.to_vec().into()))
+    );
+    assert_ne!(first.hash(), second.hash());
+
+    let tx = block_field(&first.to_value(), "tx").unwrap();
+    assert_eq!(
+        block_field(&tx, "to"),
+        Some(ICRC3Value::Array(vec![
+            ICRC3Value::Blob(other_account().owner.as_slice().to_vec().into()),
+            ICRC3Value::Blob(vec![7; 32].into()),
+        ]))
+    );
+    assert_eq!(block_field(&tx, "amt"), Some(ICRC3Value::Nat(Nat::from(10_u64))));
+}
+
+#[test]
+fn should_encode_and_decode_block() {
+    let block = Block {
+        parent_hash: Some(BlockHash([42; 32])),
+        timestamp: 1_700_000_000_000_000_000,
+        operation: Operation::Transfer {
+            from: account(),
+            to: other_account(),
+            spender: Some(other_account()),
+            amount: Erc20Value::from(5_u64),
+            token: None,
+            memo: Some(ByteVec::from(vec![1, 2, 3])),
+            created_at_time: Some(1_699_999_999_000_000_000),
+        },
+    };
+
+    let mut buf = vec![];
+    minicbor::encode(&block, &mut buf).unwrap();
+    let decoded: Block = minicbor::decode(&buf).unwrap();
+
+    assert_eq!(decoded, block);
+}
+
+#[test]
+fn should_record_token_only_when_known() {
+    let token: Address = "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
+        .parse()
+        .unwrap();
+    let block = |token| Block {
+        parent_hash: None,
+        timestamp: 1,
+        operation: Operation::Mint {
+            to: account(),
+            amount: Erc20Value::from(10_u64),
+            token,
+        },
+    };
+
+    let tx = block_field(&block(Some(token)).to_value(), "tx").unwrap();
+    assert_eq!(
+        block_field(&tx, "token"),
+        Some(ICRC3Value::Text(token.to_string()))
+    );
+    // Blocks recorded before operations carried their token keep their hash.
+    let tx = block_field(&block(None).to_value(), "tx").unwrap();
+    assert_eq!(block_field(&tx, "token"), None);
+    assert_ne!(block(None).hash(), block(Some(token)).hash());
+}
+
+#[test]
+fn should_record_memo_and_created_at_time_only_when_given() {
+    let block = |memo: Option<Vec<u8>>, created_at_time| Block {
+        parent_hash: None,
+        timestamp: 1_700_000_000_000_000_000,
+        operation: Operation::Transfer {
+            from: account(),
+            to: other_account(),
+            spender: None,
+            amount: Erc20Value::from(10_u64),
+            token: None,
+            memo: memo.map(ByteVec::from),
+            created_at_time,
+        },
+    };
+
+    let given = block(Some(vec![1, 2, 3]), Some(1_699_999_999_000_000_000));
+    let tx = block_field(&given.to_value(), "tx").unwrap();
+    assert_eq!(
+        block_field(&tx, "memo"),
+        Some(ICRC3Value::Blob(vec![1, 2, 3].into()))
+    );
+    assert_eq!(
+        block_field(&tx, "ts"),
+        Some(ICRC3Value::Nat(Nat::from(1_699_999_999_000_000_000_u64)))
+    );
+    assert_eq!(
+        block_field(&given.to_value(), "ts"),
+        Some(ICRC3Value::Nat(Nat::from(1_700_000_000_000_000_000_u64)))
+    );
+    // Blocks recorded before operations carried the caller arguments keep their hash.
+    let missing = block(None, None);
+    let tx = block_field(&missing.to_value(), "tx").unwrap();
+    assert_eq!(block_field(&tx, "memo"), None);
+    assert_eq!(block_field(&tx, "ts"), None);
+    assert_ne!(missing.hash(), given.hash());
+}
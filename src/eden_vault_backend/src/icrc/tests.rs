@@ -0,0 +1,168 @@
+mod validate_created_at_time {
+    use crate::icrc::{
+        validate_created_at_time, CreatedAtTimeError, PERMITTED_DRIFT, TRANSACTION_WINDOW,
+    };
+
+    const NOW: u64 = 1_700_000_000_000_000_000;
+
+    #[test]
+    fn should_accept_missing_created_at_time() {
+        assert_eq!(validate_created_at_time(None, NOW), Ok(()));
+    }
+
+    #[test]
+    fn should_accept_created_at_time_within_window() {
+        let drift = PERMITTED_DRIFT.as_nanos() as u64;
+        let window = TRANSACTION_WINDOW.as_nanos() as u64;
+
+        assert_eq!(validate_created_at_time(Some(NOW), NOW), Ok(()));
+        assert_eq!(validate_created_at_time(Some(NOW + drift), NOW), Ok(()));
+        assert_eq!(
+            validate_created_at_time(Some(NOW - window - drift), NOW),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn should_reject_too_old_transaction() {
+        let window = (TRANSACTION_WINDOW + PERMITTED_DRIFT).as_nanos() as u64;
+
+        assert_eq!(
+            validate_created_at_time(Some(NOW - window - 1), NOW),
+            Err(CreatedAtTimeError::TooOld)
+        );
+    }
+
+    #[test]
+    fn should_reject_transaction_created_in_future() {
+        let drift = PERMITTED_DRIFT.as_nanos() as u64;
+
+        assert_eq!(
+            validate_created_at_time(Some(NOW + drift + 1), NOW),
+            Err(CreatedAtTimeError::CreatedInFuture { ledger_time: NOW })
+        );
+    }
+}
+
+mod validate_transaction_args {
+    use crate::icrc::{validate_transaction_args, IcrcArgError, MAX_MEMO_LENGTH};
+    use candid::Nat;
+    use icrc_ledger_types::icrc1::transfer::{Memo, TransferError};
+
+    const NOW: u64 = 1_700_000_000_000_000_000;
+
+    #[test]
+    fn should_accept_zero_or_missing_fee() {
+        assert_eq!(validate_transaction_args(&None, &None, None, NOW), Ok(()));
+        assert_eq!(
+            validate_transaction_args(&Some(Nat::from(0_u8)), &None, None, NOW),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn should_reject_non_zero_fee() {
+        let error = validate_transaction_args(&Some(Nat::from(1_u8)), &None, None, NOW)
+            .unwrap_err();
+
+        assert_eq!(error, IcrcArgError::BadFee);
+        assert_eq!(
+            TransferError::from(error),
+            TransferError::BadFee {
+                expected_fee: Nat::from(0_u8)
+            }
+        );
+    }
+
+    #[test]
+    fn should_reject_too_long_memo() {
+        let memo = Memo::from(vec![0_u8; MAX_MEMO_LENGTH + 1]);
+
+        assert_eq!(
+            validate_transaction_args(&None, &Some(memo), None, NOW),
+            Err(IcrcArgError::MemoTooLong {
+                length: MAX_MEMO_LENGTH + 1
+            })
+        );
+        assert_eq!(
+            validate_transaction_args(
+                &None,
+                &Some(Memo::from(vec![0_u8; MAX_MEMO_LENGTH])),
+                None,
+                NOW
+            ),
+            Ok(())
+        );
+    }
+}
+
+mod find_duplicate {
+    use crate::icrc::find_duplicate;
+    use crate::numeric::Erc20Value;
+    use crate::state::dedup::{RecentTransfers, TransferKey, TransferKind};
+    use candid::Principal;
+    use icrc_ledger_types::icrc1::account::Account;
+    use icrc_ledger_types::icrc1::transfer::Memo;
+
+    const NOW: u64 = 1_700_000_000_000_000_000;
+
+    fn from() -> Account {
+        Account::from(Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap())
+    }
+
+    fn to() -> Account {
+        Account::from(Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap())
+    }
+
+    fn transfer_to() -> TransferKind {
+        TransferKind::Transfer {
+            to: to(),
+            spender: None,
+        }
+    }
+
+    fn recent_transfers() -> RecentTransfers {
+        let mut transfers = RecentTransfers::default();
+        transfers.record(
+            TransferKey {
+                created_at_time: NOW,
+                from: from(),
+                kind: transfer_to(),
+                amount: Erc20Value::from(100_u64),
+                memo: Some(vec![1, 2, 3]),
+            },
+            7,
+        );
+        transfers
+    }
+
+    #[test]
+    fn should_find_transfer_with_same_arguments() {
+        assert_eq!(
+            find_duplicate(
+                &recent_transfers(),
+                from(),
+                transfer_to(),
+                Erc20Value::from(100_u64),
+                &Some(Memo::from(vec![1, 2, 3])),
+                Some(NOW)
+            ),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn should_not_deduplicate_transfer_without_created_at_time() {
+        assert_eq!(
+            find_duplicate(
+                &recent_transfers(),
+                from(),
+                transfer_to(),
+                Erc20Value::from(100_u64),
+                &Some(Memo::from(vec![1, 2, 3])),
+                None
+            ),
+            None
+        );
+    }
+}
@@ -10,6 +10,7 @@ pub mod eth_rpc;
 pub mod eth_rpc_client;
 pub mod eth_rpc_error;
 pub mod guard;
+pub mod icrc;
 pub mod ledger_client;
 pub mod lifecycle;
 pub mod logs;
@@ -34,7 +35,9 @@ pub const MAIN_DERIVATION_PATH: Vec<ByteBuf> = vec![];
 // DEV: mode: 30
 // Mainnet mode: 10 * 60
 pub const SCRAPING_ETH_LOGS_INTERVAL: Duration = Duration::from_secs(10 * 60);
+pub const RETRY_SKIPPED_BLOCKS_INTERVAL: Duration = Duration::from_secs(60 * 60);
 // DEV: mode: 30
 // Mainnet mode: 15 * 60
 pub const PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL: Duration = Duration::from_secs(15 * 60);
 pub const PROCESS_ETH_RETRIEVE_TRANSACTIONS_RETRY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+pub const PROCESS_REIMBURSEMENT: Duration = Duration::from_secs(3 * 60);
@@ -9,6 +9,7 @@ use std::fmt::{Display, Formatter};
 mod tests;
 
 pub mod init;
+pub mod network;
 pub mod upgrade;
 
 pub use upgrade::post_upgrade;
@@ -42,11 +43,19 @@ impl EthereumNetwork {
         match self {
             EthereumNetwork::Mainnet => 1,
             EthereumNetwork::Sepolia => 11155111,
-            EthereumNetwork::BSC => 97,
-            EthereumNetwork::BSCTestnet => 56,
+            EthereumNetwork::BSC => 56,
+            EthereumNetwork::BSCTestnet => 97,
             EthereumNetwork::Local => 31337,
         }
     }
+
+    /// Symbol of the coin natively held on the network.
+    pub fn native_token_symbol(&self) -> &'static str {
+        match self {
+            EthereumNetwork::Mainnet | EthereumNetwork::Sepolia | EthereumNetwork::Local => "ETH",
+            EthereumNetwork::BSC | EthereumNetwork::BSCTestnet => "BNB",
+        }
+    }
 }
 
 impl TryFrom<u64> for EthereumNetwork {
@@ -56,6 +65,9 @@ impl TryFrom<u64> for EthereumNetwork {
         match value {
             1 => Ok(EthereumNetwork::Mainnet),
             11155111 => Ok(EthereumNetwork::Sepolia),
+            56 => Ok(EthereumNetwork::BSC),
+            97 => Ok(EthereumNetwork::BSCTestnet),
+            31337 => Ok(EthereumNetwork::Local),
             _ => Err("Unknown Ethereum Network".to_string()),
         }
     }
@@ -1,6 +1,7 @@
 use crate::endpoints::CandidBlockTag;
 use crate::erc20::CkTokenSymbol;
 use crate::eth_rpc::BlockTag;
+use crate::lifecycle::network::{EvmNetwork, EvmNetworkArg};
 use crate::lifecycle::EthereumNetwork;
 use crate::numeric::{BlockNumber, TransactionNonce, Wei};
 use crate::state::transactions::EthTransactions;
@@ -11,6 +12,7 @@ use candid::{CandidType, Deserialize};
 use ic_ethereum_types::Address;
 use minicbor::{Decode, Encode};
 use crate::numeric::Erc20Value;
+use std::collections::BTreeMap;
 
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
@@ -37,6 +39,9 @@ pub struct InitArg {
     pub ckerc20_token_symbol: String,
     #[cbor(n(11), with = "crate::cbor::nat::option")]
     pub withdraw_fee_value: Option<Nat>,
+    /// Overrides the defaults of `ethereum_network`.
+    #[n(12)]
+    pub network: Option<EvmNetworkArg>,
 }
 
 impl TryFrom<InitArg> for State {
@@ -54,6 +59,7 @@ impl TryFrom<InitArg> for State {
             ckerc20_token_address,
             ckerc20_token_symbol,
             withdraw_fee_value,
+            network,
         }: InitArg,
     ) -> Result<Self, Self::Error> {
         use std::str::FromStr;
@@ -96,15 +102,32 @@ impl TryFrom<InitArg> for State {
             Erc20Value::ZERO
         };
 
+        let mut ethereum_block_height = BlockTag::from(ethereum_block_height);
+        let evm_network = match network {
+            Some(arg) => {
+                if let Some(finality) = arg.finality.clone() {
+                    ethereum_block_height = finality.into();
+                }
+                EvmNetwork::try_from(arg)
+                    .map_err(|e| InvalidStateError::InvalidEvmNetwork(format!("ERROR: {}", e)))?
+            }
+            None => EvmNetwork::from(ethereum_network),
+        };
+
         let state = Self {
             admin,
-            ethereum_network,
+            roles: Default::default(),
+            proposals: Default::default(),
+            withdrawal_limits: Default::default(),
+            evm_network,
+            rpc_providers_set_by_admin: false,
             ecdsa_key_name,
             erc20_helper_contract_address: eth_helper_contract_address,
             pending_withdrawal_principals: Default::default(),
             eth_transactions: EthTransactions::new(initial_nonce),
             cketh_minimum_withdrawal_amount: minimum_withdrawal_amount,
-            ethereum_block_height: BlockTag::from(ethereum_block_height),
+            erc20_minimum_withdrawal_amounts: Default::default(),
+            ethereum_block_height,
             first_scraped_block_number,
             last_scraped_block_number,
             last_erc20_scraped_block_number: last_scraped_block_number,
@@ -113,14 +136,25 @@ impl TryFrom<InitArg> for State {
             minted_events: Default::default(),
             ecdsa_public_key: None,
             invalid_events: Default::default(),
+            reorged_events: Default::default(),
+            pending_deposits: Default::default(),
+            quarantined_deposits: Default::default(),
             eth_balance: Default::default(),
             skipped_blocks: Default::default(),
+            paused_flows: Default::default(),
             active_tasks: Default::default(),
+            last_pending_deposits_head: None,
             http_request_counter: 0,
             last_transaction_price_estimate: None,
+            provider_health: Default::default(),
+            rescrape: None,
             evm_rpc_id: None,
-            ckerc20_tokens: (ckerc20_token_address, ckerc20_token_symbol),
+            ckerc20_tokens: BTreeMap::from([(ckerc20_token_address, ckerc20_token_symbol)]),
+            default_erc20_token: ckerc20_token_address,
             erc20_balances: Default::default(),
+            erc20_allowances: Default::default(),
+            recent_erc20_transfers: Default::default(),
+            erc20_holds: Default::default(),
             withdraw_count: Nat::from(0u128),
             withdraw_fee_value,
         };
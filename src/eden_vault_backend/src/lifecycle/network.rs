@@ -0,0 +1,305 @@
+use crate::endpoints::CandidBlockTag;
+use crate::lifecycle::EthereumNetwork;
+use crate::numeric::{BlockNumber, Wei, WeiPerGas};
+use candid::{CandidType, Deserialize, Nat};
+use minicbor::{Decode, Encode};
+use std::fmt::{Debug, Formatter};
+
+/// Description of the EVM network the vault operates on,
+/// provided at init or upgrade time.
+///
+/// Any field left empty falls back to the defaults of the network
+/// with the same chain id, if it is one of [`EthereumNetwork`].
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
+pub struct EvmNetworkArg {
+    #[n(0)]
+    pub chain_id: u64,
+    #[n(1)]
+    pub name: String,
+    /// JSON-RPC endpoints queried by the vault.
+    /// Must be non-empty at init for networks that are not one of [`EthereumNetwork`].
+    /// When empty on upgrade, the current providers are kept. Once the admin set the providers
+    /// through `update_rpc_providers`, they can no longer be replaced on upgrade.
+    #[n(2)]
+    pub rpc_endpoints: Vec<String>,
+    /// Minimum number of endpoints that must agree on a response.
+    #[n(3)]
+    pub consensus_threshold: Option<u8>,
+    /// Block tag up to which deposits are considered final.
+    #[n(4)]
+    pub finality: Option<CandidBlockTag>,
+    #[n(5)]
+    pub native_token_symbol: Option<String>,
+    #[n(6)]
+    pub fee_policy: Option<FeePolicyArg>,
+    /// Number of blocks following a deposit, past the finality block tag,
+    /// before the deposit is verified against the canonical chain and credited.
+    #[n(7)]
+    pub deposit_confirmations: Option<u64>,
+    /// Show deposits in blocks that are not final yet as pending credits.
+    #[n(8)]
+    pub optimistic_deposits: Option<bool>,
+    /// Interval between two scrapes of the blocks that are not final yet, in seconds.
+    #[n(9)]
+    pub pending_deposits_scraping_interval_seconds: Option<u64>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Decode, Deserialize, Encode)]
+pub struct FeePolicyArg {
+    /// Lower bound on the max priority fee per gas of transactions sent by the vault.
+    #[cbor(n(0), with = "crate::cbor::nat::option")]
+    pub min_max_priority_fee_per_gas: Option<Nat>,
+    /// Lower bound on the minimum withdrawal amount of the native coin.
+    #[cbor(n(1), with = "crate::cbor::nat::option")]
+    pub native_transfer_fee: Option<Nat>,
+}
+
+/// JSON-RPC endpoint of a provider, queried in the order set by the admin.
+///
+/// Header values are recorded in plain text in the event log kept in stable memory,
+/// so that the providers survive upgrades. They are left out of `get_events`, the logs
+/// and `Debug`, but anyone able to read the stable memory of the canister can see them:
+/// only use API keys that are restricted to the calls made by the vault.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Decode, Encode)]
+pub struct RpcEndpoint {
+    #[n(0)]
+    pub url: String,
+    /// Headers sent with every request, e.g. to authenticate with an API key.
+    #[n(1)]
+    pub headers: Vec<RpcHeader>,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Decode, Encode)]
+pub struct RpcHeader {
+    #[n(0)]
+    pub name: String,
+    #[n(1)]
+    pub value: String,
+}
+
+impl Debug for RpcHeader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Header values are credentials and must not end up in the logs.
+        f.debug_struct("RpcHeader")
+            .field("name", &self.name)
+            .field("value", &"<redacted>")
+            .finish()
+    }
+}
+
+impl From<String> for RpcEndpoint {
+    fn from(url: String) -> Self {
+        Self {
+            url,
+            headers: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct EvmNetwork {
+    pub chain_id: u64,
+    pub name: String,
+    /// When empty, the built-in providers of the corresponding [`EthereumNetwork`] are used.
+    pub rpc_endpoints: Vec<RpcEndpoint>,
+    pub consensus_threshold: u8,
+    pub native_token_symbol: String,
+    pub fee_policy: FeePolicy,
+    /// Number of blocks following a deposit before it is verified against the canonical chain.
+    pub deposit_confirmations: u64,
+    /// Whether deposits seen at the `Latest` block tag are shown as pending credits until they are final.
+    pub optimistic_deposits: bool,
+    /// Interval between two scrapes of the blocks that are not final yet, in seconds.
+    pub pending_deposits_scraping_interval_seconds: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct FeePolicy {
+    pub min_max_priority_fee_per_gas: WeiPerGas,
+    pub native_transfer_fee: Wei,
+}
+
+impl EvmNetwork {
+    /// The network resulting from an upgrade with the given argument.
+    /// Without endpoints in the argument, the current providers are kept with their headers,
+    /// together with the current consensus threshold unless another one is given.
+    pub fn upgraded(&self, arg: EvmNetworkArg) -> Result<EvmNetwork, String> {
+        if !arg.rpc_endpoints.is_empty() {
+            return EvmNetwork::try_from(arg);
+        }
+        let consensus_threshold = arg.consensus_threshold.unwrap_or(self.consensus_threshold);
+        let network = EvmNetwork::try_from(EvmNetworkArg {
+            rpc_endpoints: self
+                .rpc_endpoints
+                .iter()
+                .map(|endpoint| endpoint.url.clone())
+                .collect(),
+            consensus_threshold: Some(consensus_threshold),
+            ..arg
+        })?;
+        Ok(EvmNetwork {
+            rpc_endpoints: self.rpc_endpoints.clone(),
+            ..network
+        })
+    }
+
+    /// The network with built-in defaults having the same chain id, if any.
+    pub fn known_network(&self) -> Option<EthereumNetwork> {
+        EthereumNetwork::try_from(self.chain_id).ok()
+    }
+
+    /// Whether a deposit in the given block is followed by enough blocks to be verified.
+    pub fn is_deposit_confirmed(
+        &self,
+        deposit_block_number: BlockNumber,
+        last_observed_block_number: BlockNumber,
+    ) -> bool {
+        deposit_block_number
+            .checked_add(BlockNumber::from(self.deposit_confirmations))
+            .is_some_and(|confirmed_at| confirmed_at <= last_observed_block_number)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chain_id == 0 {
+            return Err("chain_id must be positive".to_string());
+        }
+        if self.name.trim().is_empty() {
+            return Err("network name cannot be blank".to_string());
+        }
+        if self.native_token_symbol.trim().is_empty() {
+            return Err("native_token_symbol cannot be blank".to_string());
+        }
+        for endpoint in &self.rpc_endpoints {
+            if !endpoint.url.starts_with("https://") && !endpoint.url.starts_with("http://") {
+                return Err(format!("invalid RPC endpoint {}", endpoint.url));
+            }
+            if endpoint
+                .headers
+                .iter()
+                .any(|header| header.name.trim().is_empty())
+            {
+                return Err(format!(
+                    "header names of RPC endpoint {} cannot be blank",
+                    endpoint.url
+                ));
+            }
+        }
+        let mut urls: Vec<_> = self.rpc_endpoints.iter().map(|e| &e.url).collect();
+        urls.sort();
+        urls.dedup();
+        if urls.len() != self.rpc_endpoints.len() {
+            return Err("duplicate RPC endpoints".to_string());
+        }
+        if self.rpc_endpoints.is_empty() && self.known_network().is_none() {
+            return Err(format!(
+                "RPC endpoints are required for chain id {}",
+                self.chain_id
+            ));
+        }
+        if self.consensus_threshold == 0 {
+            return Err("consensus_threshold must be positive".to_string());
+        }
+        if self.pending_deposits_scraping_interval_seconds == 0 {
+            return Err("pending_deposits_scraping_interval_seconds must be positive".to_string());
+        }
+        if !self.rpc_endpoints.is_empty()
+            && usize::from(self.consensus_threshold) > self.rpc_endpoints.len()
+        {
+            return Err(format!(
+                "consensus_threshold {} exceeds the number of RPC endpoints {}",
+                self.consensus_threshold,
+                self.rpc_endpoints.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl From<EthereumNetwork> for EvmNetwork {
+    fn from(network: EthereumNetwork) -> Self {
+        let (consensus_threshold, native_transfer_fee, deposit_confirmations) = match network {
+            EthereumNetwork::Mainnet => (3, Wei::new(2_000_000_000_000), 12),
+            EthereumNetwork::Sepolia => (2, Wei::new(10_000_000_000), 12),
+            EthereumNetwork::Local => (1, Wei::new(1_000_000_000), 0),
+            EthereumNetwork::BSC => (3, Wei::new(2_000_000_000_000), 15),
+            EthereumNetwork::BSCTestnet => (2, Wei::new(2_000_000_000_000), 15),
+        };
+        // A few blocks, so that pending credits show up quickly without querying every block.
+        let pending_deposits_scraping_interval_seconds = match network {
+            EthereumNetwork::Mainnet | EthereumNetwork::Sepolia => 60,
+            EthereumNetwork::Local => 10,
+            EthereumNetwork::BSC | EthereumNetwork::BSCTestnet => 30,
+        };
+        Self {
+            chain_id: network.chain_id(),
+            name: network.to_string(),
+            rpc_endpoints: vec![],
+            consensus_threshold,
+            native_token_symbol: network.native_token_symbol().to_string(),
+            fee_policy: FeePolicy {
+                // average value between the `minSuggestedMaxPriorityFeePerGas`
+                // used by Metamask, see
+                // https://github.com/MetaMask/core/blob/f5a4f52e17f407c6411e4ef9bd6685aab184b91d/packages/gas-fee-controller/src/fetchGasEstimatesViaEthFeeHistory/calculateGasFeeEstimatesForPriorityLevels.ts#L14
+                min_max_priority_fee_per_gas: WeiPerGas::new(1_500_000_000), //1.5 gwei
+                native_transfer_fee,
+            },
+            deposit_confirmations,
+            optimistic_deposits: false,
+            pending_deposits_scraping_interval_seconds,
+        }
+    }
+}
+
+impl TryFrom<EvmNetworkArg> for EvmNetwork {
+    type Error = String;
+
+    fn try_from(
+        EvmNetworkArg {
+            chain_id,
+            name,
+            rpc_endpoints,
+            consensus_threshold,
+            finality: _,
+            native_token_symbol,
+            fee_policy,
+            deposit_confirmations,
+            optimistic_deposits,
+            pending_deposits_scraping_interval_seconds,
+        }: EvmNetworkArg,
+    ) -> Result<Self, Self::Error> {
+        let defaults = match EthereumNetwork::try_from(chain_id) {
+            Ok(network) => EvmNetwork::from(network),
+            // Other networks default to a majority of the endpoints and the local testnet fees.
+            Err(_) => EvmNetwork {
+                consensus_threshold: u8::try_from(rpc_endpoints.len() / 2 + 1).unwrap_or(u8::MAX),
+                ..EvmNetwork::from(EthereumNetwork::Local)
+            },
+        };
+        let fee_policy = fee_policy.unwrap_or_default();
+        let min_max_priority_fee_per_gas = match fee_policy.min_max_priority_fee_per_gas {
+            Some(fee) => WeiPerGas::try_from(fee).map_err(|e| format!("ERROR: {}", e))?,
+            None => defaults.fee_policy.min_max_priority_fee_per_gas,
+        };
+        let native_transfer_fee = match fee_policy.native_transfer_fee {
+            Some(fee) => Wei::try_from(fee).map_err(|e| format!("ERROR: {}", e))?,
+            None => defaults.fee_policy.native_transfer_fee,
+        };
+        let network = EvmNetwork {
+            chain_id,
+            name,
+            rpc_endpoints: rpc_endpoints.into_iter().map(RpcEndpoint::from).collect(),
+            consensus_threshold: consensus_threshold.unwrap_or(defaults.consensus_threshold),
+            native_token_symbol: native_token_symbol.unwrap_or(defaults.native_token_symbol),
+            fee_policy: FeePolicy {
+                min_max_priority_fee_per_gas,
+                native_transfer_fee,
+            },
+            deposit_confirmations: deposit_confirmations.unwrap_or(defaults.deposit_confirmations),
+            optimistic_deposits: optimistic_deposits.unwrap_or(defaults.optimistic_deposits),
+            pending_deposits_scraping_interval_seconds: pending_deposits_scraping_interval_seconds
+                .unwrap_or(defaults.pending_deposits_scraping_interval_seconds),
+        };
+        network.validate()?;
+        Ok(network)
+    }
+}
@@ -1,7 +1,9 @@
 use crate::endpoints::CandidBlockTag;
+use crate::lifecycle::network::EvmNetworkArg;
+use crate::icrc::blocks::certify_tip;
 use crate::logs::INFO;
 use crate::state::audit::{process_event, replay_events, EventType};
-use crate::state::mutate_state;
+use crate::state::{mutate_state, read_state};
 use crate::state::STATE;
 use crate::storage::total_event_count;
 use candid::{CandidType, Deserialize, Nat, Principal};
@@ -28,6 +30,80 @@ pub struct UpgradeArg {
     pub ckerc20_token_symbol: Option<String>,
     #[cbor(n(10), with = "crate::cbor::nat::option")]
     pub withdraw_fee_value: Option<Nat>,
+    #[n(11)]
+    pub network: Option<EvmNetworkArg>,
+    /// Replaces the withdrawal limits of the given tokens.
+    #[n(12)]
+    pub withdrawal_limits: Option<Vec<WithdrawalLimitsArg>>,
+    /// Replaces the minimum withdrawal amounts of the given tokens.
+    #[n(13)]
+    pub erc20_minimum_withdrawal_amounts: Option<Vec<Erc20MinimumWithdrawalAmountArg>>,
+    /// Native coins sent to the minter's address outside of the helper contract,
+    /// added to the gas reserve paying the transaction fees of ERC-20 withdrawals.
+    #[cbor(n(14), with = "crate::cbor::nat::option")]
+    pub gas_reserve_top_up: Option<Nat>,
+}
+
+impl UpgradeArg {
+    /// Fields that can only be changed through a proposal once a proposal policy is set.
+    pub fn sensitive_fields(&self) -> Vec<&'static str> {
+        let mut fields = vec![];
+        if self.withdraw_fee_value.is_some() {
+            fields.push("withdraw_fee_value");
+        }
+        if self.ckerc20_token_address.is_some() {
+            fields.push("ckerc20_token_address");
+        }
+        if self.ckerc20_token_symbol.is_some() {
+            fields.push("ckerc20_token_symbol");
+        }
+        if self.erc20_helper_contract_address.is_some() {
+            fields.push("erc20_helper_contract_address");
+        }
+        if self.withdrawal_limits.is_some() {
+            fields.push("withdrawal_limits");
+        }
+        if self.erc20_minimum_withdrawal_amounts.is_some() {
+            fields.push("erc20_minimum_withdrawal_amounts");
+        }
+        if self.gas_reserve_top_up.is_some() {
+            fields.push("gas_reserve_top_up");
+        }
+        if self
+            .network
+            .as_ref()
+            .is_some_and(|network| !network.rpc_endpoints.is_empty())
+        {
+            fields.push("network.rpc_endpoints");
+        }
+        fields
+    }
+}
+
+/// Minimum amount of a withdrawal of an ERC-20 token, in units of the token.
+/// A zero amount removes the minimum.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
+pub struct Erc20MinimumWithdrawalAmountArg {
+    #[n(0)]
+    pub erc20_contract_address: String,
+    #[cbor(n(1), with = "crate::cbor::nat")]
+    pub minimum_withdrawal_amount: Nat,
+}
+
+/// Caps on the withdrawals of an ERC-20 token, in units of the token.
+/// A token without any cap is not limited.
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Decode, Deserialize, Encode)]
+pub struct WithdrawalLimitsArg {
+    #[n(0)]
+    pub erc20_contract_address: String,
+    #[cbor(n(1), with = "crate::cbor::nat::option")]
+    pub max_per_withdrawal: Option<Nat>,
+    /// Cap on the withdrawals of a principal over the last 24 hours.
+    #[cbor(n(2), with = "crate::cbor::nat::option")]
+    pub max_per_principal_per_day: Option<Nat>,
+    /// Cap on the withdrawals of every principal over the last 24 hours.
+    #[cbor(n(3), with = "crate::cbor::nat::option")]
+    pub max_global_per_day: Option<Nat>,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArg>) {
@@ -36,7 +112,12 @@ pub fn post_upgrade(upgrade_args: Option<UpgradeArg>) {
     STATE.with(|cell| {
         *cell.borrow_mut() = Some(replay_events());
     });
+    // The certified data does not survive upgrades.
+    certify_tip();
     if let Some(args) = upgrade_args {
+        if let Err(e) = read_state(|s| s.validate_upgrade_without_proposal(&args)) {
+            ic_cdk::trap(&format!("ERROR: invalid upgrade argument: {:?}", e));
+        }
         mutate_state(|s| process_event(s, EventType::Upgrade(args)))
     }
 
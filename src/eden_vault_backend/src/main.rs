@@ -2,34 +2,67 @@ use crate::checked_amount::CheckedAmountOf;
 use candid::{Nat, Principal};
 use eden_vault_backend::address::{validate_address_as_destination, AddressValidationError};
 use eden_vault_backend::checked_amount;
-use eden_vault_backend::deposit::scrape_logs;
+use eden_vault_backend::deposit::{
+    rescrape_logs, retry_skipped_blocks, scrape_logs, scrape_pending_deposits,
+};
+use eden_vault_backend::erc20;
 use eden_vault_backend::endpoints::ckerc20::{
-    RetrieveErc20Request, WithdrawErc20Arg, WithdrawErc20Error,
+    CancelWithdrawalError, CancelledWithdrawal, RemainingWithdrawalLimit, RetrieveErc20Request,
+    WithdrawErc20Arg, WithdrawErc20Error,
+};
+use eden_vault_backend::endpoints::events::{
+    EventSource as CandidEventSource, GetEventsArg, GetEventsResult,
+    ReimbursementIndex as CandidReimbursementIndex,
 };
-use eden_vault_backend::endpoints::events::{GetEventsArg, GetEventsResult};
 use eden_vault_backend::endpoints::{
-    RetrieveEthStatus, WithdrawalDetail, WithdrawalSearchParameter,
+    AddCkErc20Token, CkErc20Token, Erc20BalanceDetails, EthBalanceDetails, FundGasReserveArg, GasReserve, PendingProposal, ProposalActionArg, RetrieveEthRequest, RetrieveEthStatus, ProviderHealthInfo, ProviderHealthReport, QuarantineResolution, QuarantinedDepositDetails, QuarantinedDepositInfo, QuarantinedReimbursementDetails, QuarantinedReimbursementInfo, RescrapeBlocksArg, RescrapeStatus, ResolveQuarantinedDepositArg, ResolveQuarantinedReimbursementArg, Role, RoleHolder, RpcMethodThreshold, VaultFlow, StandardRecord, UpdateRpcProvidersArg, WithdrawalArg, WithdrawalDetail, WithdrawalError, WithdrawalSearchParameter,
 };
-use eden_vault_backend::eth_logs::{EventSource, ReceivedErc20Event};
-use eden_vault_backend::guard::retrieve_withdraw_guard;
+use eden_vault_backend::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEthEvent, ReceivedEvent};
+use eden_vault_backend::eth_rpc_client::health::RpcMethod;
+use eden_vault_backend::eth_rpc_client::provider_ids;
+use eden_vault_backend::guard::{ensure_not_paused, retrieve_withdraw_guard, BalanceHold};
+use eden_vault_backend::icrc::blocks;
+use eden_vault_backend::icrc::{
+    find_duplicate, validate_transaction_args, ERC20_DECIMALS, ICRC_FEE, SELF_APPROVAL_ERROR_CODE,
+};
+use eden_vault_backend::lifecycle::network::RpcEndpoint;
 use eden_vault_backend::lifecycle::MinterArg;
 use eden_vault_backend::logs::INFO;
-use eden_vault_backend::numeric::{Erc20Value, Erc20Tag, Wei, LedgerBurnIndex};
+use eden_vault_backend::numeric::{BlockNumber, Erc20Value, Erc20Tag, Wei, LedgerBurnIndex, LogIndex};
 use eden_vault_backend::state::audit::{process_event, EventType, Event};
-use eden_vault_backend::state::transactions::{Erc20WithdrawalRequest, ReimbursementIndex, Subaccount};
+use eden_vault_backend::state::dedup::TransferKind;
+use eden_vault_backend::state::transactions::{
+    Erc20WithdrawalRequest, EthWithdrawalRequest, ReimbursementIndex, Subaccount,
+    WithdrawalRequest,
+};
+use eden_vault_backend::state::proposals::ProposalAction;
+use eden_vault_backend::state::roles::Permission;
 use eden_vault_backend::state::{
-    lazy_call_ecdsa_public_key, mutate_state, read_state, transactions, State, STATE,
+    lazy_call_ecdsa_public_key, mutate_state, read_state, transactions, Rescrape, State, STATE,
 };
 use eden_vault_backend::tx::lazy_refresh_gas_fee_estimate;
 use eden_vault_backend::withdraw::{
-    process_retrieve_eth_requests, CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
+    process_reimbursement, process_retrieve_eth_requests, CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
 };
 use eden_vault_backend::{
-    state, storage, PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, SCRAPING_ETH_LOGS_INTERVAL,
+    state, storage, PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, PROCESS_REIMBURSEMENT,
+    RETRY_SKIPPED_BLOCKS_INTERVAL, SCRAPING_ETH_LOGS_INTERVAL,
 };
 use ic_canister_log::log;
 use ic_cdk::{init, post_upgrade, pre_upgrade, query, update};
 use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc::generic_metadata_value::MetadataValue;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, ICRC3ArchiveInfo};
+use icrc_ledger_types::icrc3::blocks::{
+    GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate, SupportedBlockType,
+};
+use minicbor::bytes::ByteVec;
+use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::str::FromStr;
 use std::time::Duration;
@@ -37,6 +70,37 @@ use std::time::Duration;
 pub const SEPOLIA_TEST_CHAIN_ID: u64 = 11155111;
 pub const CKETH_LEDGER_TRANSACTION_FEE: Wei = Wei::new(2_000_000_000_000_u128);
 
+fn validate_caller_has_permission(permission: Permission) -> Result<Principal, String> {
+    let caller = validate_caller_not_anonymous();
+    if !read_state(|s| s.has_permission(&caller, permission)) {
+        return Err(format!("ERROR: {} is not allowed to {}.", caller, permission));
+    }
+    Ok(caller)
+}
+
+/// Traps unless the caller holds the permission, for queries that do not return a `Result`.
+fn ensure_caller_has_permission(permission: Permission) {
+    if let Err(error) = validate_caller_has_permission(permission) {
+        ic_cdk::trap(&error);
+    }
+}
+
+fn validate_caller_is_admin() -> Result<Principal, String> {
+    let caller = validate_caller_not_anonymous();
+    if read_state(|s| s.admin != caller) {
+        return Err(format!("ERROR: {} is not the admin.", caller));
+    }
+    Ok(caller)
+}
+
+fn validate_caller_is_approver() -> Result<Principal, String> {
+    let caller = validate_caller_not_anonymous();
+    if !read_state(|s| s.proposals.is_approver(&caller, &s.admin)) {
+        return Err(format!("ERROR: {} is not an approver of proposals.", caller));
+    }
+    Ok(caller)
+}
+
 fn validate_caller_not_anonymous() -> candid::Principal {
     let principal = ic_cdk::caller();
     if principal == candid::Principal::anonymous() {
@@ -55,9 +119,21 @@ fn setup_timers() {
     // Start scraping logs immediately after the install, then repeat with the interval.
     ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(scrape_logs()));
     ic_cdk_timers::set_timer_interval(SCRAPING_ETH_LOGS_INTERVAL, || ic_cdk::spawn(scrape_logs()));
+    if read_state(State::optimistic_deposits_enabled) {
+        let interval = read_state(|s| s.evm_network().pending_deposits_scraping_interval_seconds);
+        ic_cdk_timers::set_timer_interval(Duration::from_secs(interval), || {
+            ic_cdk::spawn(scrape_pending_deposits())
+        });
+    }
     ic_cdk_timers::set_timer_interval(PROCESS_ETH_RETRIEVE_TRANSACTIONS_INTERVAL, || {
         ic_cdk::spawn(process_retrieve_eth_requests())
     });
+    ic_cdk_timers::set_timer_interval(PROCESS_REIMBURSEMENT, || {
+        ic_cdk::spawn(process_reimbursement())
+    });
+    ic_cdk_timers::set_timer_interval(RETRY_SKIPPED_BLOCKS_INTERVAL, || {
+        ic_cdk::spawn(retry_skipped_blocks())
+    });
 }
 
 #[init]
@@ -134,12 +210,23 @@ fn withdrawal_status(parameter: WithdrawalSearchParameter) -> Vec<WithdrawalDeta
                 withdrawal_id: request.get_withdrawal_id(),
                 recipient_address: request.payee().to_string(),
                 token_symbol: match request {
-                    CkErc20(_r) => s.ckerc20_tokens.1.to_string(),
+                    CkEth(_) => s.evm_network.native_token_symbol.clone(),
+                    CkErc20(r) => s
+                        .ckerc20_tokens
+                        .get(&r.token())
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
                 },
                 withdrawal_amount: match request {
+                    CkEth(r) => r.withdrawal_amount.into(),
                     CkErc20(r) => r.withdrawal_amount.into(),
                 },
                 max_transaction_fee: match (request, tx) {
+                    (CkEth(_), None) => None,
+                    (CkEth(r), Some(tx)) => r
+                        .withdrawal_amount
+                        .checked_sub(tx.amount)
+                        .map(|fee| fee.into()),
                     (CkErc20(r), _) => Some(r.max_transaction_fee.into()),
                 },
                 from: request.from(),
@@ -155,15 +242,25 @@ fn withdrawal_status(parameter: WithdrawalSearchParameter) -> Vec<WithdrawalDeta
 
 #[update]
 async fn withdraw_erc20(
-    WithdrawErc20Arg { amount, recipient }: WithdrawErc20Arg,
+    WithdrawErc20Arg {
+        amount,
+        recipient,
+        from_subaccount,
+        erc20_contract_address,
+    }: WithdrawErc20Arg,
 ) -> Result<RetrieveErc20Request, WithdrawErc20Error> {
     let caller = validate_caller_not_anonymous();
+    ensure_not_paused(VaultFlow::Withdrawals).map_err(|_| WithdrawErc20Error::Paused)?;
     let _guard = retrieve_withdraw_guard(caller).unwrap_or_else(|e| {
         ic_cdk::trap(&format!(
             "Failed retrieving guard for principal {}: {:?}",
             caller, e
         ))
     });
+    let from = Account {
+        owner: caller,
+        subaccount: from_subaccount,
+    };
 
     let destination = validate_address_as_destination(&recipient).map_err(|e| match e {
         AddressValidationError::Invalid { .. } | AddressValidationError::NotSupported(_) => {
@@ -173,72 +270,239 @@ async fn withdraw_erc20(
             address: address.to_string(),
         },
     })?;
+    let token = resolve_erc20_token(erc20_contract_address).ok_or_else(|| {
+        WithdrawErc20Error::TokenNotSupported {
+            supported_tokens: get_erc20_tokens(),
+        }
+    })?;
     let ckerc20_withdrawal_amount =
         Erc20Value::try_from(amount).expect("ERROR: failed to convert Nat to u256");
+    let min_withdrawal_amount = read_state(|s| s.erc20_minimum_withdrawal_amount(&token));
+    if ckerc20_withdrawal_amount < min_withdrawal_amount {
+        return Err(WithdrawErc20Error::AmountTooLow {
+            min_withdrawal_amount: Nat::from(min_withdrawal_amount),
+        });
+    }
 
-    let withdraw_fee = read_state(|s| s.withdraw_fee_value.clone());
-    let withdraw_fee = Erc20Value::try_from(withdraw_fee).expect("ERROR: failed to convert Nat to u256");
+    let withdraw_fee = read_state(|s| s.withdraw_fee_value);
 
     let total_amount_needed = ckerc20_withdrawal_amount
     .checked_add(withdraw_fee)
     .expect("BUG: Overflow when calculating total amount needed");
 
-    let caller_balance = read_state(|s| s.erc20_balances.balance_of(&caller));
-    if caller_balance < total_amount_needed {
-        return Err(WithdrawErc20Error::InsufficientFunds {
-            available: Nat::from(caller_balance),
+    // Reserve the funds before awaiting, so that they cannot be spent in the meantime.
+    let hold = BalanceHold::new(token, from, total_amount_needed).map_err(|available| {
+        WithdrawErc20Error::InsufficientFunds {
+            available: Nat::from(available),
             required: Nat::from(total_amount_needed),
-        });
-    }
+        }
+    })?;
 
     let erc20_tx_fee = estimate_erc20_transaction_fee().await.ok_or_else(|| {
         WithdrawErc20Error::TemporarilyUnavailable("Failed to retrieve current gas fee".to_string())
     })?;
 
-    mutate_state(|s| {
-        s.erc20_balances
-        .principal_erc20_sub(caller, withdraw_fee);
-        s.erc20_balances
-        .principal_erc20_add(s.admin.clone(), withdraw_fee);
-    });
+    // The transaction fee is paid by the minter's address, which also holds the native
+    // deposits, so it must be covered by the gas reserve instead of the users' funds.
+    if read_state(|s| s.eth_balance().available_gas_reserve()) < erc20_tx_fee {
+        return Err(WithdrawErc20Error::TemporarilyUnavailable(
+            "Insufficient gas reserve to pay the transaction fee".to_string(),
+        ));
+    }
+
+    // Checked after awaiting, so that withdrawals accepted in the meantime count against the limits.
+    read_state(|s| {
+        s.withdrawal_limits.check(
+            &token,
+            &caller,
+            ckerc20_withdrawal_amount,
+            ic_cdk::api::time(),
+        )
+    })
+    .map_err(|e| WithdrawErc20Error::LimitExceeded {
+        limit: Nat::from(e.limit),
+        window: e.window,
+        remaining: Nat::from(e.remaining),
+    })?;
 
-    let ckerc20_tokens = read_state(|s| s.ckerc20_tokens.clone());
+    let token_symbol = read_state(|s| s.ckerc20_tokens[&token].clone());
     log!(
         INFO,
         "[withdraw_erc20]: burning {} {}",
         ckerc20_withdrawal_amount,
-        ckerc20_tokens.1
+        token_symbol
     );
-    mutate_state(|s| {
-        s.erc20_balances
-            .principal_erc20_sub(caller.into(), ckerc20_withdrawal_amount);
+
+    Ok(hold.commit(|s| {
+        let withdrawal_request = Erc20WithdrawalRequest {
+            max_transaction_fee: erc20_tx_fee,
+            withdrawal_amount: ckerc20_withdrawal_amount,
+            destination,
+            from: caller,
+            from_subaccount: Subaccount::from_icrc(from_subaccount),
+            created_at: ic_cdk::api::time(),
+            id: s.withdraw_count.clone() + Nat::from(1_u8),
+            withdrawal_fee: Some(withdraw_fee),
+            erc20_contract_address: Some(token),
+            fee_recipient: Some(Account::from(s.admin)),
+        };
+        log!(
+            INFO,
+            "[withdraw_erc20]: queuing withdrawal request {:?}",
+            withdrawal_request
+        );
+        process_event(
+            s,
+            EventType::AcceptedErc20WithdrawalRequest(withdrawal_request.clone()),
+        );
+        RetrieveErc20Request::from(withdrawal_request)
+    }))
+}
+
+/// What the caller can still withdraw of the token, the vault default token if not set,
+/// under each of its limits. Empty when the token is not limited.
+#[query]
+fn remaining_withdrawal_limits(erc20_contract_address: Option<String>) -> Vec<RemainingWithdrawalLimit> {
+    let caller = validate_caller_not_anonymous();
+    let Some(token) = resolve_erc20_token(erc20_contract_address) else {
+        return vec![];
+    };
+    let now = ic_cdk::api::time();
+    read_state(|s| {
+        s.withdrawal_limits
+            .remaining(&token, &caller, now)
+            .into_iter()
+            .map(|(window, limit, remaining)| RemainingWithdrawalLimit {
+                window,
+                limit: Nat::from(limit),
+                remaining: Nat::from(remaining),
+            })
+            .collect()
+    })
+}
+
+#[update]
+fn withdraw_native(
+    WithdrawalArg {
+        amount,
+        recipient,
+        from_subaccount,
+    }: WithdrawalArg,
+) -> Result<RetrieveEthRequest, WithdrawalError> {
+    let caller = validate_caller_not_anonymous();
+    ensure_not_paused(VaultFlow::Withdrawals).map_err(|_| WithdrawalError::Paused)?;
+    let _guard = retrieve_withdraw_guard(caller).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!(
+            "Failed retrieving guard for principal {}: {:?}",
+            caller, e
+        ))
     });
 
-    let withdrawal_request = Erc20WithdrawalRequest {
-        max_transaction_fee: erc20_tx_fee,
-        withdrawal_amount: ckerc20_withdrawal_amount,
-        destination,
-        from: caller,
-        from_subaccount: None,
-        created_at: ic_cdk::api::time(),
-        // TODO
-        id: mutate_state(|s| {
-            s.withdraw_count += 1_u128;
-            s.withdraw_count.clone()
-        }),
+    let destination = validate_address_as_destination(&recipient).map_err(|e| match e {
+        AddressValidationError::Invalid { .. } | AddressValidationError::NotSupported(_) => {
+            ic_cdk::trap(&e.to_string())
+        }
+        AddressValidationError::Blocked(address) => WithdrawalError::RecipientAddressBlocked {
+            address: address.to_string(),
+        },
+    })?;
+    let withdrawal_amount = Wei::try_from(amount).expect("ERROR: failed to convert Nat to u256");
+    let min_withdrawal_amount = read_state(|s| s.cketh_minimum_withdrawal_amount);
+    if withdrawal_amount < min_withdrawal_amount {
+        return Err(WithdrawalError::AmountTooLow {
+            min_withdrawal_amount: min_withdrawal_amount.into(),
+        });
+    }
+
+    // The transaction fee is deducted from the withdrawn amount when the transaction is created,
+    // so nothing needs to be awaited before debiting the balance.
+    let from = Account {
+        owner: caller,
+        subaccount: from_subaccount,
     };
-    log!(
-        INFO,
-        "[withdraw_erc20]: queuing withdrawal request {:?}",
-        withdrawal_request
-    );
     mutate_state(|s| {
+        let balance = s.eth_balance().balance_of(&from);
+        if balance < withdrawal_amount {
+            return Err(WithdrawalError::InsufficientFunds {
+                balance: balance.into(),
+            });
+        }
+        if s.eth_balance().withdrawable_balance() < withdrawal_amount {
+            return Err(WithdrawalError::TemporarilyUnavailable(
+                "Withdrawal would use the gas reserve of ERC-20 withdrawals".to_string(),
+            ));
+        }
+        let withdrawal_request = EthWithdrawalRequest {
+            withdrawal_amount,
+            destination,
+            ledger_burn_index: s.withdraw_count.clone() + Nat::from(1_u8),
+            from: caller,
+            from_subaccount: Subaccount::from_icrc(from_subaccount),
+            created_at: Some(ic_cdk::api::time()),
+        };
+        log!(
+            INFO,
+            "[withdraw_native]: queuing withdrawal request {:?}",
+            withdrawal_request
+        );
         process_event(
             s,
-            EventType::AcceptedErc20WithdrawalRequest(withdrawal_request.clone()),
+            EventType::AcceptedEthWithdrawalRequest(withdrawal_request.clone()),
         );
+        Ok(RetrieveEthRequest::from(withdrawal_request))
+    })
+}
+
+/// Gives back the amount of a withdrawal request that is still pending.
+/// The withdrawal fee is never refunded: it was paid to its recipient when the request was accepted.
+#[update]
+fn cancel_withdrawal(withdrawal_id: Nat) -> Result<CancelledWithdrawal, CancelWithdrawalError> {
+    let caller = validate_caller_not_anonymous();
+    let _guard = retrieve_withdraw_guard(caller).unwrap_or_else(|e| {
+        ic_cdk::trap(&format!(
+            "Failed retrieving guard for principal {}: {:?}",
+            caller, e
+        ))
     });
-    Ok(RetrieveErc20Request::from(withdrawal_request))
+
+    mutate_state(|s| {
+        let request = match s
+            .eth_transactions
+            .find_pending_withdrawal_request(&withdrawal_id)
+        {
+            Some(request) => request.clone(),
+            None => {
+                return Err(match s.eth_transactions.transaction_status(&withdrawal_id) {
+                    RetrieveEthStatus::NotFound => CancelWithdrawalError::NotFound,
+                    status => CancelWithdrawalError::AlreadyProcessing { status },
+                })
+            }
+        };
+        if request.from() != caller {
+            return Err(CancelWithdrawalError::NotOwner);
+        }
+        let refunded_amount = match &request {
+            WithdrawalRequest::CkEth(request) => Nat::from(request.withdrawal_amount),
+            WithdrawalRequest::CkErc20(request) => Nat::from(request.withdrawal_amount),
+        };
+        log!(
+            INFO,
+            "[cancel_withdrawal]: cancelling withdrawal request {:?}",
+            request
+        );
+        process_event(
+            s,
+            EventType::CancelledWithdrawalRequest {
+                withdrawal_id: withdrawal_id.clone(),
+                refunded_fee: None,
+            },
+        );
+        Ok(CancelledWithdrawal {
+            id: withdrawal_id,
+            refunded_amount,
+            refunded_fee: None,
+        })
+    })
 }
 
 async fn estimate_erc20_transaction_fee() -> Option<Wei> {
@@ -258,6 +522,102 @@ fn is_address_blocked(address_string: String) -> bool {
     eden_vault_backend::blocklist::is_blocked(&address)
 }
 
+/// Maximum number of addresses imported at once into the runtime blocklist.
+const MAX_BLOCKLIST_IMPORT: usize = 5_000;
+
+/// Adds the addresses that are not blocked yet to the runtime blocklist, returning how many were added.
+fn record_blocked_addresses(addresses: Vec<String>, reason: String) -> Result<usize, String> {
+    if reason.trim().is_empty() {
+        return Err("ERROR: a reason is required.".to_string());
+    }
+    let mut parsed = BTreeSet::new();
+    let mut invalid = vec![];
+    for address in addresses {
+        match Address::from_str(&address) {
+            Ok(address) => {
+                parsed.insert(address);
+            }
+            Err(_) => invalid.push(address),
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(format!("ERROR: invalid addresses {:?}.", invalid));
+    }
+    let addresses: Vec<Address> = parsed
+        .into_iter()
+        .filter(|address| !eden_vault_backend::blocklist::is_blocked(address))
+        .collect();
+    let count = addresses.len();
+    if count > 0 {
+        log!(INFO, "[block_addresses]: blocking {count} addresses: {reason}");
+        mutate_state(|s| process_event(s, EventType::BlockedAddresses { addresses, reason }));
+    }
+    Ok(count)
+}
+
+#[update]
+fn block_address(address: String, reason: String) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageBlocklist)?;
+    match record_blocked_addresses(vec![address.clone()], reason)? {
+        0 => Ok(format!("Address {} was already blocked.", address)),
+        _ => Ok(format!("Address {} blocked.", address)),
+    }
+}
+
+/// Blocks many addresses at once, e.g. when importing an updated sanctions list.
+/// Addresses that are already blocked are skipped.
+#[update]
+fn import_blocklist(addresses: Vec<String>, reason: String) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageBlocklist)?;
+    if addresses.len() > MAX_BLOCKLIST_IMPORT {
+        return Err(format!(
+            "ERROR: cannot import more than {} addresses at once.",
+            MAX_BLOCKLIST_IMPORT
+        ));
+    }
+    let count = record_blocked_addresses(addresses, reason)?;
+    Ok(format!("{} addresses blocked.", count))
+}
+
+#[update]
+fn unblock_address(address: String, reason: String) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageBlocklist)?;
+    if reason.trim().is_empty() {
+        return Err("ERROR: a reason is required.".to_string());
+    }
+    let address =
+        Address::from_str(&address).map_err(|e| format!("ERROR: invalid address: {}", e))?;
+    if eden_vault_backend::blocklist::is_statically_blocked(&address) {
+        return Err(format!(
+            "ERROR: {} is in the static blocklist, which can only be changed by an upgrade.",
+            address
+        ));
+    }
+    if !eden_vault_backend::storage::is_address_in_blocklist(&address) {
+        return Err(format!("ERROR: {} is not blocked.", address));
+    }
+    log!(INFO, "[unblock_address]: unblocking {address}: {reason}");
+    mutate_state(|s| {
+        process_event(
+            s,
+            EventType::UnblockedAddresses {
+                addresses: vec![address],
+                reason,
+            },
+        )
+    });
+    Ok(format!("Address {} unblocked.", address))
+}
+
+/// Addresses blocked at runtime, on top of the static blocklist.
+#[query]
+fn get_blocklist() -> Vec<String> {
+    eden_vault_backend::storage::blocklist()
+        .into_iter()
+        .map(|address| address.to_string())
+        .collect()
+}
+
 #[update]
 async fn get_canister_status() -> ic_cdk::api::management_canister::main::CanisterStatusResponse {
     ic_cdk::api::management_canister::main::canister_status(
@@ -273,15 +633,13 @@ async fn get_canister_status() -> ic_cdk::api::management_canister::main::Canist
 #[query]
 fn get_events(arg: GetEventsArg) -> GetEventsResult {
     use eden_vault_backend::endpoints::events::{
-        AccessListItem, ReimbursementIndex as CandidReimbursementIndex,
-        TransactionReceipt as CandidTransactionReceipt,
+        AccessListItem, TransactionReceipt as CandidTransactionReceipt,
         TransactionStatus as CandidTransactionStatus, UnsignedTransaction,
-        Event as CandidEvent,
+        Event as CandidEvent, RpcProvider,
     };
     use eden_vault_backend::eth_rpc_client::responses::TransactionReceipt;
     use eden_vault_backend::tx::Eip1559TransactionRequest;
     use serde_bytes::ByteBuf;
-    use eden_vault_backend::endpoints::events::EventSource as CandidEventSource;
 
     const MAX_EVENTS_PER_RESPONSE: u64 = 100;
 
@@ -299,6 +657,9 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
 
     fn map_reimbursement_index(index: ReimbursementIndex) -> CandidReimbursementIndex {
         match index {
+            ReimbursementIndex::CkEth { ledger_burn_index } => {
+                CandidReimbursementIndex::CkEth { ledger_burn_index }
+            }
             ReimbursementIndex::CkErc20 {
                 withdrawal_id,
             } => CandidReimbursementIndex::CkErc20 {
@@ -356,6 +717,23 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
             payload : match payload {
                 EventType::Init(args) => EP::Init(args),
                 EventType::Upgrade(args) => EP::Upgrade(args),
+                EventType::AcceptedDeposit(ReceivedEthEvent {
+                    transaction_hash,
+                    block_number,
+                    log_index,
+                    from_address,
+                    value,
+                    principal,
+                    block_hash,
+                }) => EP::AcceptedDeposit {
+                    transaction_hash: transaction_hash.to_string(),
+                    block_number: block_number.into(),
+                    log_index: log_index.into(),
+                    from_address: from_address.to_string(),
+                    value: value.into(),
+                    principal,
+                    block_hash: block_hash.map(|hash| hash.to_string()),
+                },
                 EventType::AcceptedErc20Deposit(ReceivedErc20Event {
                     transaction_hash,
                     block_number,
@@ -364,6 +742,8 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     value,
                     principal,
                     erc20_contract_address,
+                    subaccount,
+                    block_hash,
                 }) => EP::AcceptedErc20Deposit {
                     transaction_hash: transaction_hash.to_string(),
                     block_number: block_number.into(),
@@ -372,6 +752,8 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     value: value.into(),
                     principal,
                     erc20_contract_address: erc20_contract_address.to_string(),
+                    subaccount: subaccount.map(Subaccount::to_bytes),
+                    block_hash: block_hash.map(|hash| hash.to_string()),
                 },
                 EventType::InvalidDeposit {
                     event_source,
@@ -422,6 +804,79 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     contract_address: Some(contract_address.to_string()),
                     block_number: block_number.into(),
                 },
+                EventType::UpdatedPause { flow, paused } => EP::UpdatedPause { flow, paused },
+                EventType::GrantedRole { principal, role } => EP::GrantedRole { principal, role },
+                EventType::RevokedRole { principal, role } => EP::RevokedRole { principal, role },
+                EventType::UpdatedAdmin { admin } => EP::UpdatedAdmin { admin },
+                EventType::CreatedProposal {
+                    id,
+                    proposer,
+                    action,
+                    executable_at,
+                } => EP::CreatedProposal {
+                    id,
+                    proposer,
+                    action: action.into(),
+                    executable_at,
+                },
+                EventType::ApprovedProposal { id, approver } => {
+                    EP::ApprovedProposal { id, approver }
+                }
+                EventType::ApprovedProposalCancellation { id, approver } => {
+                    EP::ApprovedProposalCancellation { id, approver }
+                }
+                EventType::ExecutedProposal { id, executed_by } => {
+                    EP::ExecutedProposal { id, executed_by }
+                }
+                EventType::CancelledProposal { id, cancelled_by } => {
+                    EP::CancelledProposal { id, cancelled_by }
+                }
+                EventType::BlockedAddresses { addresses, reason } => EP::BlockedAddresses {
+                    addresses: addresses.iter().map(|address| address.to_string()).collect(),
+                    reason,
+                },
+                EventType::UnblockedAddresses { addresses, reason } => EP::UnblockedAddresses {
+                    addresses: addresses.iter().map(|address| address.to_string()).collect(),
+                    reason,
+                },
+                EventType::FundedGasReserve {
+                    from,
+                    from_subaccount,
+                    amount,
+                } => EP::FundedGasReserve {
+                    from,
+                    from_subaccount: from_subaccount.map(Subaccount::to_bytes),
+                    amount: amount.into(),
+                },
+                EventType::RejectedWithdrawalRequest {
+                    withdrawal_id,
+                    destination,
+                } => EP::RejectedWithdrawalRequest {
+                    withdrawal_id,
+                    destination: destination.to_string(),
+                },
+                EventType::RetriedSkippedBlock {
+                    contract_address,
+                    block_number,
+                } => EP::RetriedSkippedBlock {
+                    contract_address: contract_address.to_string(),
+                    block_number: block_number.into(),
+                },
+                EventType::AcceptedEthWithdrawalRequest(EthWithdrawalRequest {
+                    withdrawal_amount,
+                    destination,
+                    ledger_burn_index,
+                    from,
+                    from_subaccount,
+                    created_at,
+                }) => EP::AcceptedEthWithdrawalRequest {
+                    withdrawal_amount: withdrawal_amount.into(),
+                    destination: destination.to_string(),
+                    withdrawal_id: ledger_burn_index,
+                    from,
+                    from_subaccount: from_subaccount.map(Subaccount::to_bytes),
+                    created_at,
+                },
                 EventType::AcceptedErc20WithdrawalRequest(Erc20WithdrawalRequest {
                     max_transaction_fee,
                     withdrawal_amount,
@@ -429,7 +884,10 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     from,
                     from_subaccount,
                     created_at,
-                    id
+                    id,
+                    withdrawal_fee,
+                    erc20_contract_address,
+                    fee_recipient,
                 }) => EP::AcceptedErc20WithdrawalRequest {
                     max_transaction_fee: max_transaction_fee.into(),
                     withdrawal_amount: withdrawal_amount.into(),
@@ -437,7 +895,17 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     from,
                     from_subaccount: from_subaccount.map(Subaccount::to_bytes),
                     created_at,
-                    withdrawal_id: id
+                    withdrawal_id: id,
+                    withdrawal_fee: withdrawal_fee.map(|fee| fee.into()),
+                    erc20_contract_address: erc20_contract_address.map(|address| address.to_string()),
+                    fee_recipient: fee_recipient.map(|account| account.owner),
+                    fee_recipient_subaccount: fee_recipient.and_then(|account| account.subaccount),
+                },
+                EventType::AddedCkErc20Token(token) => EP::AddedCkErc20Token {
+                    chain_id: token.erc20_chain_id.into(),
+                    address: token.erc20_contract_address.to_string(),
+                    erc20_token_symbol: token.ckerc20_token_symbol.to_string(),
+                    erc20_ledger_id: token.ckerc20_ledger_id,
                 },
                 EventType::MintedCkErc20 {
                     event_source,
@@ -448,16 +916,144 @@ fn get_events(arg: GetEventsArg) -> GetEventsResult {
                     principal,
                     amount: amount.into()
                 },
+                EventType::MintedCkEth {
+                    event_source,
+                    principal,
+                    amount,
+                } => EP::MintedCkEth {
+                    event_source: map_event_source(event_source),
+                    principal,
+                    amount: amount.into(),
+                },
                 EventType::QuarantinedDeposit { event_source } => EP::QuarantinedDeposit {
                     event_source: map_event_source(event_source),
                 },
+                EventType::ReorgedDeposit {
+                    event_source,
+                    canonical_block_hash,
+                } => EP::ReorgedDeposit {
+                    event_source: map_event_source(event_source),
+                    canonical_block_hash: canonical_block_hash.map(|hash| hash.to_string()),
+                },
+                EventType::CancelledWithdrawalRequest {
+                    withdrawal_id,
+                    refunded_fee,
+                } => EP::CancelledWithdrawalRequest {
+                    withdrawal_id,
+                    refunded_fee: refunded_fee.map(|fee| fee.into()),
+                },
+                EventType::ReimbursedEthWithdrawal(reimbursed) => EP::ReimbursedEthWithdrawal {
+                    withdrawal_id: reimbursed.burn_in_block,
+                    reimbursed_in_block: reimbursed.reimbursed_in_block.get().into(),
+                    reimbursed_amount: reimbursed.reimbursed_amount.into(),
+                    transaction_hash: reimbursed.transaction_hash.map(|h| h.to_string()),
+                },
+                EventType::ReimbursedErc20Withdrawal {
+                    withdrawal_id,
+                    reimbursed,
+                } => EP::ReimbursedErc20Withdrawal {
+                    withdrawal_id,
+                    burn_in_block: reimbursed.burn_in_block,
+                    reimbursed_in_block: reimbursed.reimbursed_in_block.get().into(),
+                    reimbursed_amount: reimbursed.reimbursed_amount.into(),
+                    transaction_hash: reimbursed.transaction_hash.map(|h| h.to_string()),
+                },
                 EventType::QuarantinedReimbursement { index } => EP::QuarantinedReimbursement {
                     index: map_reimbursement_index(index),
                 },
-                EventType::Erc20TransferCompleted { from, to, amount } => EP::Erc20TransferCompleted {
+                EventType::ResolvedQuarantinedDeposit {
+                    event_source,
+                    resolution,
+                    reason,
+                } => EP::ResolvedQuarantinedDeposit {
+                    event_source: map_event_source(event_source),
+                    resolution,
+                    reason,
+                },
+                EventType::ResolvedQuarantinedReimbursement {
+                    index,
+                    resolution,
+                    reason,
+                } => EP::ResolvedQuarantinedReimbursement {
+                    index: map_reimbursement_index(index),
+                    resolution,
+                    reason,
+                },
+                EventType::Erc20TransferCompleted {
+                    from,
+                    to,
+                    amount,
+                    from_subaccount,
+                    to_subaccount,
+                    token,
+                    memo,
+                    created_at_time,
+                    block_index,
+                } => EP::Erc20TransferCompleted {
+                    from,
+                    to,
+                    amount: amount.into(),
+                    from_subaccount: from_subaccount.map(Subaccount::to_bytes),
+                    to_subaccount: to_subaccount.map(Subaccount::to_bytes),
+                    token: token.map(|token| token.to_string()),
+                    memo: memo.map(|memo| ByteBuf::from(Vec::from(memo))),
+                    created_at_time,
+                    block_index,
+                },
+                EventType::Erc20Approved {
+                    owner,
+                    spender,
+                    amount,
+                    expires_at,
+                    owner_subaccount,
+                    spender_subaccount,
+                    token,
+                    memo,
+                    created_at_time,
+                    block_index,
+                } => EP::Erc20Approved {
+                    owner,
+                    spender,
+                    amount: amount.into(),
+                    expires_at,
+                    owner_subaccount: owner_subaccount.map(Subaccount::to_bytes),
+                    spender_subaccount: spender_subaccount.map(Subaccount::to_bytes),
+                    token: token.map(|token| token.to_string()),
+                    memo: memo.map(|memo| ByteBuf::from(Vec::from(memo))),
+                    created_at_time,
+                    block_index,
+                },
+                EventType::Erc20TransferFromCompleted {
+                    spender,
+                    from,
+                    to,
+                    amount,
+                    spender_subaccount,
+                    from_subaccount,
+                    to_subaccount,
+                    token,
+                    memo,
+                    created_at_time,
+                    block_index,
+                } => EP::Erc20TransferFromCompleted {
+                    spender,
                     from,
                     to,
                     amount: amount.into(),
+                    spender_subaccount: spender_subaccount.map(Subaccount::to_bytes),
+                    from_subaccount: from_subaccount.map(Subaccount::to_bytes),
+                    to_subaccount: to_subaccount.map(Subaccount::to_bytes),
+                    token: token.map(|token| token.to_string()),
+                    memo: memo.map(|memo| ByteBuf::from(Vec::from(memo))),
+                    created_at_time,
+                    block_index,
+                },
+                EventType::UpdatedRpcProviders {
+                    providers,
+                    consensus_threshold,
+                } => EP::UpdatedRpcProviders {
+                    providers: providers.into_iter().map(RpcProvider::from).collect(),
+                    consensus_threshold,
                 },
             },
         }
@@ -554,71 +1150,1109 @@ fn check_candid_interface_compatibility() {
     );
 }
 
+/// Hands over the admin. Owners manage roles, but only the admin can replace itself.
 #[update]
 async fn set_admin(new_admin: candid::Principal) -> Result<String, String> {
-    let caller = validate_caller_not_anonymous();
-    let current_admin = read_state(|s| s.admin.clone());
-    if caller != current_admin {
-        return Err("ERROR: Only the current admin can set a new admin.".to_string());
-    }
+    validate_caller_is_admin()?;
+    read_state(|s| s.validate_direct_change(&ProposalAction::SetAdmin(new_admin)))
+        .map_err(|e| format!("ERROR: {}", e))?;
 
     mutate_state(|s| {
-        s.admin = new_admin;
+        log!(INFO, "[set_admin]: updating admin from {} to {}", s.admin, new_admin);
+        process_event(s, EventType::UpdatedAdmin { admin: new_admin });
     });
 
     Ok("Admin successfully updated.".to_string())
 }
 
-#[query]
-async fn erc20_my_balance() -> Nat {
-    let caller = validate_caller_not_anonymous();
-    read_state(|s| s.erc20_balances.balance_of(&caller).try_into().unwrap())
-}
-
-#[query]
-async fn erc20_balance_of(principal: Principal) -> Nat {
-    read_state(|s| s.erc20_balances.balance_of(&principal).try_into().unwrap())
+#[update]
+fn grant_role(principal: Principal, role: Role) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageRoles)?;
+    read_state(|s| s.validate_direct_change(&ProposalAction::GrantRole { principal, role }))
+        .map_err(|e| format!("ERROR: {}", e))?;
+    mutate_state(|s| {
+        if !s.roles.has_role(&principal, role) {
+            log!(INFO, "[grant_role]: granting {role:?} to {principal}");
+            process_event(s, EventType::GrantedRole { principal, role });
+        }
+    });
+    Ok(format!("{:?} granted to {}.", role, principal))
 }
 
-#[query]
-async fn erc20_balance() -> Nat {
-    read_state(|s| s.erc20_balances.get_erc20_balance().try_into().unwrap())
+#[update]
+fn revoke_role(principal: Principal, role: Role) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageRoles)?;
+    mutate_state(|s| {
+        s.validate_direct_change(&ProposalAction::RevokeRole { principal, role })
+            .map_err(|e| format!("ERROR: {}", e))?;
+        log!(INFO, "[revoke_role]: revoking {role:?} from {principal}");
+        process_event(s, EventType::RevokedRole { principal, role });
+        Ok(format!("{:?} revoked from {}.", role, principal))
+    })
 }
 
+/// Proposes a change of sensitive configuration, which the proposer approves right away.
+/// Until a policy is set by a proposal, the admin is the only approver.
 #[update]
-async fn erc20_transfer(receiver: Principal, amount: Nat) -> Result<String, String> {
-    let caller = validate_caller_not_anonymous();
-
-    let checked_amount = CheckedAmountOf::<Erc20Tag>::try_from(amount.clone()).map_err(|err| {
+fn create_proposal(action: ProposalActionArg) -> Result<u64, String> {
+    let caller = validate_caller_is_approver()?;
+    if let ProposalActionArg::AddErc20Token(token) = &action {
+        validate_erc20_ledger_id(token)?;
+    }
+    let action = ProposalAction::try_from(action)?;
+    let now = ic_cdk::api::time();
+    mutate_state(|s| {
+        s.validate_proposal_action(&action)
+            .map_err(|e| format!("ERROR: {}", e))?;
+        let id = s.proposals.next_id();
+        let executable_at =
+            now.saturating_add(s.proposals.delay_seconds().saturating_mul(1_000_000_000));
+        log!(INFO, "[create_proposal]: {caller} proposed {action:?} as proposal {id}");
+        process_event(
+            s,
+            EventType::CreatedProposal {
+                id,
+                proposer: caller,
+                action,
+                executable_at,
+            },
+        );
+        Ok(id)
+    })
+}
+
+#[update]
+fn approve_proposal(id: u64) -> Result<String, String> {
+    let caller = validate_caller_is_approver()?;
+    mutate_state(|s| {
+        let proposal = s
+            .proposals
+            .get(id)
+            .ok_or_else(|| format!("ERROR: unknown proposal {}.", id))?;
+        if proposal.approvals.contains(&caller) {
+            return Err(format!("ERROR: {} already approved proposal {}.", caller, id));
+        }
+        log!(INFO, "[approve_proposal]: {caller} approved proposal {id}");
+        process_event(s, EventType::ApprovedProposal { id, approver: caller });
+        Ok(format!("Proposal {} approved.", id))
+    })
+}
+
+/// Applies a proposal once enough approvers approved it and its delay elapsed.
+#[update]
+fn execute_proposal(id: u64) -> Result<String, String> {
+    let caller = validate_caller_is_approver()?;
+    let now = ic_cdk::api::time();
+    mutate_state(|s| {
+        let proposal = s
+            .proposals
+            .get(id)
+            .ok_or_else(|| format!("ERROR: unknown proposal {}.", id))?;
+        let approvals = s.proposals.count_approvals(proposal, &s.admin);
+        let threshold = s.proposals.threshold();
+        if approvals < usize::from(threshold) {
+            return Err(format!(
+                "ERROR: proposal {} has {} approvals out of the {} required.",
+                id, approvals, threshold
+            ));
+        }
+        if now < proposal.executable_at {
+            return Err(format!(
+                "ERROR: proposal {} cannot be executed before {}.",
+                id, proposal.executable_at
+            ));
+        }
+        // The state may have changed since the proposal was created.
+        s.validate_proposal_action(&proposal.action)
+            .map_err(|e| format!("ERROR: {}", e))?;
+        log!(INFO, "[execute_proposal]: {caller} executed proposal {id}");
+        process_event(
+            s,
+            EventType::ExecutedProposal {
+                id,
+                executed_by: caller,
+            },
+        );
+        Ok(format!("Proposal {} executed.", id))
+    })
+}
+
+/// Cancels a pending proposal right away when called by its proposer. Other approvers approve
+/// cancelling it, which happens once as many of them approved as required to execute it.
+#[update]
+fn cancel_proposal(id: u64) -> Result<String, String> {
+    let caller = validate_caller_is_approver()?;
+    mutate_state(|s| {
+        let proposal = s
+            .proposals
+            .get(id)
+            .ok_or_else(|| format!("ERROR: unknown proposal {}.", id))?;
+        if proposal.proposer != caller {
+            if proposal.cancellation_approvals.contains(&caller) {
+                return Err(format!(
+                    "ERROR: {} already approved cancelling proposal {}.",
+                    caller, id
+                ));
+            }
+            log!(
+                INFO,
+                "[cancel_proposal]: {caller} approved cancelling proposal {id}"
+            );
+            process_event(
+                s,
+                EventType::ApprovedProposalCancellation {
+                    id,
+                    approver: caller,
+                },
+            );
+            let proposal = s
+                .proposals
+                .get(id)
+                .expect("BUG: approved proposal not found");
+            let approvals = s.proposals.count_cancellation_approvals(proposal, &s.admin);
+            let threshold = s.proposals.threshold();
+            if approvals < usize::from(threshold) {
+                return Ok(format!(
+                    "Cancelling proposal {} approved, {} approvals out of the {} required.",
+                    id, approvals, threshold
+                ));
+            }
+        }
+        log!(INFO, "[cancel_proposal]: {caller} cancelled proposal {id}");
+        process_event(
+            s,
+            EventType::CancelledProposal {
+                id,
+                cancelled_by: caller,
+            },
+        );
+        Ok(format!("Proposal {} cancelled.", id))
+    })
+}
+
+#[query]
+fn get_pending_proposals() -> Vec<PendingProposal> {
+    read_state(|s| {
+        s.proposals
+            .iter()
+            .map(|(id, proposal)| PendingProposal {
+                id: *id,
+                proposer: proposal.proposer,
+                action: proposal.action.clone().into(),
+                approvals: proposal.approvals.iter().copied().collect(),
+                approvals_required: s.proposals.threshold(),
+                cancellation_approvals: proposal.cancellation_approvals.iter().copied().collect(),
+                executable_at: proposal.executable_at,
+            })
+            .collect()
+    })
+}
+
+/// Holders of a role, starting with the admin who implicitly holds the owner role.
+#[query]
+fn get_role_holders() -> Vec<RoleHolder> {
+    read_state(|s| {
+        let mut holders = vec![RoleHolder {
+            principal: s.admin,
+            roles: vec![Role::Owner],
+        }];
+        for (principal, roles) in s.roles.iter() {
+            match holders.iter_mut().find(|holder| &holder.principal == principal) {
+                Some(admin) => admin
+                    .roles
+                    .extend(roles.iter().filter(|role| **role != Role::Owner)),
+                None => holders.push(RoleHolder {
+                    principal: *principal,
+                    roles: roles.iter().copied().collect(),
+                }),
+            }
+        }
+        holders
+    })
+}
+
+#[query]
+async fn erc20_my_balance() -> Nat {
+    let caller = validate_caller_not_anonymous();
+    read_state(|s| {
+        s.erc20_balances
+            .balance_of(&s.default_erc20_token, &Account::from(caller))
+            .try_into()
+            .unwrap()
+    })
+}
+
+#[query]
+async fn erc20_balance_of(principal: Principal) -> Nat {
+    read_state(|s| {
+        s.erc20_balances
+            .balance_of(&s.default_erc20_token, &Account::from(principal))
+            .try_into()
+            .unwrap()
+    })
+}
+
+#[query]
+fn erc20_balance_details(account: Account, token: Option<String>) -> Erc20BalanceDetails {
+    let token = resolve_erc20_token(token)
+        .unwrap_or_else(|| ic_cdk::trap("ERROR: unsupported ERC-20 token"));
+    read_state(|s| Erc20BalanceDetails {
+        total: s.erc20_balances.balance_of(&token, &account).into(),
+        available: s.available_erc20_balance(&token, &account).into(),
+        pending: s.pending_erc20_balance_of(&token, &account).into(),
+    })
+}
+
+#[query]
+async fn erc20_balance() -> Nat {
+    read_state(|s| {
+        s.erc20_balances
+            .get_erc20_balance(&s.default_erc20_token)
+            .try_into()
+            .unwrap()
+    })
+}
+
+#[query]
+fn eth_balance_details(account: Account) -> EthBalanceDetails {
+    read_state(|s| EthBalanceDetails {
+        total: s.eth_balance().balance_of(&account).into(),
+        pending: s.pending_eth_balance_of(&account).into(),
+    })
+}
+
+#[query]
+fn eth_my_balance() -> Nat {
+    let caller = validate_caller_not_anonymous();
+    read_state(|s| s.eth_balance().balance_of(&Account::from(caller)).into())
+}
+
+#[query]
+fn eth_balance_of(account: Account) -> Nat {
+    read_state(|s| s.eth_balance().balance_of(&account).into())
+}
+
+#[query]
+fn eth_balance() -> Nat {
+    read_state(|s| s.eth_balance().eth_balance().into())
+}
+
+/// Moves native coins from the caller's account to the gas reserve paying the transaction
+/// fees of ERC-20 withdrawals, which cannot be accepted beyond what the reserve covers.
+/// The moved coins are no longer owned by the caller.
+/// Native coins that the minter holds outside of any account are added with the
+/// `gas_reserve_top_up` upgrade argument instead.
+#[update]
+fn fund_gas_reserve(
+    FundGasReserveArg {
+        amount,
+        from_subaccount,
+    }: FundGasReserveArg,
+) -> Result<String, String> {
+    let caller = validate_caller_not_anonymous();
+    ensure_not_paused(VaultFlow::Transfers)
+        .map_err(|_| "ERROR: Transfers are paused.".to_string())?;
+    let amount = Wei::try_from(amount).map_err(|e| format!("ERROR: invalid amount: {}", e))?;
+    if amount == Wei::ZERO {
+        return Err("ERROR: amount must be positive.".to_string());
+    }
+    let from = Account {
+        owner: caller,
+        subaccount: from_subaccount,
+    };
+    mutate_state(|s| {
+        let balance = s.eth_balance().balance_of(&from);
+        if balance < amount {
+            return Err(format!(
+                "ERROR: insufficient funds, balance is {}.",
+                balance
+            ));
+        }
+        log!(INFO, "[fund_gas_reserve]: {} funded the gas reserve with {}", from, amount);
+        process_event(
+            s,
+            EventType::FundedGasReserve {
+                from: caller,
+                from_subaccount: Subaccount::from_icrc(from_subaccount),
+                amount,
+            },
+        );
+        Ok(format!(
+            "Gas reserve is now {}.",
+            s.eth_balance().gas_reserve()
+        ))
+    })
+}
+
+#[query]
+fn get_gas_reserve() -> GasReserve {
+    read_state(|s| GasReserve {
+        total: s.eth_balance().gas_reserve().into(),
+        available: s.eth_balance().available_gas_reserve().into(),
+        total_erc20_tx_fees: s.eth_balance().total_erc20_tx_fees().into(),
+    })
+}
+
+/// Resolves the ERC-20 token named by a request, which is the default token if none is given.
+/// Returns `None` if the token is not held by the vault.
+fn resolve_erc20_token(erc20_contract_address: Option<String>) -> Option<Address> {
+    read_state(|s| match erc20_contract_address {
+        None => Some(s.default_erc20_token),
+        Some(address) => Address::from_str(&address)
+            .ok()
+            .filter(|address| s.ckerc20_tokens.contains_key(address)),
+    })
+}
+
+#[query]
+fn get_erc20_tokens() -> Vec<CkErc20Token> {
+    read_state(|s| {
+        s.ckerc20_tokens
+            .iter()
+            .map(|(address, symbol)| CkErc20Token {
+                ckerc20_token_symbol: symbol.to_string(),
+                erc20_contract_address: address.to_string(),
+                // Balances of every token are held by the vault itself.
+                ledger_canister_id: ic_cdk::id(),
+            })
+            .collect()
+    })
+}
+
+fn validate_erc20_ledger_id(token: &AddCkErc20Token) -> Result<(), String> {
+    if token.ckerc20_ledger_id != ic_cdk::id() {
+        return Err("ERROR: the balances of every token are held by the vault itself.".to_string());
+    }
+    Ok(())
+}
+
+#[update]
+fn add_erc20_token(arg: AddCkErc20Token) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageTokens)?;
+    validate_erc20_ledger_id(&arg)?;
+    let token = erc20::CkErc20Token::try_from(arg)?;
+    mutate_state(|s| {
+        s.validate_direct_change(&ProposalAction::AddErc20Token(token.clone()))
+            .map_err(|e| format!("ERROR: {}", e))?;
+        log!(INFO, "[add_erc20_token]: adding {:?}", token);
+        process_event(s, EventType::AddedCkErc20Token(token));
+        Ok("Token successfully added.".to_string())
+    })
+}
+
+#[update]
+fn update_rpc_providers(arg: UpdateRpcProvidersArg) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageProviders)?;
+    let providers: Vec<RpcEndpoint> = arg.providers.into_iter().map(RpcEndpoint::from).collect();
+    mutate_state(|s| {
+        s.validate_direct_change(&ProposalAction::UpdateRpcProviders {
+            providers: providers.clone(),
+            consensus_threshold: arg.consensus_threshold,
+        })
+        .map_err(|e| format!("ERROR: {}", e))?;
+        log!(INFO, "[update_rpc_providers]: updating providers to {:?}", providers);
+        process_event(
+            s,
+            EventType::UpdatedRpcProviders {
+                providers,
+                consensus_threshold: arg.consensus_threshold,
+            },
+        );
+        Ok("RPC providers successfully updated.".to_string())
+    })
+}
+
+#[query]
+fn get_provider_health() -> ProviderHealthReport {
+    let now = ic_cdk::api::time();
+    read_state(|s| {
+        // Read from the state rather than building a client, whose checks are meant for calls.
+        let configured = provider_ids(s.evm_network());
+        let healthy = s
+            .provider_health
+            .count_healthy(configured.iter().map(String::as_str), now);
+        let thresholds = RpcMethod::ALL
+            .iter()
+            .map(|method| RpcMethodThreshold {
+                method: method.to_string(),
+                threshold: method.threshold(
+                    s.evm_network().consensus_threshold,
+                    healthy,
+                    configured.len(),
+                ) as u64,
+            })
+            .collect();
+        let mut providers = configured;
+        for (provider, _) in s.provider_health.iter() {
+            if !providers.contains(provider) {
+                providers.push(provider.clone());
+            }
+        }
+        ProviderHealthReport {
+            providers: providers
+                .into_iter()
+                .map(|provider| {
+                    let stats = s.provider_health.get(&provider).cloned().unwrap_or_default();
+                    ProviderHealthInfo {
+                        healthy: !stats.is_demoted(now),
+                        successes: stats.successes,
+                        failures: stats.failures,
+                        disagreements: stats.disagreements,
+                        consecutive_failures: stats.consecutive_failures,
+                        average_latency_ns: stats.average_latency_ns(),
+                        last_error: stats.last_error,
+                        demoted_until: stats.demoted_until_ns.filter(|until| *until > now),
+                        provider,
+                    }
+                })
+                .collect(),
+            thresholds,
+        }
+    })
+}
+
+#[query]
+fn get_quarantined_deposits() -> Vec<QuarantinedDepositInfo> {
+    ensure_caller_has_permission(Permission::ReadAudit);
+    read_state(|s| {
+        s.invalid_events
+            .keys()
+            .filter(|source| s.is_quarantined_deposit(source))
+            .map(|source| QuarantinedDepositInfo {
+                event_source: CandidEventSource {
+                    transaction_hash: source.transaction_hash.to_string(),
+                    log_index: source.log_index.into(),
+                },
+                deposit: s
+                    .quarantined_deposits
+                    .get(source)
+                    .map(|event| QuarantinedDepositDetails {
+                        block_number: event.block_number().into(),
+                        from_address: event.from_address().to_string(),
+                        value: event.value(),
+                        principal: event.principal(),
+                        subaccount: event.beneficiary().subaccount,
+                        erc20_contract_address: match event {
+                            ReceivedEvent::Eth(_) => None,
+                            ReceivedEvent::Erc20(received) => {
+                                Some(received.erc20_contract_address.to_string())
+                            }
+                        },
+                    }),
+            })
+            .collect()
+    })
+}
+
+#[query]
+fn get_quarantined_reimbursements() -> Vec<QuarantinedReimbursementInfo> {
+    ensure_caller_has_permission(Permission::ReadAudit);
+    read_state(|s| {
+        s.eth_transactions
+            .reimbursed_transactions_iter()
+            .filter(|(index, _)| s.eth_transactions.is_quarantined_reimbursement(index))
+            .map(|(index, _)| QuarantinedReimbursementInfo {
+                index: match index {
+                    ReimbursementIndex::CkEth { ledger_burn_index } => {
+                        CandidReimbursementIndex::CkEth {
+                            ledger_burn_index: ledger_burn_index.clone(),
+                        }
+                    }
+                    ReimbursementIndex::CkErc20 { withdrawal_id } => {
+                        CandidReimbursementIndex::CkErc20 {
+                            withdrawal_id: withdrawal_id.clone(),
+                        }
+                    }
+                },
+                reimbursement: s
+                    .eth_transactions
+                    .quarantined_reimbursements_iter()
+                    .find(|(quarantined, _)| quarantined == &index)
+                    .map(|(_, request)| QuarantinedReimbursementDetails {
+                        to: request.to,
+                        to_subaccount: request.to_subaccount.clone().map(Subaccount::to_bytes),
+                        reimbursed_amount: request.reimbursed_amount.into(),
+                        erc20_contract_address: request
+                            .erc20_contract_address
+                            .map(|address| address.to_string()),
+                        transaction_hash: request.transaction_hash.map(|hash| hash.to_string()),
+                    }),
+            })
+            .collect()
+    })
+}
+
+#[update]
+fn set_paused(flow: VaultFlow, paused: bool) -> Result<String, String> {
+    validate_caller_has_permission(Permission::Pause)?;
+    mutate_state(|s| {
+        if s.is_paused(flow) != paused {
+            log!(INFO, "[set_paused]: {flow:?} paused: {paused}");
+            process_event(s, EventType::UpdatedPause { flow, paused });
+        }
+    });
+    Ok(format!("{:?} paused: {}.", flow, paused))
+}
+
+#[query]
+fn get_paused_flows() -> Vec<VaultFlow> {
+    read_state(|s| s.paused_flows.iter().copied().collect())
+}
+
+#[update]
+fn retry_skipped_blocks_now() -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageScraping)?;
+    let skipped = read_state(|s| s.skipped_blocks.values().map(|blocks| blocks.len()).sum::<usize>());
+    ic_cdk_timers::set_timer(Duration::from_secs(0), || {
+        ic_cdk::spawn(retry_skipped_blocks())
+    });
+    Ok(format!("Retrying {} skipped blocks.", skipped))
+}
+
+#[update]
+fn rescrape_blocks(arg: RescrapeBlocksArg) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ManageScraping)?;
+    let from = BlockNumber::try_from(arg.from).map_err(|e| format!("ERROR: {}", e))?;
+    let to = BlockNumber::try_from(arg.to).map_err(|e| format!("ERROR: {}", e))?;
+    if from > to {
+        return Err(format!("ERROR: block {} is after block {}.", from, to));
+    }
+    mutate_state(|s| {
+        if s.is_paused(VaultFlow::Deposits) {
+            return Err("ERROR: deposits are paused.".to_string());
+        }
+        if s.rescrape.as_ref().is_some_and(Rescrape::is_running) {
+            return Err("ERROR: a rescrape is already running.".to_string());
+        }
+        // Blocks that were not scraped yet are left to the regular scraping.
+        let last_scraped_block_number =
+            std::cmp::min(s.last_scraped_block_number, s.last_erc20_scraped_block_number);
+        if to > last_scraped_block_number {
+            return Err(format!(
+                "ERROR: blocks after {} were not scraped yet.",
+                last_scraped_block_number
+            ));
+        }
+        log!(INFO, "[rescrape_blocks]: rescraping blocks {from} to {to}");
+        s.rescrape = Some(Rescrape::new(from, to));
+        Ok(())
+    })?;
+    ic_cdk_timers::set_timer(Duration::from_secs(0), || ic_cdk::spawn(rescrape_logs()));
+    Ok(format!("Rescraping blocks {} to {}.", from, to))
+}
+
+#[query]
+fn get_rescrape_status() -> Option<RescrapeStatus> {
+    let map_event_source = |source: &EventSource| CandidEventSource {
+        transaction_hash: source.transaction_hash.to_string(),
+        log_index: source.log_index.into(),
+    };
+    read_state(|s| {
+        s.rescrape.as_ref().map(|rescrape| RescrapeStatus {
+            from: rescrape.from.into(),
+            to: rescrape.to.into(),
+            scraped_up_to: rescrape.scraped_up_to.map(Nat::from),
+            new_deposits: rescrape.new_deposits.iter().map(map_event_source).collect(),
+            new_invalid_deposits: rescrape
+                .new_invalid_deposits
+                .iter()
+                .map(map_event_source)
+                .collect(),
+            skipped_blocks: rescrape.skipped_blocks.iter().copied().map(Nat::from).collect(),
+            error: rescrape.error.clone(),
+            done: rescrape.done,
+        })
+    })
+}
+
+#[update]
+fn resolve_quarantined_deposit(arg: ResolveQuarantinedDepositArg) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ResolveQuarantine)?;
+    if arg.reason.trim().is_empty() {
+        return Err("ERROR: a reason is required.".to_string());
+    }
+    let event_source = EventSource {
+        transaction_hash: arg
+            .event_source
+            .transaction_hash
+            .parse()
+            .map_err(|e| format!("ERROR: invalid transaction hash: {}", e))?,
+        log_index: LogIndex::try_from(arg.event_source.log_index)
+            .map_err(|e| format!("ERROR: invalid log index: {}", e))?,
+    };
+    mutate_state(|s| {
+        if !s.is_quarantined_deposit(&event_source) {
+            return Err(format!("ERROR: deposit {} is not quarantined.", event_source));
+        }
+        let known = s.quarantined_deposits.contains_key(&event_source);
+        let can_resolve = match arg.resolution {
+            QuarantineResolution::Release => known,
+            QuarantineResolution::MarkProcessed => {
+                known || s.minted_events.contains_key(&event_source)
+            }
+            QuarantineResolution::Reject => true,
+        };
+        if !can_resolve {
+            return Err(format!(
+                "ERROR: the details of deposit {} are unknown, it can only be rejected.",
+                event_source
+            ));
+        }
+        log!(
+            INFO,
+            "[resolve_quarantined_deposit]: {:?} deposit {}: {}",
+            arg.resolution,
+            event_source,
+            arg.reason
+        );
+        process_event(
+            s,
+            EventType::ResolvedQuarantinedDeposit {
+                event_source,
+                resolution: arg.resolution,
+                reason: arg.reason,
+            },
+        );
+        Ok(format!("Deposit {} successfully resolved.", event_source))
+    })
+}
+
+#[update]
+fn resolve_quarantined_reimbursement(
+    arg: ResolveQuarantinedReimbursementArg,
+) -> Result<String, String> {
+    validate_caller_has_permission(Permission::ResolveQuarantine)?;
+    if arg.reason.trim().is_empty() {
+        return Err("ERROR: a reason is required.".to_string());
+    }
+    let index = match arg.index {
+        CandidReimbursementIndex::CkEth { ledger_burn_index } => {
+            ReimbursementIndex::CkEth { ledger_burn_index }
+        }
+        CandidReimbursementIndex::CkErc20 { withdrawal_id } => {
+            ReimbursementIndex::CkErc20 { withdrawal_id }
+        }
+    };
+    mutate_state(|s| {
+        if !s.eth_transactions.is_quarantined_reimbursement(&index) {
+            return Err(format!(
+                "ERROR: reimbursement of withdrawal {} is not quarantined.",
+                index.id()
+            ));
+        }
+        if arg.resolution == QuarantineResolution::Release
+            && !s
+                .eth_transactions
+                .quarantined_reimbursements_iter()
+                .any(|(quarantined, _)| quarantined == &index)
+        {
+            return Err(format!(
+                "ERROR: the details of the reimbursement of withdrawal {} are unknown, it cannot be released.",
+                index.id()
+            ));
+        }
+        log!(
+            INFO,
+            "[resolve_quarantined_reimbursement]: {:?} reimbursement of withdrawal {}: {}",
+            arg.resolution,
+            index.id(),
+            arg.reason
+        );
+        process_event(
+            s,
+            EventType::ResolvedQuarantinedReimbursement {
+                index: index.clone(),
+                resolution: arg.resolution,
+                reason: arg.reason,
+            },
+        );
+        Ok(())
+    })?;
+    if arg.resolution == QuarantineResolution::Release {
+        ic_cdk_timers::set_timer(Duration::from_secs(0), || {
+            ic_cdk::spawn(process_reimbursement())
+        });
+    }
+    Ok(format!(
+        "Reimbursement of withdrawal {} successfully resolved.",
+        index.id()
+    ))
+}
+
+/// Moves `amount` from `from` to `to` and returns the index of the recorded block,
+/// or the available balance of `from` if it does not cover the amount.
+fn execute_erc20_transfer(
+    from: Account,
+    to: Account,
+    amount: Erc20Value,
+    memo: Option<Memo>,
+    created_at_time: Option<u64>,
+) -> Result<u64, Erc20Value> {
+    mutate_state(|s| {
+        let from_balance = s.available_erc20_balance(&s.default_erc20_token, &from);
+        if from_balance < amount {
+            return Err(from_balance);
+        }
+        let index = storage::total_block_count();
+        process_event(
+            s,
+            EventType::Erc20TransferCompleted {
+                from: from.owner,
+                to: to.owner,
+                amount,
+                from_subaccount: Subaccount::from_icrc(from.subaccount),
+                to_subaccount: Subaccount::from_icrc(to.subaccount),
+                token: Some(s.default_erc20_token),
+                memo: memo.map(|memo| ByteVec::from(memo.0.into_vec())),
+                created_at_time,
+                block_index: Some(index),
+            },
+        );
+        Ok(index)
+    })
+}
+
+/// Legacy transfer of the default ERC-20 token between the default accounts of the caller and receiver.
+/// Errors are only reported as text, e.g. when transfers are paused or the balance is insufficient:
+/// callers that need to tell them apart should use `icrc1_transfer`, which returns a `TransferError`.
+#[update]
+async fn erc20_transfer(receiver: Principal, amount: Nat) -> Result<String, String> {
+    let caller = validate_caller_not_anonymous();
+    ensure_not_paused(VaultFlow::Transfers)
+        .map_err(|_| "ERROR: Transfers are paused.".to_string())?;
+
+    let checked_amount = CheckedAmountOf::<Erc20Tag>::try_from(amount.clone()).map_err(|err| {
         format!(
             "Failed to convert Nat to CheckedAmountOf<Erc20Tag>: {}",
             err
         )
     })?;
 
-    mutate_state(|s| {
-        let caller_balance = s.erc20_balances.balance_of(&caller);
-        if caller_balance < checked_amount {
-            return Err("ERROR: Insufficient balance".to_string());
-        }
+    execute_erc20_transfer(
+        Account::from(caller),
+        Account::from(receiver),
+        checked_amount,
+        None,
+        None,
+    )
+    .map_err(|_| "ERROR: Insufficient balance".to_string())?;
+
+    Ok("Transfer succeded.".to_string())
+}
+
+fn icrc1_token_symbol() -> String {
+    read_state(|s| s.default_erc20_token_symbol().to_string())
+}
+
+#[query]
+fn icrc1_name() -> String {
+    icrc1_token_symbol()
+}
+
+#[query]
+fn icrc1_symbol() -> String {
+    icrc1_token_symbol()
+}
+
+#[query]
+fn icrc1_decimals() -> u8 {
+    ERC20_DECIMALS
+}
+
+#[query]
+fn icrc1_fee() -> Nat {
+    Nat::from(ICRC_FEE)
+}
+
+#[query]
+fn icrc1_metadata() -> Vec<(String, MetadataValue)> {
+    vec![
+        ("icrc1:name".to_string(), MetadataValue::Text(icrc1_token_symbol())),
+        ("icrc1:symbol".to_string(), MetadataValue::Text(icrc1_token_symbol())),
+        ("icrc1:decimals".to_string(), MetadataValue::Nat(Nat::from(ERC20_DECIMALS))),
+        ("icrc1:fee".to_string(), MetadataValue::Nat(icrc1_fee())),
+    ]
+}
 
-        s.erc20_balances.principal_erc20_sub(caller, checked_amount);
+#[query]
+fn icrc1_total_supply() -> Nat {
+    read_state(|s| s.erc20_balances.total_supply(&s.default_erc20_token).into())
+}
+
+#[query]
+fn icrc1_minting_account() -> Option<Account> {
+    None
+}
+
+#[query]
+fn icrc1_balance_of(account: Account) -> Nat {
+    read_state(|s| {
         s.erc20_balances
-            .principal_erc20_add(receiver, checked_amount);
+            .balance_of(&s.default_erc20_token, &account)
+            .into()
+    })
+}
 
+#[query]
+fn icrc1_supported_standards() -> Vec<StandardRecord> {
+    vec![
+        StandardRecord {
+            name: "ICRC-1".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-1".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-2".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
+        },
+        StandardRecord {
+            name: "ICRC-3".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3".to_string(),
+        },
+    ]
+}
+
+#[update]
+fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
+    let from = Account {
+        owner: validate_caller_not_anonymous(),
+        subaccount: arg.from_subaccount,
+    };
+
+    validate_transaction_args(&arg.fee, &arg.memo, arg.created_at_time, ic_cdk::api::time())?;
+    ensure_not_paused(VaultFlow::Transfers).map_err(|_| TransferError::TemporarilyUnavailable)?;
+
+    let insufficient_funds = |balance: Erc20Value| TransferError::InsufficientFunds {
+        balance: balance.into(),
+    };
+    let amount = Erc20Value::try_from(arg.amount)
+        .map_err(|_| insufficient_funds(read_state(|s| s.available_erc20_balance(&s.default_erc20_token, &from))))?;
+
+    let duplicate_of = read_state(|s| {
+        find_duplicate(
+            &s.recent_erc20_transfers,
+            from,
+            TransferKind::Transfer {
+                to: arg.to,
+                spender: None,
+            },
+            amount,
+            &arg.memo,
+            arg.created_at_time,
+        )
+    });
+    if let Some(duplicate_of) = duplicate_of {
+        return Err(TransferError::Duplicate {
+            duplicate_of: Nat::from(duplicate_of),
+        });
+    }
+
+    execute_erc20_transfer(from, arg.to, amount, arg.memo, arg.created_at_time)
+        .map(Nat::from)
+        .map_err(insufficient_funds)
+}
+
+#[query]
+fn icrc2_allowance(arg: AllowanceArgs) -> Allowance {
+    let allowance = read_state(|s| {
+        s.erc20_allowances
+            .allowance(&arg.account, &arg.spender, ic_cdk::api::time())
+    });
+    Allowance {
+        allowance: allowance.amount.into(),
+        expires_at: allowance.expires_at,
+    }
+}
+
+#[update]
+fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
+    let owner = Account {
+        owner: validate_caller_not_anonymous(),
+        subaccount: arg.from_subaccount,
+    };
+    let spender = arg.spender;
+    let now = ic_cdk::api::time();
+
+    validate_transaction_args(&arg.fee, &arg.memo, arg.created_at_time, now)?;
+    if spender == owner {
+        return Err(ApproveError::GenericError {
+            error_code: Nat::from(SELF_APPROVAL_ERROR_CODE),
+            message: "ERROR: an account cannot approve itself".to_string(),
+        });
+    }
+    if let Some(expires_at) = arg.expires_at {
+        if expires_at <= now {
+            return Err(ApproveError::Expired { ledger_time: now });
+        }
+    }
+    // Allowances exceeding any representable balance are capped, as they can never be used in full.
+    let amount = Erc20Value::try_from(arg.amount).unwrap_or(Erc20Value::MAX);
+
+    mutate_state(|s| {
+        if let Some(duplicate_of) = find_duplicate(
+            &s.recent_erc20_transfers,
+            owner,
+            TransferKind::Approve {
+                spender,
+                expires_at: arg.expires_at,
+            },
+            amount,
+            &arg.memo,
+            arg.created_at_time,
+        ) {
+            return Err(ApproveError::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            });
+        }
+        if let Some(expected_allowance) = arg.expected_allowance {
+            let current_allowance: Nat = s
+                .erc20_allowances
+                .allowance(&owner, &spender, now)
+                .amount
+                .into();
+            if current_allowance != expected_allowance {
+                return Err(ApproveError::AllowanceChanged { current_allowance });
+            }
+        }
+        let index = storage::total_block_count();
         process_event(
             s,
-            EventType::Erc20TransferCompleted {
-                from: caller,
-                to: receiver,
-                amount: checked_amount.into(),
+            EventType::Erc20Approved {
+                owner: owner.owner,
+                spender: spender.owner,
+                amount,
+                expires_at: arg.expires_at,
+                owner_subaccount: Subaccount::from_icrc(owner.subaccount),
+                spender_subaccount: Subaccount::from_icrc(spender.subaccount),
+                token: Some(s.default_erc20_token),
+                memo: arg.memo.map(|memo| ByteVec::from(memo.0.into_vec())),
+                created_at_time: arg.created_at_time,
+                block_index: Some(index),
             },
         );
+        Ok(Nat::from(index))
+    })
+}
+
+#[update]
+fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let spender = Account {
+        owner: validate_caller_not_anonymous(),
+        subaccount: arg.spender_subaccount,
+    };
+    let now = ic_cdk::api::time();
 
-        Ok("Transfer succeded.".to_string())
+    validate_transaction_args(&arg.fee, &arg.memo, arg.created_at_time, now)?;
+    ensure_not_paused(VaultFlow::Transfers)
+        .map_err(|_| TransferFromError::TemporarilyUnavailable)?;
+
+    let from = arg.from;
+    let to = arg.to;
+    if spender == from {
+        // Spending one's own funds does not require an allowance, and is recorded as a transfer.
+        let amount = Erc20Value::try_from(arg.amount).map_err(|_| {
+            TransferFromError::InsufficientFunds {
+                balance: read_state(|s| s.available_erc20_balance(&s.default_erc20_token, &from)).into(),
+            }
+        })?;
+        let duplicate_of = read_state(|s| {
+            find_duplicate(
+                &s.recent_erc20_transfers,
+                from,
+                TransferKind::Transfer { to, spender: None },
+                amount,
+                &arg.memo,
+                arg.created_at_time,
+            )
+        });
+        if let Some(duplicate_of) = duplicate_of {
+            return Err(TransferFromError::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            });
+        }
+        return execute_erc20_transfer(from, to, amount, arg.memo, arg.created_at_time)
+            .map(Nat::from)
+            .map_err(|balance| TransferFromError::InsufficientFunds {
+                balance: balance.into(),
+            });
+    }
+
+    mutate_state(|s| {
+        // Amounts that do not fit in a balance were never transferred, so have no duplicates.
+        let amount = Erc20Value::try_from(arg.amount).ok();
+        if let Some(duplicate_of) = amount.and_then(|amount| {
+            find_duplicate(
+                &s.recent_erc20_transfers,
+                from,
+                TransferKind::Transfer {
+                    to,
+                    spender: Some(spender),
+                },
+                amount,
+                &arg.memo,
+                arg.created_at_time,
+            )
+        }) {
+            return Err(TransferFromError::Duplicate {
+                duplicate_of: Nat::from(duplicate_of),
+            });
+        }
+        let allowance = s.erc20_allowances.allowance(&from, &spender, now).amount;
+        let balance = s.available_erc20_balance(&s.default_erc20_token, &from);
+        let amount = match amount {
+            Some(amount) if amount <= allowance => amount,
+            _ => {
+                return Err(TransferFromError::InsufficientAllowance {
+                    allowance: allowance.into(),
+                })
+            }
+        };
+        if balance < amount {
+            return Err(TransferFromError::InsufficientFunds {
+                balance: balance.into(),
+            });
+        }
+        let index = storage::total_block_count();
+        process_event(
+            s,
+            EventType::Erc20TransferFromCompleted {
+                spender: spender.owner,
+                from: from.owner,
+                to: to.owner,
+                amount,
+                spender_subaccount: Subaccount::from_icrc(spender.subaccount),
+                from_subaccount: Subaccount::from_icrc(from.subaccount),
+                to_subaccount: Subaccount::from_icrc(to.subaccount),
+                token: Some(s.default_erc20_token),
+                memo: arg.memo.map(|memo| ByteVec::from(memo.0.into_vec())),
+                created_at_time: arg.created_at_time,
+                block_index: Some(index),
+            },
+        );
+        Ok(Nat::from(index))
     })
 }
 
+#[query]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    blocks::get_blocks(args)
+}
+
+#[query]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    blocks::tip_certificate()
+}
+
+#[query]
+fn icrc3_get_archives(_arg: GetArchivesArgs) -> Vec<ICRC3ArchiveInfo> {
+    // All blocks are kept in the stable memory of this canister.
+    vec![]
+}
+
+#[query]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    blocks::supported_block_types()
+}
+
 #[query]
 async fn smart_contract_address() -> String {
     read_state(|s| s.erc20_helper_contract_address.clone())
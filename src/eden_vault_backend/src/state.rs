@@ -1,27 +1,45 @@
 use crate::address::ecdsa_public_key_to_address;
-use crate::erc20::CkTokenSymbol;
+use crate::endpoints::{QuarantineResolution, VaultFlow};
+use crate::erc20::{CkErc20Token, CkTokenSymbol};
 use crate::eth_logs::{EventSource, ReceivedEvent};
 use crate::eth_rpc::BlockTag;
+use crate::eth_rpc_client::health::ProviderHealth;
 use crate::eth_rpc_client::responses::{TransactionReceipt, TransactionStatus};
 use crate::lifecycle::upgrade::UpgradeArg;
-use crate::lifecycle::EthereumNetwork;
+use crate::lifecycle::network::{EvmNetwork, RpcEndpoint};
 use crate::logs::DEBUG;
 use crate::numeric::{BlockNumber, Erc20Value, TransactionNonce, Wei};
-use crate::state::transactions::{Erc20WithdrawalRequest, TransactionCallData, WithdrawalRequest};
+use crate::state::transactions::{
+    to_account, Erc20WithdrawalRequest, EthWithdrawalRequest, Reimbursed, ReimbursementIndex,
+    TransactionCallData, WithdrawalRequest,
+};
 use crate::tx::GasFeeEstimate;
 use candid::{Nat, Principal};
 use ic_canister_log::log;
 use ic_cdk::api::management_canister::ecdsa::EcdsaPublicKeyResponse;
 use ic_crypto_secp256k1::PublicKey;
 use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc1::account::Account;
 use std::cell::RefCell;
 use std::collections::{btree_map, BTreeMap, BTreeSet, HashSet};
 use std::fmt::{Display, Formatter};
 use strum_macros::EnumIter;
+use allowances::Erc20Allowances;
+use dedup::RecentTransfers;
+use holds::Erc20Holds;
+use limits::{TokenWithdrawalLimits, WithdrawalLimits};
+use proposals::{ProposalAction, Proposals};
+use roles::{Permission, Roles};
 use transactions::EthTransactions;
 
+pub mod allowances;
 pub mod audit;
+pub mod dedup;
 pub mod event;
+pub mod holds;
+pub mod limits;
+pub mod proposals;
+pub mod roles;
 pub mod transactions;
 
 #[cfg(test)]
@@ -42,14 +60,60 @@ impl MintedEvent {
     }
 }
 
+/// Rescrape of the deposits of a range of blocks that were already scraped,
+/// recording the deposits that were missed the first time.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Rescrape {
+    pub from: BlockNumber,
+    pub to: BlockNumber,
+    /// Last block of the range that was rescraped so far.
+    pub scraped_up_to: Option<BlockNumber>,
+    pub new_deposits: Vec<EventSource>,
+    pub new_invalid_deposits: Vec<EventSource>,
+    /// Blocks whose logs did not fit in a single response, left to [`TaskType::RetrySkippedBlocks`].
+    pub skipped_blocks: Vec<BlockNumber>,
+    pub error: Option<String>,
+    pub done: bool,
+}
+
+impl Rescrape {
+    pub fn new(from: BlockNumber, to: BlockNumber) -> Self {
+        Self {
+            from,
+            to,
+            scraped_up_to: None,
+            new_deposits: vec![],
+            new_invalid_deposits: vec![],
+            skipped_blocks: vec![],
+            error: None,
+            done: false,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.done
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct State {
     pub admin: Principal,
-    pub ethereum_network: EthereumNetwork,
+    /// Roles granted by the admin over the admin endpoints.
+    pub roles: Roles,
+    /// Pending changes of sensitive configuration awaiting approval.
+    pub proposals: Proposals,
+    /// Caps on the withdrawals of each token and the withdrawals counted against them.
+    pub withdrawal_limits: WithdrawalLimits,
+    pub evm_network: EvmNetwork,
+    /// Whether the RPC providers were set through `update_rpc_providers`,
+    /// in which case upgrades keep them instead of replacing them.
+    pub rpc_providers_set_by_admin: bool,
     pub ecdsa_key_name: String,
     pub erc20_helper_contract_address: Option<Address>,
     pub ecdsa_public_key: Option<EcdsaPublicKeyResponse>,
     pub cketh_minimum_withdrawal_amount: Wei,
+    /// Minimum amount of an ERC-20 withdrawal, in units of the token, for the tokens that have one.
+    pub erc20_minimum_withdrawal_amounts: BTreeMap<Address, Erc20Value>,
     pub ethereum_block_height: BlockTag,
     pub first_scraped_block_number: BlockNumber,
     pub last_scraped_block_number: BlockNumber,
@@ -58,8 +122,17 @@ pub struct State {
     pub events_to_mint: BTreeMap<EventSource, ReceivedEvent>,
     pub minted_events: BTreeMap<EventSource, MintedEvent>,
     pub invalid_events: BTreeMap<EventSource, InvalidEventReason>,
+    /// Accepted deposits that were no longer part of the canonical chain when verified before minting.
+    pub reorged_events: BTreeMap<EventSource, ReceivedEvent>,
+    /// Deposits seen in blocks that are not final yet, shown as pending credits.
+    /// They are not recorded in the event log: the first scrape after an upgrade finds them again.
+    pub pending_deposits: BTreeMap<EventSource, ReceivedEvent>,
+    /// Deposits quarantined while being minted, kept until the admin resolves them.
+    pub quarantined_deposits: BTreeMap<EventSource, ReceivedEvent>,
     pub eth_transactions: EthTransactions,
     pub skipped_blocks: BTreeMap<Address, BTreeSet<BlockNumber>>,
+    /// Flows of funds paused by the admin.
+    pub paused_flows: BTreeSet<VaultFlow>,
 
     /// Current balance of ETH held by the minter.
     /// Computed based on audit events.
@@ -69,26 +142,48 @@ pub struct State {
     /// Computed based on audit events.
     pub erc20_balances: Erc20Balances,
 
+    /// ICRC-2 allowances over the ERC-20 balances held by the minter.
+    /// Computed based on audit events.
+    pub erc20_allowances: Erc20Allowances,
+
+    /// ICRC-1 transfers that can still be duplicated, to reject their duplicates.
+    /// Computed based on audit events.
+    pub recent_erc20_transfers: RecentTransfers,
+
+    /// Amounts of the ERC-20 balances reserved by pending asynchronous flows.
+    pub erc20_holds: Erc20Holds,
+
     /// Per-principal lock for pending withdrawals
     pub pending_withdrawal_principals: BTreeSet<Principal>,
 
     /// Locks preventing concurrent execution timer tasks
     pub active_tasks: HashSet<TaskType>,
 
+    /// Latest block number seen when the blocks that are not final yet were last scraped.
+    pub last_pending_deposits_head: Option<BlockNumber>,
+
     /// Number of HTTP outcalls since the last upgrade.
     /// Used to correlate request and response in logs.
     pub http_request_counter: u64,
 
     pub last_transaction_price_estimate: Option<(u64, GasFeeEstimate)>,
 
+    /// Health of the JSON-RPC providers since the last upgrade.
+    /// Used to avoid unreliable providers and to adapt consensus thresholds.
+    pub provider_health: ProviderHealth,
+
+    /// Progress of the last rescrape requested by the admin, since the last upgrade.
+    pub rescrape: Option<Rescrape>,
+
     /// Canister ID of the EVM RPC canister that
     /// handles communication with Ethereum
     pub evm_rpc_id: Option<Principal>,
 
-    /// ERC-20 tokens that the minter can mint:
-    /// - 0 item: ERC-20 contract address on Ethereum
-    /// - 1 item: ckERC20 token symbol
-    pub ckerc20_tokens: (Address, CkTokenSymbol),
+    /// ERC-20 tokens held by the vault, by contract address on Ethereum.
+    pub ckerc20_tokens: BTreeMap<Address, CkTokenSymbol>,
+
+    /// Token exposed through the ICRC interface and used by requests that do not name a token.
+    pub default_erc20_token: Address,
 
     pub withdraw_count: Nat,
 
@@ -109,6 +204,12 @@ pub enum InvalidStateError {
     InvalidCkTokenSymbol(String),
     InvalidWithdrawFeeValue(String),
     InvalidWithdrawalFeeValue(String),
+    InvalidEvmNetwork(String),
+    InvalidWithdrawalLimits(String),
+    InvalidErc20MinimumWithdrawalAmount(String),
+    InvalidGasReserveTopUp(String),
+    /// The change can only be applied through a proposal.
+    RequiresProposal(String),
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -123,6 +224,9 @@ pub enum InvalidEventReason {
     /// The deposit is quarantined to avoid any double minting and
     /// will not be further processed without manual intervention.
     QuarantinedDeposit,
+
+    /// The admin rejected the quarantined deposit, for the given reason.
+    RejectedDeposit(String),
 }
 
 impl Display for InvalidEventReason {
@@ -134,6 +238,9 @@ impl Display for InvalidEventReason {
             InvalidEventReason::QuarantinedDeposit => {
                 write!(f, "Quarantined deposit")
             }
+            InvalidEventReason::RejectedDeposit(reason) => {
+                write!(f, "Rejected deposit: {}", reason)
+            }
         }
     }
 }
@@ -150,23 +257,53 @@ impl State {
                 "minimum_withdrawal_amount must be positive".to_string(),
             ));
         }
-        let cketh_ledger_transfer_fee = match self.ethereum_network {
-            EthereumNetwork::Mainnet => Wei::new(2_000_000_000_000),
-            EthereumNetwork::Sepolia => Wei::new(10_000_000_000),
-            EthereumNetwork::Local => Wei::new(1_000_000_000),
-            EthereumNetwork::BSC => Wei::new(2_000_000_000_000),
-            EthereumNetwork::BSCTestnet => Wei::new(2_000_000_000_000),
-        };
-        if self.cketh_minimum_withdrawal_amount < cketh_ledger_transfer_fee {
+        self.evm_network
+            .validate()
+            .map_err(|e| InvalidStateError::InvalidEvmNetwork(format!("ERROR: {}", e)))?;
+        if self.cketh_minimum_withdrawal_amount < self.evm_network.fee_policy.native_transfer_fee {
             return Err(InvalidStateError::InvalidMinimumWithdrawalAmount(
                 "minimum_withdrawal_amount must cover ledger transaction fee, \
                 otherwise ledger can return a BadBurn error that should be returned to the user"
                     .to_string(),
             ));
         }
+        for (token, minimum) in &self.erc20_minimum_withdrawal_amounts {
+            if !self.ckerc20_tokens.contains_key(token) {
+                return Err(InvalidStateError::InvalidErc20MinimumWithdrawalAmount(format!(
+                    "unsupported ERC-20 token {}",
+                    token
+                )));
+            }
+            if *minimum == Erc20Value::ZERO {
+                return Err(InvalidStateError::InvalidErc20MinimumWithdrawalAmount(format!(
+                    "minimum withdrawal amount of {} must be positive",
+                    token
+                )));
+            }
+            if let Some(max_per_withdrawal) = self
+                .withdrawal_limits
+                .get(token)
+                .and_then(|limits| limits.max_per_withdrawal)
+            {
+                if *minimum > max_per_withdrawal {
+                    return Err(InvalidStateError::InvalidErc20MinimumWithdrawalAmount(format!(
+                        "minimum withdrawal amount {} of {} exceeds the maximum per withdrawal {}",
+                        minimum, token, max_per_withdrawal
+                    )));
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Minimum amount of a withdrawal of the token, zero if it has none.
+    pub fn erc20_minimum_withdrawal_amount(&self, token: &Address) -> Erc20Value {
+        self.erc20_minimum_withdrawal_amounts
+            .get(token)
+            .copied()
+            .unwrap_or(Erc20Value::ZERO)
+    }
+
     pub fn minter_address(&self) -> Option<Address> {
         let pubkey = PublicKey::deserialize_sec1(&self.ecdsa_public_key.as_ref()?.public_key)
             .unwrap_or_else(|e| {
@@ -187,12 +324,17 @@ impl State {
         );
         assert!(!self.minted_events.contains_key(&event_source));
         assert!(!self.invalid_events.contains_key(&event_source));
-
-        let ReceivedEvent::Erc20(event_content) = event;
-        assert!(
-            self.ckerc20_tokens.0 == event_content.erc20_contract_address,
-            "BUG: unsupported ERC-20 contract address in event {event_content:?}"
-        );
+        // A reorged deposit may be included again in the canonical chain.
+        self.reorged_events.remove(&event_source);
+        self.pending_deposits.remove(&event_source);
+
+        if let ReceivedEvent::Erc20(event_content) = event {
+            assert!(
+                self.ckerc20_tokens
+                    .contains_key(&event_content.erc20_contract_address),
+                "BUG: unsupported ERC-20 contract address in event {event_content:?}"
+            );
+        }
 
         self.events_to_mint.insert(event_source, event.clone());
 
@@ -203,11 +345,108 @@ impl State {
         !self.events_to_mint.is_empty()
     }
 
+    /// Whether deposits in blocks that are not final yet are shown as pending credits.
+    pub fn optimistic_deposits_enabled(&self) -> bool {
+        self.evm_network.optimistic_deposits && self.ethereum_block_height != BlockTag::Latest
+    }
+
+    /// Whether the deposit was already seen, in any state.
+    /// Reorged deposits are not known anymore, so that their log is recorded again once scraped
+    /// from the block now including their transaction.
+    pub fn is_known_deposit(&self, source: &EventSource) -> bool {
+        self.pending_deposits.contains_key(source)
+            || self.events_to_mint.contains_key(source)
+            || self.minted_events.contains_key(source)
+            || self.invalid_events.contains_key(source)
+    }
+
+    /// Whether a scraped deposit still has to be recorded, e.g. when scraping blocks for the second time.
+    /// Pending deposits are accepted as if they had not been seen before.
+    pub fn is_new_deposit(&self, source: &EventSource) -> bool {
+        !self.is_known_deposit(source) || self.pending_deposits.contains_key(source)
+    }
+
+    pub fn record_pending_deposit(&mut self, event: &ReceivedEvent) {
+        let event_source = event.source();
+        assert!(
+            !self.is_known_deposit(&event_source),
+            "BUG: attempted to record known deposit {event_source:?} as pending"
+        );
+        self.pending_deposits.insert(event_source, event.clone());
+    }
+
+    pub fn record_dropped_pending_deposit(&mut self, source: EventSource) {
+        assert!(
+            self.pending_deposits.remove(&source).is_some(),
+            "BUG: attempted to drop unknown pending deposit {source:?}"
+        );
+    }
+
+    /// Whether the blocks that are not final yet must be scraped again, which is only the case
+    /// when they may contain new deposits or when pending deposits may have disappeared.
+    pub fn should_scrape_pending_deposits(&self, latest_block_number: BlockNumber) -> bool {
+        !self.pending_deposits.is_empty()
+            || self.last_pending_deposits_head != Some(latest_block_number)
+    }
+
+    /// Pending deposits in blocks that were scraped at finality without being accepted.
+    pub fn stale_pending_deposits(&self) -> Vec<EventSource> {
+        self.pending_deposits
+            .values()
+            .filter(|event| {
+                let last_scraped_block_number = match event {
+                    ReceivedEvent::Eth(_) => self.last_scraped_block_number,
+                    ReceivedEvent::Erc20(_) => self.last_erc20_scraped_block_number,
+                };
+                event.block_number() <= last_scraped_block_number
+            })
+            .map(ReceivedEvent::source)
+            .collect()
+    }
+
+    /// Deposits credited to `account` that are not spendable yet,
+    /// either because their block is not final or because they are not minted yet.
+    fn deposits_not_spendable_yet<'a>(
+        &'a self,
+        account: &'a Account,
+    ) -> impl Iterator<Item = &'a ReceivedEvent> + 'a {
+        self.pending_deposits
+            .values()
+            .chain(self.events_to_mint.values())
+            .filter(move |event| &event.beneficiary() == account)
+    }
+
+    pub fn pending_eth_balance_of(&self, account: &Account) -> Wei {
+        self.deposits_not_spendable_yet(account)
+            .filter_map(|event| match event {
+                ReceivedEvent::Eth(received) => Some(received.value),
+                ReceivedEvent::Erc20(_) => None,
+            })
+            .fold(Wei::ZERO, |total, value| {
+                total.checked_add(value).unwrap_or(Wei::MAX)
+            })
+    }
+
+    pub fn pending_erc20_balance_of(&self, token: &Address, account: &Account) -> Erc20Value {
+        self.deposits_not_spendable_yet(account)
+            .filter_map(|event| match event {
+                ReceivedEvent::Erc20(received) if &received.erc20_contract_address == token => {
+                    Some(received.value)
+                }
+                _ => None,
+            })
+            .fold(Erc20Value::ZERO, |total, value| {
+                total.checked_add(value).unwrap_or(Erc20Value::MAX)
+            })
+    }
+
     /// Quarantine the deposit event to prevent double minting.
     /// WARNING!: It's crucial that this method does not panic,
     /// since it's called inside the clean-up callback, when an unexpected panic did occur before.
     fn record_quarantined_deposit(&mut self, source: EventSource) -> bool {
-        self.events_to_mint.remove(&source);
+        if let Some(event) = self.events_to_mint.remove(&source) {
+            self.quarantined_deposits.insert(source, event);
+        }
         match self.invalid_events.entry(source) {
             btree_map::Entry::Occupied(_) => false,
             btree_map::Entry::Vacant(entry) => {
@@ -217,7 +456,68 @@ impl State {
         }
     }
 
+    pub fn is_quarantined_deposit(&self, source: &EventSource) -> bool {
+        self.invalid_events.get(source) == Some(&InvalidEventReason::QuarantinedDeposit)
+    }
+
+    /// Resolves a quarantined deposit as decided by the admin.
+    /// A released deposit is minted again, a deposit marked as processed is considered minted
+    /// without crediting its beneficiary, and a rejected deposit is never minted.
+    fn record_resolved_quarantined_deposit(
+        &mut self,
+        source: EventSource,
+        resolution: QuarantineResolution,
+        reason: String,
+    ) {
+        assert!(
+            self.is_quarantined_deposit(&source),
+            "BUG: deposit {source:?} is not quarantined"
+        );
+        let event = self.quarantined_deposits.remove(&source);
+        match resolution {
+            QuarantineResolution::Release => {
+                let event = event.unwrap_or_else(|| {
+                    panic!("BUG: cannot release deposit {source:?} without its event")
+                });
+                self.invalid_events.remove(&source);
+                self.events_to_mint.insert(source, event);
+            }
+            QuarantineResolution::MarkProcessed => {
+                self.invalid_events.remove(&source);
+                // The deposit may have been minted right before being quarantined.
+                if !self.minted_events.contains_key(&source) {
+                    let deposit_event = event.unwrap_or_else(|| {
+                        panic!("BUG: cannot mark deposit {source:?} as minted without its event")
+                    });
+                    self.minted_events
+                        .insert(source, MintedEvent { deposit_event });
+                }
+            }
+            QuarantineResolution::Reject => {
+                self.invalid_events
+                    .insert(source, InvalidEventReason::RejectedDeposit(reason));
+            }
+        }
+    }
+
+    /// Moves a deposit that is no longer part of the canonical chain out of the deposits to mint
+    /// and takes back the funds it was expected to bring to the vault.
+    fn record_reorged_deposit(&mut self, source: EventSource) {
+        let event = self
+            .events_to_mint
+            .remove(&source)
+            .unwrap_or_else(|| panic!("BUG: attempted to mark an unknown deposit {source:?} as reorged"));
+        match &event {
+            ReceivedEvent::Eth(received) => self.eth_balance.eth_balance_sub(received.value),
+            ReceivedEvent::Erc20(received) => self
+                .erc20_balances
+                .erc20_sub(received.erc20_contract_address, received.value),
+        }
+        self.reorged_events.insert(source, event);
+    }
+
     fn record_invalid_deposit(&mut self, source: EventSource, error: String) -> bool {
+        self.pending_deposits.remove(&source);
         assert!(
             !self.events_to_mint.contains_key(&source),
             "attempted to mark an accepted event as invalid"
@@ -236,12 +536,8 @@ impl State {
         }
     }
 
-    fn record_successful_mint(
-        &mut self,
-        source: EventSource,
-        principal: Principal,
-        amount: Erc20Value,
-    ) {
+    /// Moves the deposit to the minted events and returns it.
+    fn record_minted_event(&mut self, source: EventSource) -> ReceivedEvent {
         assert!(
             !self.invalid_events.contains_key(&source),
             "attempted to mint an event previously marked as invalid {source:?}"
@@ -251,18 +547,196 @@ impl State {
             None => panic!("attempted to mint ckETH for an unknown event {source:?}"),
         };
         assert_eq!(
-            self.minted_events
-                .insert(source, MintedEvent { deposit_event },),
+            self.minted_events.insert(
+                source,
+                MintedEvent {
+                    deposit_event: deposit_event.clone()
+                },
+            ),
             None,
             "attempted to mint ckETH twice for the same event {source:?}"
         );
-        self.erc20_balances.principal_erc20_add(principal, amount);
+        deposit_event
+    }
+
+    fn record_successful_mint(&mut self, source: EventSource, amount: Erc20Value) {
+        let deposit_event = self.record_minted_event(source);
+        let token = match &deposit_event {
+            ReceivedEvent::Erc20(received) => received.erc20_contract_address,
+            ReceivedEvent::Eth(_) => panic!("BUG: minting ERC-20 for native deposit {source:?}"),
+        };
+        self.erc20_balances
+            .account_erc20_add(token, deposit_event.beneficiary(), amount);
+    }
+
+    fn record_successful_eth_mint(&mut self, source: EventSource, amount: Wei) {
+        let deposit_event = self.record_minted_event(source);
+        if let ReceivedEvent::Erc20(_) = deposit_event {
+            panic!("BUG: minting native coins for ERC-20 deposit {source:?}");
+        }
+        self.eth_balance
+            .account_eth_add(deposit_event.beneficiary(), amount);
+    }
+
+    /// Transfers and approvals recorded before they carried their token are in the default token,
+    /// which cannot change once set.
+    fn expect_default_erc20_token(&self, token: Option<Address>) -> Address {
+        let token = token.unwrap_or(self.default_erc20_token);
+        assert_eq!(
+            token, self.default_erc20_token,
+            "BUG: transfers and approvals are only supported for the default token"
+        );
+        token
+    }
+
+    fn record_erc20_transfer(
+        &mut self,
+        token: Option<Address>,
+        from: Account,
+        to: Account,
+        amount: Erc20Value,
+    ) {
+        let token = self.expect_default_erc20_token(token);
+        self.erc20_balances.account_erc20_sub(token, from, amount);
+        self.erc20_balances.account_erc20_add(token, to, amount);
+    }
+
+    fn record_erc20_approval(
+        &mut self,
+        token: Option<Address>,
+        owner: Account,
+        spender: Account,
+        amount: Erc20Value,
+        expires_at: Option<u64>,
+    ) {
+        self.expect_default_erc20_token(token);
+        self.erc20_allowances
+            .approve(owner, spender, amount, expires_at);
+    }
+
+    fn record_erc20_transfer_from(
+        &mut self,
+        token: Option<Address>,
+        spender: Account,
+        from: Account,
+        to: Account,
+        amount: Erc20Value,
+    ) {
+        self.expect_default_erc20_token(token);
+        self.erc20_allowances.spend(from, spender, amount);
+        self.record_erc20_transfer(token, from, to, amount);
+    }
+
+    pub fn record_erc20_withdrawal_request(&mut self, mut request: Erc20WithdrawalRequest) {
+        // Requests accepted before the vault held several tokens withdrew the default one.
+        let token = *request
+            .erc20_contract_address
+            .get_or_insert(self.default_erc20_token);
+        let fee_recipient = *request
+            .fee_recipient
+            .get_or_insert(Account::from(self.admin));
+        let from = request.from_account();
+        self.erc20_balances
+            .account_erc20_sub(token, from, request.withdrawal_amount);
+        if let Some(fee) = request.withdrawal_fee {
+            self.erc20_balances.account_erc20_sub(token, from, fee);
+            self.erc20_balances
+                .account_erc20_add(token, fee_recipient, fee);
+        }
+        if request.id > self.withdraw_count {
+            self.withdraw_count = request.id.clone();
+        }
+        self.withdrawal_limits.record_outflow(
+            request.id.clone(),
+            request.created_at,
+            request.from,
+            token,
+            request.withdrawal_amount,
+        );
+        self.eth_balance.commit_gas(request.max_transaction_fee);
+        self.eth_transactions.record_withdrawal_request(request);
     }
 
-    pub fn record_erc20_withdrawal_request(&mut self, request: Erc20WithdrawalRequest) {
+    pub fn record_eth_withdrawal_request(&mut self, request: EthWithdrawalRequest) {
+        self.eth_balance.account_eth_sub(
+            to_account(request.from, request.from_subaccount.clone()),
+            request.withdrawal_amount,
+        );
+        self.eth_balance
+            .pending_withdrawals_add(request.withdrawal_amount);
+        if request.ledger_burn_index > self.withdraw_count {
+            self.withdraw_count = request.ledger_burn_index.clone();
+        }
         self.eth_transactions.record_withdrawal_request(request);
     }
 
+    fn record_funded_gas_reserve(&mut self, from: Account, amount: Wei) {
+        self.eth_balance.fund_gas_reserve(from, amount);
+    }
+
+    fn record_cancelled_withdrawal_request(
+        &mut self,
+        withdrawal_id: &Nat,
+        refunded_fee: Option<Erc20Value>,
+    ) {
+        let request = match self
+            .eth_transactions
+            .record_cancelled_withdrawal_request(withdrawal_id)
+        {
+            WithdrawalRequest::CkEth(request) => {
+                self.eth_balance
+                    .pending_withdrawals_sub(request.withdrawal_amount);
+                self.eth_balance.account_eth_add(
+                    to_account(request.from, request.from_subaccount),
+                    request.withdrawal_amount,
+                );
+                return;
+            }
+            WithdrawalRequest::CkErc20(request) => request,
+        };
+        self.eth_balance.release_gas(request.max_transaction_fee);
+        self.withdrawal_limits.release_outflow(&request.id);
+        let token = request.token();
+        let from = request.from_account();
+        self.erc20_balances
+            .account_erc20_add(token, from, request.withdrawal_amount);
+        if let Some(fee) = refunded_fee {
+            self.erc20_balances
+                .account_erc20_sub(token, request.fee_recipient(), fee);
+            self.erc20_balances.account_erc20_add(token, from, fee);
+        }
+    }
+
+    fn record_reimbursed_erc20_withdrawal(&mut self, withdrawal_id: Nat, reimbursed: Reimbursed) {
+        // The withdrawn tokens are given back, so they no longer count toward the limits.
+        self.withdrawal_limits.release_outflow(&withdrawal_id);
+        let request = self.eth_transactions.record_finalized_reimbursement(
+            ReimbursementIndex::CkErc20 { withdrawal_id },
+            reimbursed,
+        );
+        let token = request
+            .erc20_contract_address
+            .unwrap_or(self.default_erc20_token);
+        self.erc20_balances.account_erc20_add(
+            token,
+            to_account(request.to, request.to_subaccount),
+            request.reimbursed_amount.change_units(),
+        );
+    }
+
+    fn record_reimbursed_eth_withdrawal(&mut self, reimbursed: Reimbursed) {
+        let request = self.eth_transactions.record_finalized_reimbursement(
+            ReimbursementIndex::CkEth {
+                ledger_burn_index: reimbursed.burn_in_block.clone(),
+            },
+            reimbursed,
+        );
+        self.eth_balance.account_eth_add(
+            to_account(request.to, request.to_subaccount),
+            request.reimbursed_amount.change_units(),
+        );
+    }
+
     pub fn record_finalized_transaction(
         &mut self,
         withdrawal_id: &Nat,
@@ -283,7 +757,10 @@ impl State {
 
     fn update_balance_upon_deposit(&mut self, event: &ReceivedEvent) {
         match event {
-            ReceivedEvent::Erc20(event) => self.erc20_balances.erc20_add(event.value),
+            ReceivedEvent::Eth(event) => self.eth_balance.eth_balance_add(event.value),
+            ReceivedEvent::Erc20(event) => self
+                .erc20_balances
+                .erc20_add(event.erc20_contract_address, event.value),
         };
     }
 
@@ -292,32 +769,45 @@ impl State {
         withdrawal_id: &Nat,
         receipt: &TransactionReceipt,
     ) {
-        // let tx_fee = receipt.effective_transaction_fee();
         let tx = self
             .eth_transactions
             .get_finalized_transaction(withdrawal_id)
             .expect("BUG: missing finalized transaction");
-        // let withdrawal_request = self
-        //     .eth_transactions
-        //     .get_processed_withdrawal_request(withdrawal_id)
-        //     .expect("BUG: missing withdrawal request");
-        // let charged_tx_fee = match withdrawal_request {
-        //     WithdrawalRequest::CkErc20(req) => req.max_transaction_fee,
-        // };
-        // let unspent_tx_fee = charged_tx_fee.checked_sub(tx_fee).expect(
-        //     "BUG: charged transaction fee MUST always be at least the effective transaction fee",
-        // );
-        // let debited_amount = match receipt.status {
-        //     TransactionStatus::Success => tx
-        //         .transaction()
-        //         .amount
-        //         .checked_add(tx_fee)
-        //         .expect("BUG: debited amount always fits into U256"),
-        //     TransactionStatus::Failure => tx_fee,
-        // };
-        // // self.eth_balance.eth_balance_sub(debited_amount);
-        // self.eth_balance.total_effective_tx_fees_add(tx_fee);
-        // self.eth_balance.total_unspent_tx_fees_add(unspent_tx_fee);
+        let withdrawal_request = self
+            .eth_transactions
+            .get_processed_withdrawal_request(withdrawal_id)
+            .expect("BUG: missing withdrawal request");
+        // Native coin withdrawals pay their transaction fee out of the withdrawn amount,
+        // ERC-20 withdrawals out of the gas reserve.
+        if let WithdrawalRequest::CkErc20(request) = withdrawal_request {
+            self.eth_balance.pay_erc20_tx_fee(
+                request.max_transaction_fee,
+                receipt.effective_transaction_fee(),
+            );
+        }
+        if let WithdrawalRequest::CkEth(request) = withdrawal_request {
+            let tx_fee = receipt.effective_transaction_fee();
+            let charged_tx_fee = request
+                .withdrawal_amount
+                .checked_sub(tx.transaction().amount)
+                .expect("BUG: transaction amount MUST always be at most the withdrawal amount");
+            let unspent_tx_fee = charged_tx_fee.checked_sub(tx_fee).expect(
+                "BUG: charged transaction fee MUST always be at least the effective transaction fee",
+            );
+            let debited_amount = match receipt.status {
+                TransactionStatus::Success => tx
+                    .transaction()
+                    .amount
+                    .checked_add(tx_fee)
+                    .expect("BUG: debited amount always fits into U256"),
+                TransactionStatus::Failure => tx_fee,
+            };
+            self.eth_balance.eth_balance_sub(debited_amount);
+            self.eth_balance
+                .pending_withdrawals_sub(request.withdrawal_amount);
+            self.eth_balance.total_effective_tx_fees_add(tx_fee);
+            self.eth_balance.total_unspent_tx_fees_add(unspent_tx_fee);
+        }
 
         // if receipt.status == TransactionStatus::Success && !tx.transaction_data().is_empty() {
         //     let TransactionCallData::Erc20Transfer { to: _, value } = TransactionCallData::decode(
@@ -332,7 +822,9 @@ impl State {
                 tx.transaction_data(),
             )
             .expect("BUG: failed to decode transaction data from transaction issued by minter");
-            self.erc20_balances.erc20_sub(value);
+            // ERC-20 transfers are sent to the token contract.
+            self.erc20_balances
+                .erc20_sub(tx.transaction().destination, value);
         }
     }
 
@@ -350,25 +842,268 @@ impl State {
         );
     }
 
+    fn record_retried_skipped_block(&mut self, contract_address: Address, block_number: BlockNumber) {
+        let blocks = self
+            .skipped_blocks
+            .get_mut(&contract_address)
+            .filter(|blocks| blocks.remove(&block_number))
+            .unwrap_or_else(|| {
+                panic!("BUG: block {block_number} was not skipped for contract {contract_address}")
+            });
+        if blocks.is_empty() {
+            self.skipped_blocks.remove(&contract_address);
+        }
+    }
+
+    /// Whether the principal may perform the action, either as the admin or through its roles.
+    pub fn has_permission(&self, principal: &Principal, permission: Permission) -> bool {
+        principal == &self.admin || self.roles.has_permission(principal, permission)
+    }
+
+    pub fn is_paused(&self, flow: VaultFlow) -> bool {
+        self.paused_flows.contains(&flow)
+    }
+
+    fn record_updated_pause(&mut self, flow: VaultFlow, paused: bool) {
+        if paused {
+            self.paused_flows.insert(flow);
+        } else {
+            self.paused_flows.remove(&flow);
+        }
+    }
+
     pub fn erc20_balances_by_token_symbol(&self) -> BTreeMap<&CkTokenSymbol, &Erc20Value> {
         self.erc20_balances
-            .principal_balance_by_erc20
+            .balance_by_token
             .iter()
-            .map(|(_, balance)| {
-                let symbol = &self.ckerc20_tokens.1;
+            .map(|(token, balance)| {
+                let symbol = self.ckerc20_tokens.get(token).unwrap_or_else(|| {
+                    panic!("BUG: missing symbol for ERC-20 token {token}")
+                });
                 (symbol, balance)
             })
             .collect()
     }
 
-    pub const fn ethereum_network(&self) -> EthereumNetwork {
-        self.ethereum_network
+    pub fn default_erc20_token_symbol(&self) -> &CkTokenSymbol {
+        self.ckerc20_tokens
+            .get(&self.default_erc20_token)
+            .expect("BUG: default ERC-20 token is not supported")
+    }
+
+    /// Checks that the token can be added to the supported ones.
+    pub fn validate_new_erc20_token(&self, token: &CkErc20Token) -> Result<(), String> {
+        if token.erc20_chain_id != self.evm_network.chain_id {
+            return Err(format!(
+                "expected a token on {} (chain id {}), but got chain id {}",
+                self.evm_network.name, self.evm_network.chain_id, token.erc20_chain_id
+            ));
+        }
+        if self
+            .ckerc20_tokens
+            .contains_key(&token.erc20_contract_address)
+        {
+            return Err(format!(
+                "ERC-20 token {} is already supported",
+                token.erc20_contract_address
+            ));
+        }
+        if self
+            .ckerc20_tokens
+            .values()
+            .any(|symbol| symbol == &token.ckerc20_token_symbol)
+        {
+            return Err(format!(
+                "token symbol {} is already used",
+                token.ckerc20_token_symbol
+            ));
+        }
+        Ok(())
+    }
+
+    fn record_add_ckerc20_token(&mut self, token: CkErc20Token) {
+        assert_eq!(
+            self.evm_network.chain_id, token.erc20_chain_id,
+            "ERROR: Expected chain id {}, but got {}",
+            self.evm_network.chain_id, token.erc20_chain_id
+        );
+        assert!(
+            !self
+                .ckerc20_tokens
+                .contains_key(&token.erc20_contract_address),
+            "ERROR: ERC-20 token {} is already supported",
+            token.erc20_contract_address
+        );
+        assert!(
+            !self
+                .ckerc20_tokens
+                .values()
+                .any(|symbol| symbol == &token.ckerc20_token_symbol),
+            "ERROR: token symbol {} is already used",
+            token.ckerc20_token_symbol
+        );
+        self.ckerc20_tokens
+            .insert(token.erc20_contract_address, token.ckerc20_token_symbol);
+    }
+
+    pub fn evm_network(&self) -> &EvmNetwork {
+        &self.evm_network
+    }
+
+    /// The network resulting from replacing the RPC providers, if valid.
+    pub fn network_with_rpc_providers(
+        &self,
+        providers: Vec<RpcEndpoint>,
+        consensus_threshold: Option<u8>,
+    ) -> Result<EvmNetwork, String> {
+        let network = EvmNetwork {
+            rpc_endpoints: providers,
+            consensus_threshold: consensus_threshold
+                .unwrap_or(self.evm_network.consensus_threshold),
+            ..self.evm_network.clone()
+        };
+        network.validate()?;
+        Ok(network)
+    }
+
+    fn record_updated_rpc_providers(
+        &mut self,
+        providers: Vec<RpcEndpoint>,
+        consensus_threshold: Option<u8>,
+    ) {
+        self.rpc_providers_set_by_admin = !providers.is_empty();
+        self.evm_network = self
+            .network_with_rpc_providers(providers, consensus_threshold)
+            .unwrap_or_else(|e| panic!("BUG: invalid RPC providers: {e}"));
+    }
+
+    /// Checks that the proposed change can be applied to the current state.
+    pub fn validate_proposal_action(&self, action: &ProposalAction) -> Result<(), String> {
+        match action {
+            ProposalAction::UpdateConfig(upgrade_arg) => self
+                .validate_upgrade(upgrade_arg)
+                .map_err(|e| format!("invalid configuration: {:?}", e)),
+            ProposalAction::UpdateRpcProviders {
+                providers,
+                consensus_threshold,
+            } => self
+                .network_with_rpc_providers(providers.clone(), *consensus_threshold)
+                .map(|_| ()),
+            ProposalAction::SetAdmin(admin) => {
+                if admin == &Principal::anonymous() {
+                    return Err("the admin cannot be anonymous".to_string());
+                }
+                Ok(())
+            }
+            ProposalAction::UpdatePolicy(policy) => policy.validate(),
+            ProposalAction::GrantRole { principal, .. } => {
+                if principal == &Principal::anonymous() {
+                    return Err("roles cannot be granted to the anonymous principal".to_string());
+                }
+                Ok(())
+            }
+            ProposalAction::RevokeRole { principal, role } => {
+                if !self.roles.has_role(principal, *role) {
+                    return Err(format!("{} does not hold the {:?} role", principal, role));
+                }
+                Ok(())
+            }
+            ProposalAction::AddErc20Token(token) => self.validate_new_erc20_token(token),
+        }
+    }
+
+    /// Checks a change made through an admin endpoint rather than a proposal,
+    /// which is only allowed until a proposal policy is set.
+    pub fn validate_direct_change(&self, action: &ProposalAction) -> Result<(), String> {
+        if self.proposals.is_enabled() {
+            return Err(format!(
+                "{} can only be changed through a proposal",
+                action.target()
+            ));
+        }
+        self.validate_proposal_action(action)
+    }
+
+    fn record_executed_proposal(&mut self, id: u64) {
+        match self.proposals.take(id).action {
+            ProposalAction::UpdateConfig(upgrade_arg) => self
+                .upgrade(upgrade_arg)
+                .expect("BUG: executing a validated proposal should succeed"),
+            ProposalAction::UpdateRpcProviders {
+                providers,
+                consensus_threshold,
+            } => self.record_updated_rpc_providers(providers, consensus_threshold),
+            ProposalAction::SetAdmin(admin) => self.admin = admin,
+            ProposalAction::UpdatePolicy(policy) => self.proposals.set_policy(policy),
+            ProposalAction::GrantRole { principal, role } => self.roles.grant(principal, role),
+            ProposalAction::RevokeRole { principal, role } => self.roles.revoke(&principal, role),
+            ProposalAction::AddErc20Token(token) => self.record_add_ckerc20_token(token),
+        }
     }
 
     pub const fn ethereum_block_height(&self) -> BlockTag {
         self.ethereum_block_height
     }
 
+    /// Checks a configuration change before it is recorded.
+    /// Stricter than replaying the change, since older upgrade events were not subject to these checks.
+    pub fn validate_upgrade(&self, upgrade_arg: &UpgradeArg) -> Result<(), InvalidStateError> {
+        use std::str::FromStr;
+
+        if let Some(address) = &upgrade_arg.ckerc20_token_address {
+            let address = Address::from_str(address)
+                .map_err(|e| InvalidStateError::InvalidCkErc20Address(format!("ERROR: {}", e)))?;
+            // Transfers, approvals and the ICRC-3 block log are in the default token.
+            if address != self.default_erc20_token {
+                return Err(InvalidStateError::InvalidCkErc20Address(format!(
+                    "ERROR: cannot change the default token from {} to {}, use add_erc20_token to support other tokens",
+                    self.default_erc20_token, address
+                )));
+            }
+        }
+        if let Some(symbol) = &upgrade_arg.ckerc20_token_symbol {
+            let symbol = CkTokenSymbol::from_str(symbol)
+                .map_err(|e| InvalidStateError::InvalidCkTokenSymbol(format!("ERROR: {}", e)))?;
+            if self
+                .ckerc20_tokens
+                .iter()
+                .any(|(token, used)| token != &self.default_erc20_token && used == &symbol)
+            {
+                return Err(InvalidStateError::InvalidCkTokenSymbol(format!(
+                    "ERROR: token symbol {} is already used",
+                    symbol
+                )));
+            }
+        }
+        if self.rpc_providers_set_by_admin
+            && upgrade_arg
+                .network
+                .as_ref()
+                .is_some_and(|network| !network.rpc_endpoints.is_empty())
+        {
+            return Err(InvalidStateError::InvalidEvmNetwork(
+                "ERROR: the RPC providers were set through update_rpc_providers, which must also be used to replace them"
+                    .to_string(),
+            ));
+        }
+        self.clone().upgrade(upgrade_arg.clone())
+    }
+
+    /// Checks a configuration change passed when upgrading the canister, which bypasses proposals.
+    pub fn validate_upgrade_without_proposal(
+        &self,
+        upgrade_arg: &UpgradeArg,
+    ) -> Result<(), InvalidStateError> {
+        let sensitive_fields = upgrade_arg.sensitive_fields();
+        if self.proposals.is_enabled() && !sensitive_fields.is_empty() {
+            return Err(InvalidStateError::RequiresProposal(format!(
+                "ERROR: {} can only be changed through a proposal",
+                sensitive_fields.join(", ")
+            )));
+        }
+        self.validate_upgrade(upgrade_arg)
+    }
+
     fn upgrade(&mut self, upgrade_args: UpgradeArg) -> Result<(), InvalidStateError> {
         use std::str::FromStr;
 
@@ -382,6 +1117,10 @@ impl State {
             ckerc20_token_address,
             ckerc20_token_symbol,
             withdraw_fee_value,
+            network,
+            withdrawal_limits,
+            erc20_minimum_withdrawal_amounts,
+            gas_reserve_top_up,
         } = upgrade_args;
 
         if let Some(nonce) = next_transaction_nonce {
@@ -410,6 +1149,22 @@ impl State {
         if let Some(block_height) = ethereum_block_height {
             self.ethereum_block_height = block_height.into();
         }
+        if let Some(network) = network {
+            // Transactions and deposits already recorded belong to the current chain.
+            if network.chain_id != self.evm_network.chain_id {
+                return Err(InvalidStateError::InvalidEvmNetwork(format!(
+                    "ERROR: cannot change chain id from {} to {}",
+                    self.evm_network.chain_id, network.chain_id
+                )));
+            }
+            if let Some(finality) = network.finality.clone() {
+                self.ethereum_block_height = finality.into();
+            }
+            self.evm_network = self
+                .evm_network
+                .upgraded(network)
+                .map_err(|e| InvalidStateError::InvalidEvmNetwork(format!("ERROR: {}", e)))?;
+        }
         if let Some(evm_id) = evm_rpc_id {
             if evm_id == Principal::management_canister() {
                 self.evm_rpc_id = None;
@@ -423,13 +1178,45 @@ impl State {
             let ckerc20_token_symbol = CkTokenSymbol::from_str(&symbol)
                 .map_err(|e| InvalidStateError::InvalidCkTokenSymbol(format!("ERROR: {}", e)))?;
 
-            self.ckerc20_tokens = (ckerc20_token_address, ckerc20_token_symbol);
+            // Tokens previously held by the vault remain supported.
+            self.ckerc20_tokens
+                .insert(ckerc20_token_address, ckerc20_token_symbol);
+            self.default_erc20_token = ckerc20_token_address;
         }
         if let Some(fee_value) = withdraw_fee_value {
             self.withdraw_fee_value = Erc20Value::try_from(fee_value)
                 .map_err(|e| InvalidStateError::InvalidWithdrawFeeValue(format!("ERROR: {}", e)))?;
         }
-        
+        for arg in withdrawal_limits.unwrap_or_default() {
+            let invalid =
+                |e: String| InvalidStateError::InvalidWithdrawalLimits(format!("ERROR: {}", e));
+            let token = Address::from_str(&arg.erc20_contract_address)
+                .map_err(|e| invalid(e.to_string()))?;
+            if !self.ckerc20_tokens.contains_key(&token) {
+                return Err(invalid(format!("unsupported ERC-20 token {}", token)));
+            }
+            let limits = TokenWithdrawalLimits::try_from(arg).map_err(invalid)?;
+            self.withdrawal_limits.set(token, limits);
+        }
+        for arg in erc20_minimum_withdrawal_amounts.unwrap_or_default() {
+            let invalid = |e: String| {
+                InvalidStateError::InvalidErc20MinimumWithdrawalAmount(format!("ERROR: {}", e))
+            };
+            let token = Address::from_str(&arg.erc20_contract_address)
+                .map_err(|e| invalid(e.to_string()))?;
+            let minimum = Erc20Value::try_from(arg.minimum_withdrawal_amount).map_err(invalid)?;
+            if minimum == Erc20Value::ZERO {
+                self.erc20_minimum_withdrawal_amounts.remove(&token);
+            } else {
+                self.erc20_minimum_withdrawal_amounts.insert(token, minimum);
+            }
+        }
+        if let Some(amount) = gas_reserve_top_up {
+            let amount = Wei::try_from(amount)
+                .map_err(|e| InvalidStateError::InvalidGasReserveTopUp(format!("ERROR: {}", e)))?;
+            self.eth_balance.top_up_gas_reserve(amount);
+        }
+
         self.validate_config()
     }
 
@@ -441,10 +1228,14 @@ impl State {
         //
         // For example, we don't compare:
         // 1. Computed fields and caches, such as `ecdsa_public_key`.
-        // 2. Transient fields, such as `active_tasks`.
+        // 2. Transient fields, such as `active_tasks` and `pending_deposits`.
         use ic_utils_ensure::ensure_eq;
 
-        ensure_eq!(self.ethereum_network, other.ethereum_network);
+        ensure_eq!(self.evm_network, other.evm_network);
+        ensure_eq!(
+            self.rpc_providers_set_by_admin,
+            other.rpc_providers_set_by_admin
+        );
         ensure_eq!(self.ecdsa_key_name, other.ecdsa_key_name);
         ensure_eq!(
             self.cketh_minimum_withdrawal_amount,
@@ -462,12 +1253,35 @@ impl State {
         ensure_eq!(self.events_to_mint, other.events_to_mint);
         ensure_eq!(self.minted_events, other.minted_events);
         ensure_eq!(self.invalid_events, other.invalid_events);
+        ensure_eq!(self.reorged_events, other.reorged_events);
+        ensure_eq!(self.quarantined_deposits, other.quarantined_deposits);
+        ensure_eq!(self.paused_flows, other.paused_flows);
+        ensure_eq!(self.admin, other.admin);
+        ensure_eq!(self.roles, other.roles);
+        ensure_eq!(self.proposals, other.proposals);
+        ensure_eq!(self.withdrawal_limits, other.withdrawal_limits);
+        ensure_eq!(
+            self.erc20_minimum_withdrawal_amounts,
+            other.erc20_minimum_withdrawal_amounts
+        );
         ensure_eq!(self.ckerc20_tokens, other.ckerc20_tokens);
+        ensure_eq!(self.default_erc20_token, other.default_erc20_token);
+        ensure_eq!(self.erc20_allowances, other.erc20_allowances);
+        ensure_eq!(self.recent_erc20_transfers, other.recent_erc20_transfers);
+        ensure_eq!(self.eth_balance, other.eth_balance);
 
         self.eth_transactions
             .is_equivalent_to(&other.eth_transactions)
     }
 
+    /// Part of the balance of `account` in `token` that is not reserved by a pending flow.
+    pub fn available_erc20_balance(&self, token: &Address, account: &Account) -> Erc20Value {
+        self.erc20_balances
+            .balance_of(token, account)
+            .checked_sub(self.erc20_holds.held(token, account))
+            .unwrap_or(Erc20Value::ZERO)
+    }
+
     pub fn eth_balance(&self) -> &EthBalance {
         &self.eth_balance
     }
@@ -559,6 +1373,19 @@ pub struct EthBalance {
     /// Total amount of fees that were charged to the user during the withdrawal
     /// but not consumed by the finalized transaction ckETH -> ETH
     total_unspent_tx_fees: Wei,
+    /// Vault balance of native coins of each account.
+    balance_by_account: BTreeMap<Account, Wei>,
+    /// Amount of native withdrawals debited from their account whose transaction
+    /// is not finalized yet.
+    pending_withdrawals: Wei,
+    /// Native coins owned by no account and set aside to pay the transaction fees
+    /// of ERC-20 withdrawals, which are sent from the same address as native withdrawals.
+    gas_reserve: Wei,
+    /// Part of the gas reserve covering the maximum transaction fee of ERC-20 withdrawals
+    /// that are not finalized yet.
+    committed_gas: Wei,
+    /// Total amount of fees across all finalized ERC-20 withdrawal transactions.
+    total_erc20_tx_fees: Wei,
 }
 
 impl Default for EthBalance {
@@ -567,6 +1394,11 @@ impl Default for EthBalance {
             eth_balance: Wei::ZERO,
             total_effective_tx_fees: Wei::ZERO,
             total_unspent_tx_fees: Wei::ZERO,
+            balance_by_account: BTreeMap::default(),
+            pending_withdrawals: Wei::ZERO,
+            gas_reserve: Wei::ZERO,
+            committed_gas: Wei::ZERO,
+            total_erc20_tx_fees: Wei::ZERO,
         }
     }
 }
@@ -625,37 +1457,170 @@ impl EthBalance {
     pub fn total_unspent_tx_fees(&self) -> Wei {
         self.total_unspent_tx_fees
     }
-}
 
-#[derive(Clone, Eq, PartialEq, Debug)]
-pub struct Erc20Balances {
-    erc20_balance: Erc20Value,
-    principal_balance_by_erc20: BTreeMap<Principal, Erc20Value>,
-}
+    pub fn balance_of(&self, account: &Account) -> Wei {
+        *self
+            .balance_by_account
+            .get(account)
+            .unwrap_or(&Wei::ZERO)
+    }
 
-impl Default for Erc20Balances {
-    fn default() -> Self {
-        Self {
-            erc20_balance: 0u8.into(),
-            principal_balance_by_erc20: BTreeMap::default(),
+    pub fn gas_reserve(&self) -> Wei {
+        self.gas_reserve
+    }
+
+    pub fn total_erc20_tx_fees(&self) -> Wei {
+        self.total_erc20_tx_fees
+    }
+
+    /// Part of the gas reserve that is not committed to ERC-20 withdrawals yet.
+    pub fn available_gas_reserve(&self) -> Wei {
+        self.gas_reserve
+            .checked_sub(self.committed_gas)
+            .unwrap_or(Wei::ZERO)
+    }
+
+    /// Native coins that can be withdrawn without drawing on the gas reserve
+    /// or on the amounts of native withdrawals that are still in flight.
+    pub fn withdrawable_balance(&self) -> Wei {
+        self.eth_balance
+            .checked_sub(self.pending_withdrawals)
+            .and_then(|balance| balance.checked_sub(self.gas_reserve))
+            .unwrap_or(Wei::ZERO)
+    }
+
+    fn fund_gas_reserve(&mut self, from: Account, amount: Wei) {
+        self.account_eth_sub(from, amount);
+        self.gas_reserve = self.gas_reserve.checked_add(amount).unwrap_or_else(|| {
+            panic!(
+                "BUG: overflow when adding {} to {}",
+                amount, self.gas_reserve
+            )
+        });
+    }
+
+    /// Adds native coins held by the minter but owned by no account to the gas reserve.
+    fn top_up_gas_reserve(&mut self, amount: Wei) {
+        self.eth_balance_add(amount);
+        self.gas_reserve = self.gas_reserve.checked_add(amount).unwrap_or_else(|| {
+            panic!(
+                "BUG: overflow when adding {} to {}",
+                amount, self.gas_reserve
+            )
+        });
+    }
+
+    fn commit_gas(&mut self, max_transaction_fee: Wei) {
+        self.committed_gas = self
+            .committed_gas
+            .checked_add(max_transaction_fee)
+            .expect("BUG: committed gas always fits into U256");
+    }
+
+    fn release_gas(&mut self, max_transaction_fee: Wei) {
+        self.committed_gas = self
+            .committed_gas
+            .checked_sub(max_transaction_fee)
+            .expect("BUG: released more gas than was committed");
+    }
+
+    /// Pays the fee of a finalized ERC-20 withdrawal transaction out of the gas reserve.
+    fn pay_erc20_tx_fee(&mut self, max_transaction_fee: Wei, tx_fee: Wei) {
+        self.release_gas(max_transaction_fee);
+        // Withdrawals accepted before the reserve existed were paid out of native coins
+        // that the minter held beyond what is tracked here.
+        let paid_from_reserve = tx_fee.min(self.gas_reserve);
+        self.gas_reserve = self
+            .gas_reserve
+            .checked_sub(paid_from_reserve)
+            .expect("BUG: paid at most the gas reserve");
+        self.eth_balance_sub(paid_from_reserve);
+        self.total_erc20_tx_fees = self
+            .total_erc20_tx_fees
+            .checked_add(tx_fee)
+            .expect("BUG: total ERC-20 transaction fees always fit into U256");
+    }
+
+    fn pending_withdrawals_add(&mut self, amount: Wei) {
+        self.pending_withdrawals = self
+            .pending_withdrawals
+            .checked_add(amount)
+            .expect("BUG: pending withdrawals always fit into U256");
+    }
+
+    fn pending_withdrawals_sub(&mut self, amount: Wei) {
+        self.pending_withdrawals = self
+            .pending_withdrawals
+            .checked_sub(amount)
+            .expect("BUG: finalized more native withdrawals than were pending");
+    }
+
+    fn account_eth_add(&mut self, account: Account, deposit: Wei) {
+        let previous_value = self.balance_of(&account);
+        let new_value = previous_value.checked_add(deposit).unwrap_or_else(|| {
+            panic!(
+                "BUG: overflow when adding {} to {}",
+                deposit, previous_value
+            )
+        });
+        self.balance_by_account.insert(account, new_value);
+    }
+
+    fn account_eth_sub(&mut self, account: Account, withdrawal_amount: Wei) {
+        let previous_value = self.balance_of(&account);
+        let new_value = previous_value
+            .checked_sub(withdrawal_amount)
+            .unwrap_or_else(|| {
+                panic!(
+                    "BUG: underflow when subtracting {} from {}",
+                    withdrawal_amount, previous_value
+                )
+            });
+        if new_value == Wei::ZERO {
+            self.balance_by_account.remove(&account);
+        } else {
+            self.balance_by_account.insert(account, new_value);
         }
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Erc20Balances {
+    /// Amount of each ERC-20 token held by the minter on Ethereum.
+    balance_by_token: BTreeMap<Address, Erc20Value>,
+    /// Vault balance of each account, per ERC-20 token.
+    balance_by_account: BTreeMap<(Address, Account), Erc20Value>,
+}
+
 impl Erc20Balances {
-    pub fn get_erc20_balance(&self) -> Erc20Value {
-        self.erc20_balance
+    pub fn get_erc20_balance(&self, token: &Address) -> Erc20Value {
+        *self
+            .balance_by_token
+            .get(token)
+            .unwrap_or(&Erc20Value::ZERO)
     }
 
-    pub fn balance_of(&self, principal: &Principal) -> Erc20Value {
+    pub fn balance_of(&self, token: &Address, account: &Account) -> Erc20Value {
         *self
-            .principal_balance_by_erc20
-            .get(principal)
+            .balance_by_account
+            .get(&(*token, *account))
             .unwrap_or(&Erc20Value::ZERO)
     }
 
-    pub fn principal_erc20_add(&mut self, erc20_contract: Principal, deposit: Erc20Value) {
-        match self.principal_balance_by_erc20.get(&erc20_contract) {
+    /// Sum of the balances in `token` held by all accounts in the vault.
+    pub fn total_supply(&self, token: &Address) -> Erc20Value {
+        self.balance_by_account
+            .iter()
+            .filter(|((balance_token, _), _)| balance_token == token)
+            .fold(Erc20Value::ZERO, |total, (_, balance)| {
+                total
+                    .checked_add(*balance)
+                    .expect("BUG: total supply always fits into U256")
+            })
+    }
+
+    pub fn account_erc20_add(&mut self, token: Address, account: Account, deposit: Erc20Value) {
+        match self.balance_by_account.get(&(token, account)) {
             Some(previous_value) => {
                 let new_value = previous_value.checked_add(deposit).unwrap_or_else(|| {
                     panic!(
@@ -663,25 +1628,21 @@ impl Erc20Balances {
                         deposit, previous_value
                     )
                 });
-                self.principal_balance_by_erc20
-                    .insert(erc20_contract, new_value);
+                self.balance_by_account.insert((token, account), new_value);
             }
             None => {
-                self.principal_balance_by_erc20
-                    .insert(erc20_contract, deposit);
+                self.balance_by_account.insert((token, account), deposit);
             }
         }
     }
 
-    pub fn principal_erc20_sub(
+    pub fn account_erc20_sub(
         &mut self,
-        erc20_contract: Principal,
+        token: Address,
+        account: Account,
         withdrawal_amount: Erc20Value,
     ) {
-        let previous_value = self
-            .principal_balance_by_erc20
-            .get(&erc20_contract)
-            .expect("BUG: Cannot subtract from a missing ERC-20 balance");
+        let previous_value = self.balance_of(&token, &account);
         let new_value = previous_value
             .checked_sub(withdrawal_amount)
             .unwrap_or_else(|| {
@@ -690,29 +1651,35 @@ impl Erc20Balances {
                     withdrawal_amount, previous_value
                 )
             });
-        self.principal_balance_by_erc20
-            .insert(erc20_contract, new_value);
+        if new_value == Erc20Value::ZERO {
+            self.balance_by_account.remove(&(token, account));
+        } else {
+            self.balance_by_account.insert((token, account), new_value);
+        }
     }
 
-    pub fn erc20_add(&mut self, deposit: Erc20Value) {
-        self.erc20_balance = self.erc20_balance.checked_add(deposit).unwrap_or_else(|| {
+    pub fn erc20_add(&mut self, token: Address, deposit: Erc20Value) {
+        let previous_value = self.get_erc20_balance(&token);
+        let new_value = previous_value.checked_add(deposit).unwrap_or_else(|| {
             panic!(
                 "BUG: overflow when adding {} to {}",
-                deposit, self.erc20_balance
+                deposit, previous_value
             )
         });
+        self.balance_by_token.insert(token, new_value);
     }
 
-    pub fn erc20_sub(&mut self, withdrawal_amount: Erc20Value) {
-        self.erc20_balance = self
-            .erc20_balance
+    pub fn erc20_sub(&mut self, token: Address, withdrawal_amount: Erc20Value) {
+        let previous_value = self.get_erc20_balance(&token);
+        let new_value = previous_value
             .checked_sub(withdrawal_amount)
             .unwrap_or_else(|| {
                 panic!(
                     "BUG: underflow when subtracting {} from {}",
-                    withdrawal_amount, self.erc20_balance
+                    withdrawal_amount, previous_value
                 )
             });
+        self.balance_by_token.insert(token, new_value);
     }
 }
 
@@ -721,7 +1688,26 @@ pub enum TaskType {
     Mint,
     RetrieveEth,
     ScrapEthLogs,
+    ScrapPendingDeposits,
+    RetrySkippedBlocks,
+    Rescrape,
     RefreshGasFeeEstimate,
     Reimbursement,
     MintCkErc20,
 }
+
+impl TaskType {
+    /// Flow of funds that the task belongs to, and that stops it when paused.
+    pub fn flow(&self) -> Option<VaultFlow> {
+        match self {
+            TaskType::Mint
+            | TaskType::ScrapEthLogs
+            | TaskType::ScrapPendingDeposits
+            | TaskType::RetrySkippedBlocks
+            | TaskType::Rescrape
+            | TaskType::MintCkErc20 => Some(VaultFlow::Deposits),
+            TaskType::RetrieveEth => Some(VaultFlow::Transactions),
+            TaskType::RefreshGasFeeEstimate | TaskType::Reimbursement => None,
+        }
+    }
+}
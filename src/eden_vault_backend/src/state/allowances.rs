@@ -0,0 +1,77 @@
+use crate::numeric::Erc20Value;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+/// Amount that a spender may transfer on behalf of an owner.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct Allowance {
+    pub amount: Erc20Value,
+    /// Time in nanoseconds since the UNIX epoch after which the allowance is no longer usable.
+    pub expires_at: Option<u64>,
+}
+
+impl Allowance {
+    pub const ZERO: Allowance = Allowance {
+        amount: Erc20Value::ZERO,
+        expires_at: None,
+    };
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at <= now)
+    }
+}
+
+/// ICRC-2 allowances granted over the vault balances of the default token.
+/// Computed based on audit events.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Erc20Allowances {
+    allowances: BTreeMap<(Account, Account), Allowance>,
+}
+
+impl Erc20Allowances {
+    /// Returns the allowance usable at time `now`. Expired allowances are reported as zero.
+    pub fn allowance(&self, owner: &Account, spender: &Account, now: u64) -> Allowance {
+        match self.allowances.get(&(*owner, *spender)) {
+            Some(allowance) if !allowance.is_expired(now) => *allowance,
+            _ => Allowance::ZERO,
+        }
+    }
+
+    /// Replaces the allowance of `spender` over the funds of `owner`.
+    pub fn approve(
+        &mut self,
+        owner: Account,
+        spender: Account,
+        amount: Erc20Value,
+        expires_at: Option<u64>,
+    ) {
+        if amount == Erc20Value::ZERO {
+            self.allowances.remove(&(owner, spender));
+        } else {
+            self.allowances
+                .insert((owner, spender), Allowance { amount, expires_at });
+        }
+    }
+
+    /// Decreases the allowance of `spender` over the funds of `owner` by `amount`.
+    /// Expiration must be checked by the caller, since this is also used when replaying events.
+    pub fn spend(&mut self, owner: Account, spender: Account, amount: Erc20Value) {
+        let key = (owner, spender);
+        let allowance = self
+            .allowances
+            .get_mut(&key)
+            .expect("BUG: Cannot spend a missing allowance");
+        allowance.amount = allowance.amount.checked_sub(amount).unwrap_or_else(|| {
+            panic!(
+                "BUG: underflow when subtracting {} from allowance {}",
+                amount, allowance.amount
+            )
+        });
+        if allowance.amount == Erc20Value::ZERO {
+            self.allowances.remove(&key);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use crate::numeric::Erc20Value;
+use crate::state::allowances::{Allowance, Erc20Allowances};
+use candid::Principal;
+use icrc_ledger_types::icrc1::account::Account;
+
+const NOW: u64 = 1_700_000_000_000_000_000;
+
+fn owner() -> Account {
+    Account::from(Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap())
+}
+
+fn spender() -> Account {
+    Account {
+        owner: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+        subaccount: Some([1; 32]),
+    }
+}
+
+#[test]
+fn should_return_zero_allowance_when_none_granted() {
+    let allowances = Erc20Allowances::default();
+
+    assert_eq!(allowances.allowance(&owner(), &spender(), NOW), Allowance::ZERO);
+}
+
+#[test]
+fn should_replace_allowance_on_approve() {
+    let mut allowances = Erc20Allowances::default();
+
+    allowances.approve(owner(), spender(), Erc20Value::from(100_u64), None);
+    allowances.approve(owner(), spender(), Erc20Value::from(50_u64), Some(NOW + 1));
+
+    assert_eq!(
+        allowances.allowance(&owner(), &spender(), NOW),
+        Allowance {
+            amount: Erc20Value::from(50_u64),
+            expires_at: Some(NOW + 1),
+        }
+    );
+    assert_eq!(
+        allowances.allowance(&spender(), &owner(), NOW),
+        Allowance::ZERO
+    );
+}
+
+#[test]
+fn should_treat_expired_allowance_as_zero() {
+    let mut allowances = Erc20Allowances::default();
+
+    allowances.approve(owner(), spender(), Erc20Value::from(100_u64), Some(NOW));
+
+    assert_eq!(allowances.allowance(&owner(), &spender(), NOW), Allowance::ZERO);
+    assert_eq!(
+        allowances.allowance(&owner(), &spender(), NOW - 1).amount,
+        Erc20Value::from(100_u64)
+    );
+}
+
+#[test]
+fn should_spend_allowance_and_remove_it_when_exhausted() {
+    let mut allowances = Erc20Allowances::default();
+    allowances.approve(owner(), spender(), Erc20Value::from(100_u64), None);
+
+    allowances.spend(owner(), spender(), Erc20Value::from(40_u64));
+    assert_eq!(
+        allowances.allowance(&owner(), &spender(), NOW).amount,
+        Erc20Value::from(60_u64)
+    );
+
+    allowances.spend(owner(), spender(), Erc20Value::from(60_u64));
+    assert_eq!(allowances, Erc20Allowances::default());
+}
+
+#[test]
+#[should_panic(expected = "BUG: underflow")]
+fn should_panic_when_spending_more_than_allowance() {
+    let mut allowances = Erc20Allowances::default();
+    allowances.approve(owner(), spender(), Erc20Value::from(100_u64), None);
+
+    allowances.spend(owner(), spender(), Erc20Value::from(101_u64));
+}
+
+#[test]
+fn should_distinguish_subaccounts_of_the_same_principal() {
+    let mut allowances = Erc20Allowances::default();
+    let spender_default_account = Account::from(spender().owner);
+
+    allowances.approve(owner(), spender(), Erc20Value::from(100_u64), None);
+
+    assert_eq!(
+        allowances.allowance(&owner(), &spender_default_account, NOW),
+        Allowance::ZERO
+    );
+}
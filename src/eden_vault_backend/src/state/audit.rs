@@ -2,10 +2,14 @@
 mod tests;
 
 pub use super::event::{Event, EventType};
+use super::dedup::{TransferKey, TransferKind};
+use super::transactions::to_account;
 use super::State;
-use crate::storage::{record_event, with_event_iter};
-use ic_canister_log::log;
-use crate::logs::INFO;
+use crate::icrc::blocks::{certify_tip, operations};
+use crate::storage::{
+    append_block, block_address, record_event, total_block_count, unblock_address,
+    with_event_iter,
+};
 /// Updates the state to reflect the given state transition.
 // public because it's used in tests since process_event
 // requires canister infrastructure to retrieve time
@@ -19,6 +23,9 @@ pub fn apply_state_transition(state: &mut State, payload: &EventType) {
                 .upgrade(upgrade_arg.clone())
                 .expect("applying upgrade event should succeed");
         }
+        EventType::AcceptedDeposit(eth_event) => {
+            state.record_event_to_mint(&eth_event.clone().into());
+        }
         EventType::AcceptedErc20Deposit(erc20_event) => {
             state.record_event_to_mint(&erc20_event.clone().into());
         }
@@ -30,10 +37,17 @@ pub fn apply_state_transition(state: &mut State, payload: &EventType) {
         }
         EventType::MintedCkErc20 {
             event_source,
-            principal,
+            principal: _,
+            amount,
+        } => {
+            state.record_successful_mint(*event_source, *amount);
+        }
+        EventType::MintedCkEth {
+            event_source,
+            principal: _,
             amount,
         } => {
-            state.record_successful_mint(*event_source, *principal, *amount);
+            state.record_successful_eth_mint(*event_source, *amount);
         }
         EventType::SyncedToBlock { block_number } => {
             state.last_scraped_block_number = *block_number;
@@ -77,31 +91,245 @@ pub fn apply_state_transition(state: &mut State, payload: &EventType) {
         } => {
             state.record_skipped_block_for_contract(*contract_address, *block_number);
         }
+        EventType::UpdatedPause { flow, paused } => {
+            state.record_updated_pause(*flow, *paused);
+        }
+        EventType::GrantedRole { principal, role } => {
+            state.roles.grant(*principal, *role);
+        }
+        EventType::RevokedRole { principal, role } => {
+            state.roles.revoke(principal, *role);
+        }
+        EventType::UpdatedAdmin { admin } => {
+            state.admin = *admin;
+        }
+        EventType::RetriedSkippedBlock {
+            contract_address,
+            block_number,
+        } => {
+            state.record_retried_skipped_block(*contract_address, *block_number);
+        }
+        EventType::AcceptedEthWithdrawalRequest(request) => {
+            state.record_eth_withdrawal_request(request.clone())
+        }
         EventType::AcceptedErc20WithdrawalRequest(request) => {
             state.record_erc20_withdrawal_request(request.clone())
         }
+        EventType::AddedCkErc20Token(token) => {
+            state.record_add_ckerc20_token(token.clone());
+        }
+        EventType::CancelledWithdrawalRequest {
+            withdrawal_id,
+            refunded_fee,
+        } => {
+            state.record_cancelled_withdrawal_request(withdrawal_id, *refunded_fee);
+        }
         EventType::QuarantinedDeposit { event_source } => {
             state.record_quarantined_deposit(*event_source);
         }
+        EventType::ReorgedDeposit {
+            event_source,
+            canonical_block_hash: _,
+        } => {
+            state.record_reorged_deposit(*event_source);
+        }
+        EventType::ReimbursedEthWithdrawal(reimbursed) => {
+            state.record_reimbursed_eth_withdrawal(reimbursed.clone());
+        }
+        EventType::ReimbursedErc20Withdrawal {
+            withdrawal_id,
+            reimbursed,
+        } => {
+            state.record_reimbursed_erc20_withdrawal(withdrawal_id.clone(), reimbursed.clone());
+        }
         EventType::QuarantinedReimbursement { index } => {
             state
                 .eth_transactions
                 .record_quarantined_reimbursement(index.clone());
         }
-        EventType::Erc20TransferCompleted { from, to, amount } => {
-            log!(
-                INFO,
-                "ERC-20 transfer completed: from {:?} to {:?}, amount {:?}",
-                from.to_text(), to.to_text(), amount
+        EventType::ResolvedQuarantinedDeposit {
+            event_source,
+            resolution,
+            reason,
+        } => {
+            state.record_resolved_quarantined_deposit(*event_source, *resolution, reason.clone());
+        }
+        EventType::ResolvedQuarantinedReimbursement {
+            index,
+            resolution,
+            reason,
+        } => {
+            state.eth_transactions.record_resolved_quarantined_reimbursement(
+                index.clone(),
+                *resolution,
+                reason.clone(),
             );
         }
+        EventType::Erc20TransferCompleted {
+            from,
+            to,
+            amount,
+            from_subaccount,
+            to_subaccount,
+            token,
+            memo,
+            created_at_time,
+            block_index,
+        } => {
+            let from = to_account(*from, from_subaccount.clone());
+            let to = to_account(*to, to_subaccount.clone());
+            state.record_erc20_transfer(*token, from, to, *amount);
+            if let (Some(created_at_time), Some(block_index)) = (created_at_time, block_index) {
+                state.recent_erc20_transfers.record(
+                    TransferKey {
+                        created_at_time: *created_at_time,
+                        from,
+                        kind: TransferKind::Transfer { to, spender: None },
+                        amount: *amount,
+                        memo: memo.as_ref().map(|memo| memo.to_vec()),
+                    },
+                    *block_index,
+                );
+            }
+        }
+        EventType::Erc20Approved {
+            owner,
+            spender,
+            amount,
+            expires_at,
+            owner_subaccount,
+            spender_subaccount,
+            token,
+            memo,
+            created_at_time,
+            block_index,
+        } => {
+            let owner = to_account(*owner, owner_subaccount.clone());
+            let spender = to_account(*spender, spender_subaccount.clone());
+            state.record_erc20_approval(*token, owner, spender, *amount, *expires_at);
+            if let (Some(created_at_time), Some(block_index)) = (created_at_time, block_index) {
+                state.recent_erc20_transfers.record(
+                    TransferKey {
+                        created_at_time: *created_at_time,
+                        from: owner,
+                        kind: TransferKind::Approve {
+                            spender,
+                            expires_at: *expires_at,
+                        },
+                        amount: *amount,
+                        memo: memo.as_ref().map(|memo| memo.to_vec()),
+                    },
+                    *block_index,
+                );
+            }
+        }
+        EventType::Erc20TransferFromCompleted {
+            spender,
+            from,
+            to,
+            amount,
+            spender_subaccount,
+            from_subaccount,
+            to_subaccount,
+            token,
+            memo,
+            created_at_time,
+            block_index,
+        } => {
+            let spender = to_account(*spender, spender_subaccount.clone());
+            let from = to_account(*from, from_subaccount.clone());
+            let to = to_account(*to, to_subaccount.clone());
+            state.record_erc20_transfer_from(*token, spender, from, to, *amount);
+            if let (Some(created_at_time), Some(block_index)) = (created_at_time, block_index) {
+                state.recent_erc20_transfers.record(
+                    TransferKey {
+                        created_at_time: *created_at_time,
+                        from,
+                        kind: TransferKind::Transfer {
+                            to,
+                            spender: Some(spender),
+                        },
+                        amount: *amount,
+                        memo: memo.as_ref().map(|memo| memo.to_vec()),
+                    },
+                    *block_index,
+                );
+            }
+        }
+        EventType::UpdatedRpcProviders {
+            providers,
+            consensus_threshold,
+        } => {
+            state.record_updated_rpc_providers(providers.clone(), *consensus_threshold);
+        }
+        EventType::CreatedProposal {
+            id,
+            proposer,
+            action,
+            executable_at,
+        } => {
+            state
+                .proposals
+                .record_created(*id, *proposer, action.clone(), *executable_at);
+        }
+        EventType::ApprovedProposal { id, approver } => {
+            state.proposals.record_approved(*id, *approver);
+        }
+        EventType::ApprovedProposalCancellation { id, approver } => {
+            state.proposals.record_cancellation_approved(*id, *approver);
+        }
+        EventType::ExecutedProposal { id, executed_by: _ } => {
+            state.record_executed_proposal(*id);
+        }
+        EventType::CancelledProposal { id, cancelled_by: _ } => {
+            state.proposals.take(*id);
+        }
+        // The runtime blocklist lives in stable memory, so that it can be checked without the state.
+        // Replaying these events after an upgrade leaves it unchanged.
+        EventType::BlockedAddresses {
+            addresses,
+            reason: _,
+        } => {
+            for address in addresses {
+                block_address(*address);
+            }
+        }
+        EventType::UnblockedAddresses {
+            addresses,
+            reason: _,
+        } => {
+            for address in addresses {
+                unblock_address(*address);
+            }
+        }
+        EventType::FundedGasReserve {
+            from,
+            from_subaccount,
+            amount,
+        } => {
+            state.record_funded_gas_reserve(to_account(*from, from_subaccount.clone()), *amount);
+        }
+        EventType::RejectedWithdrawalRequest {
+            withdrawal_id,
+            destination: _,
+        } => {
+            state.record_cancelled_withdrawal_request(withdrawal_id, None);
+        }
     }
 }
 
 /// Records the given event payload in the event log and updates the state to reflect the change.
 pub fn process_event(state: &mut State, payload: EventType) {
+    let operations = operations(state, &payload);
     apply_state_transition(state, &payload);
     record_event(payload);
+    if !operations.is_empty() {
+        let now = ic_cdk::api::time();
+        for operation in operations {
+            append_block(operation, now);
+        }
+        certify_tip();
+    }
 }
 
 /// Recomputes the minter state from the event log.
@@ -112,11 +340,32 @@ pub fn process_event(state: &mut State, payload: EventType) {
 ///   * The event log is empty.
 ///   * The first event in the log is not an Init event.
 ///   * One of the events in the log invalidates the minter's state invariants.
+///
+/// If the block log is empty (e.g. the first upgrade after it was introduced),
+/// it is rebuilt from the balance movements recorded in the event log.
 pub fn replay_events() -> State {
-    with_event_iter(|iter| replay_events_internal(iter))
+    if total_block_count() > 0 {
+        return with_event_iter(|iter| replay_events_internal(iter));
+    }
+    with_event_iter(|iter| {
+        replay_events_with(iter, |state, event| {
+            for operation in operations(state, &event.payload) {
+                append_block(operation, event.timestamp);
+            }
+        })
+    })
 }
 
 fn replay_events_internal<T: IntoIterator<Item = Event>>(events: T) -> State {
+    replay_events_with(events, |_state, _event| {})
+}
+
+/// Replays the events, calling `before_transition` on the state preceding each event.
+fn replay_events_with<T, F>(events: T, mut before_transition: F) -> State
+where
+    T: IntoIterator<Item = Event>,
+    F: FnMut(&State, &Event),
+{
     let mut events_iter = events.into_iter();
     let mut state = match events_iter
         .next()
@@ -129,6 +378,7 @@ fn replay_events_internal<T: IntoIterator<Item = Event>>(events: T) -> State {
         other => panic!("the first event must be an Init event, got: {other:?}"),
     };
     for event in events_iter {
+        before_transition(&state, &event);
         apply_state_transition(&mut state, &event.payload);
     }
     state
@@ -28,7 +28,7 @@ async fn should_replay_events_for_mainnet() {
 
     let state = replay_events_internal(GetEventsFile::Mainnet.deserialize());
 
-    assert_eq!(state.ethereum_network, EthereumNetwork::Mainnet);
+    assert_eq!(state.evm_network.chain_id, EthereumNetwork::Mainnet.chain_id());
     assert_eq!(
         state.eth_balance.eth_balance(),
         Wei::from(698_140_999_426_625_854_528_u128)
@@ -43,7 +43,7 @@ async fn should_replay_events_for_sepolia() {
 
     let state = replay_events_internal(GetEventsFile::Sepolia.deserialize());
 
-    assert_eq!(state.ethereum_network, EthereumNetwork::Sepolia);
+    assert_eq!(state.evm_network.chain_id, EthereumNetwork::Sepolia.chain_id());
     assert_eq!(
         state.eth_balance.eth_balance(),
         Wei::from(29_749_130_254_874_558_434_938_u128)
@@ -384,8 +384,7 @@ impl GetEventsFile {
                     ckerc20_token_symbol,
                     ckerc20_ledger_id,
                 } => ET::AddedCkErc20Token(CkErc20Token {
-                    erc20_ethereum_network: EthereumNetwork::try_from(chain_id.0.to_u64().unwrap())
-                        .unwrap(),
+                    erc20_chain_id: chain_id.0.to_u64().unwrap(),
                     erc20_contract_address: address.parse().unwrap(),
                     ckerc20_token_symbol: ckerc20_token_symbol.parse().unwrap(),
                     ckerc20_ledger_id,
@@ -0,0 +1,67 @@
+use crate::icrc::{PERMITTED_DRIFT, TRANSACTION_WINDOW};
+use crate::numeric::Erc20Value;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+/// Arguments identifying an ICRC transfer or approval: two with the same ones are duplicates.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct TransferKey {
+    /// Time in nanoseconds since the UNIX epoch at which the client created the transaction.
+    pub created_at_time: u64,
+    /// The account whose funds are transferred or approved.
+    pub from: Account,
+    pub kind: TransferKind,
+    pub amount: Erc20Value,
+    pub memo: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub enum TransferKind {
+    /// A transfer to `to`, made by `spender` when it is not the owner of `from`.
+    Transfer {
+        to: Account,
+        spender: Option<Account>,
+    },
+    /// An approval letting `spender` transfer up to the amount until `expires_at`.
+    Approve {
+        spender: Account,
+        expires_at: Option<u64>,
+    },
+}
+
+/// Transfers and approvals that carried a `created_at_time`, with the index of the block
+/// recording them, kept as long as a duplicate could still be accepted.
+/// Computed based on audit events.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RecentTransfers {
+    /// Ordered by creation time first, so that the oldest transfers are pruned first.
+    transfers: BTreeMap<TransferKey, u64>,
+    /// Latest creation time recorded, from which the canister time can be bounded.
+    latest_created_at_time: u64,
+}
+
+impl RecentTransfers {
+    /// Index of the block recording a previous transaction with the same arguments.
+    /// The creation time must have been validated, older transfers are no longer kept.
+    pub fn find(&self, key: &TransferKey) -> Option<u64> {
+        self.transfers.get(key).copied()
+    }
+
+    /// Records a transaction and forgets the ones that can no longer be duplicated.
+    ///
+    /// The canister time is not part of the audit events, so transfers are pruned relative to the
+    /// latest creation time, which is at most [`PERMITTED_DRIFT`] ahead of the canister time.
+    pub fn record(&mut self, key: TransferKey, block_index: u64) {
+        self.latest_created_at_time = self.latest_created_at_time.max(key.created_at_time);
+        self.transfers.insert(key, block_index);
+        let retention = (TRANSACTION_WINDOW + PERMITTED_DRIFT * 2).as_nanos() as u64;
+        while self.transfers.first_key_value().is_some_and(|(oldest, _)| {
+            oldest.created_at_time.saturating_add(retention) < self.latest_created_at_time
+        }) {
+            self.transfers.pop_first();
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::icrc::{PERMITTED_DRIFT, TRANSACTION_WINDOW};
+use crate::numeric::Erc20Value;
+use crate::state::dedup::{RecentTransfers, TransferKey, TransferKind};
+use candid::Principal;
+use icrc_ledger_types::icrc1::account::Account;
+
+const NOW: u64 = 1_700_000_000_000_000_000;
+
+fn other_account() -> Account {
+    Account {
+        owner: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+        subaccount: Some([1; 32]),
+    }
+}
+
+fn transfer(created_at_time: u64) -> TransferKey {
+    TransferKey {
+        created_at_time,
+        from: Account::from(Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap()),
+        kind: TransferKind::Transfer {
+            to: other_account(),
+            spender: None,
+        },
+        amount: Erc20Value::from(100_u64),
+        memo: Some(vec![1, 2, 3]),
+    }
+}
+
+#[test]
+fn should_find_recorded_transfer() {
+    let mut transfers = RecentTransfers::default();
+
+    transfers.record(transfer(NOW), 7);
+
+    assert_eq!(transfers.find(&transfer(NOW)), Some(7));
+}
+
+#[test]
+fn should_not_find_transfer_with_other_arguments() {
+    let mut transfers = RecentTransfers::default();
+    transfers.record(transfer(NOW), 7);
+
+    assert_eq!(transfers.find(&transfer(NOW + 1)), None);
+    assert_eq!(
+        transfers.find(&TransferKey {
+            amount: Erc20Value::from(101_u64),
+            ..transfer(NOW)
+        }),
+        None
+    );
+    assert_eq!(
+        transfers.find(&TransferKey {
+            memo: None,
+            ..transfer(NOW)
+        }),
+        None
+    );
+    assert_eq!(
+        transfers.find(&TransferKey {
+            kind: TransferKind::Transfer {
+                to: transfer(NOW).from,
+                spender: None,
+            },
+            ..transfer(NOW)
+        }),
+        None
+    );
+}
+
+#[test]
+fn should_tell_apart_transfers_on_behalf_of_owner_and_approvals() {
+    let mut transfers = RecentTransfers::default();
+    transfers.record(transfer(NOW), 7);
+
+    let transfer_from = TransferKey {
+        kind: TransferKind::Transfer {
+            to: other_account(),
+            spender: Some(other_account()),
+        },
+        ..transfer(NOW)
+    };
+    let approval = TransferKey {
+        kind: TransferKind::Approve {
+            spender: other_account(),
+            expires_at: None,
+        },
+        ..transfer(NOW)
+    };
+    assert_eq!(transfers.find(&transfer_from), None);
+    assert_eq!(transfers.find(&approval), None);
+
+    transfers.record(transfer_from.clone(), 8);
+    transfers.record(approval.clone(), 9);
+
+    assert_eq!(transfers.find(&transfer(NOW)), Some(7));
+    assert_eq!(transfers.find(&transfer_from), Some(8));
+    assert_eq!(transfers.find(&approval), Some(9));
+}
+
+#[test]
+fn should_keep_transfers_while_they_can_be_duplicated() {
+    let mut transfers = RecentTransfers::default();
+    transfers.record(transfer(NOW), 7);
+
+    let later = NOW + (TRANSACTION_WINDOW + PERMITTED_DRIFT * 2).as_nanos() as u64;
+    transfers.record(transfer(later), 8);
+
+    assert_eq!(transfers.find(&transfer(NOW)), Some(7));
+    assert_eq!(transfers.find(&transfer(later)), Some(8));
+}
+
+#[test]
+fn should_forget_transfers_that_can_no_longer_be_duplicated() {
+    let mut transfers = RecentTransfers::default();
+    transfers.record(transfer(NOW), 7);
+
+    let later = NOW + (TRANSACTION_WINDOW + PERMITTED_DRIFT * 2).as_nanos() as u64 + 1;
+    transfers.record(transfer(later), 8);
+
+    assert_eq!(transfers.find(&transfer(NOW)), None);
+    assert_eq!(transfers.find(&transfer(later)), Some(8));
+}
+
+#[test]
+fn should_not_forget_transfers_when_recording_older_one() {
+    let mut transfers = RecentTransfers::default();
+    transfers.record(transfer(NOW), 7);
+
+    transfers.record(transfer(NOW - TRANSACTION_WINDOW.as_nanos() as u64), 8);
+
+    assert_eq!(transfers.find(&transfer(NOW)), Some(7));
+    assert_eq!(
+        transfers.find(&transfer(NOW - TRANSACTION_WINDOW.as_nanos() as u64)),
+        Some(8)
+    );
+}
@@ -1,11 +1,19 @@
-use crate::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEvent};
+use crate::endpoints::{QuarantineResolution, Role, VaultFlow};
+use crate::erc20::CkErc20Token;
+use crate::eth_logs::{EventSource, ReceivedErc20Event, ReceivedEthEvent, ReceivedEvent};
+use crate::eth_rpc::Hash;
 use crate::eth_rpc_client::responses::TransactionReceipt;
+use crate::lifecycle::network::RpcEndpoint;
 use crate::lifecycle::{init::InitArg, upgrade::UpgradeArg};
-use crate::numeric::{BlockNumber, Erc20Value};
-use crate::state::transactions::{Erc20WithdrawalRequest, ReimbursementIndex};
+use crate::numeric::{BlockNumber, Erc20Value, Wei};
+use crate::state::proposals::ProposalAction;
+use crate::state::transactions::{
+    Erc20WithdrawalRequest, EthWithdrawalRequest, Reimbursed, ReimbursementIndex, Subaccount,
+};
 use crate::tx::{Eip1559TransactionRequest, SignedEip1559TransactionRequest};
 use candid::{Nat, Principal};
 use ic_ethereum_types::Address;
+use minicbor::bytes::ByteVec;
 use minicbor::{Decode, Encode};
 
 /// The event describing the ckETH minter state transition.
@@ -18,6 +26,12 @@ pub enum EventType {
     /// The minter upgraded with the specified arguments.
     #[n(1)]
     Upgrade(#[n(0)] UpgradeArg),
+    /// The minter discovered a native coin deposit in the helper contract logs.
+    #[n(2)]
+    AcceptedDeposit(#[n(0)] ReceivedEthEvent),
+    /// The minter accepted a new native coin withdrawal request.
+    #[n(3)]
+    AcceptedEthWithdrawalRequest(#[n(0)] EthWithdrawalRequest),
     /// The minter discovered an invalid ckETH deposit in the helper contract logs.
     #[n(4)]
     InvalidDeposit {
@@ -28,6 +42,17 @@ pub enum EventType {
         #[n(1)]
         reason: String,
     },
+    /// The minter credited a native coin deposit to the vault balance of the depositor.
+    #[n(5)]
+    MintedCkEth {
+        /// The unique identifier of the deposit on the Ethereum network.
+        #[n(0)]
+        event_source: EventSource,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        principal: Principal,
+        #[n(2)]
+        amount: Wei,
+    },
     /// The minter processed the helper smart contract logs up to the specified height.
     #[n(6)]
     SyncedToBlock {
@@ -35,6 +60,9 @@ pub enum EventType {
         #[n(0)]
         block_number: BlockNumber,
     },
+    /// The minter credited back the amount of a failed native coin withdrawal.
+    #[n(7)]
+    ReimbursedEthWithdrawal(#[n(0)] Reimbursed),
     /// The minter created a new transaction to handle a withdrawal request.
     #[n(8)]
     CreatedTransaction {
@@ -96,6 +124,28 @@ pub enum EventType {
         #[n(0)]
         block_number: BlockNumber,
     },
+    /// Add a new ERC-20 token to the vault.
+    #[n(29)]
+    AddedCkErc20Token(#[n(0)] CkErc20Token),
+    /// The user cancelled a withdrawal request before a transaction was created for it.
+    #[n(28)]
+    CancelledWithdrawalRequest {
+        #[cbor(n(0), with = "crate::cbor::nat")]
+        withdrawal_id: Nat,
+        /// The withdrawal fee given back to the user, only by cancellations recorded before
+        /// fees stopped being refunded.
+        #[n(1)]
+        refunded_fee: Option<Erc20Value>,
+    },
+    /// The minter credited back the amount of a failed ERC-20 withdrawal.
+    #[n(19)]
+    ReimbursedErc20Withdrawal {
+        /// The unique identifier of the failed withdrawal.
+        #[cbor(n(0), with = "crate::cbor::nat")]
+        withdrawal_id: Nat,
+        #[n(1)]
+        reimbursed: Reimbursed,
+    },
     /// The minter unexpectedly panic while processing a deposit.
     /// The deposit is quarantined to prevent any double minting and
     /// will not be processed without further manual intervention.
@@ -131,12 +181,252 @@ pub enum EventType {
         to: Principal,
         #[n(2)]
         amount: Erc20Value,
+        #[n(3)]
+        from_subaccount: Option<Subaccount>,
+        #[n(4)]
+        to_subaccount: Option<Subaccount>,
+        /// The transferred token, missing for transfers of the default token recorded before it was set.
+        #[n(5)]
+        token: Option<Address>,
+        #[n(6)]
+        memo: Option<ByteVec>,
+        /// Time in nanoseconds since the UNIX epoch at which the client created the transfer.
+        #[n(7)]
+        created_at_time: Option<u64>,
+        /// Index of the block recording the transfer, returned to duplicates of the transfer.
+        /// Missing for transfers recorded before duplicates were rejected.
+        #[n(8)]
+        block_index: Option<u64>,
+    },
+    /// The owner set the allowance of a spender over its balance.
+    #[n(26)]
+    Erc20Approved {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        owner: Principal,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        spender: Principal,
+        /// The new allowance, replacing any previous one.
+        #[n(2)]
+        amount: Erc20Value,
+        /// Time in nanoseconds since the UNIX epoch at which the allowance expires.
+        #[n(3)]
+        expires_at: Option<u64>,
+        #[n(4)]
+        owner_subaccount: Option<Subaccount>,
+        #[n(5)]
+        spender_subaccount: Option<Subaccount>,
+        /// The token of the allowance, missing for approvals of the default token recorded before it was set.
+        #[n(6)]
+        token: Option<Address>,
+        #[n(7)]
+        memo: Option<ByteVec>,
+        /// Time in nanoseconds since the UNIX epoch at which the client created the approval.
+        #[n(8)]
+        created_at_time: Option<u64>,
+        /// Index of the block recording the approval, returned to duplicates of the approval.
+        /// Missing for approvals recorded before duplicates were rejected.
+        #[n(9)]
+        block_index: Option<u64>,
+    },
+    /// The spender transferred funds on behalf of the owner, using its allowance.
+    #[n(27)]
+    Erc20TransferFromCompleted {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        spender: Principal,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        from: Principal,
+        #[cbor(n(2), with = "crate::cbor::principal")]
+        to: Principal,
+        #[n(3)]
+        amount: Erc20Value,
+        #[n(4)]
+        spender_subaccount: Option<Subaccount>,
+        #[n(5)]
+        from_subaccount: Option<Subaccount>,
+        #[n(6)]
+        to_subaccount: Option<Subaccount>,
+        /// The transferred token, missing for transfers of the default token recorded before it was set.
+        #[n(7)]
+        token: Option<Address>,
+        #[n(8)]
+        memo: Option<ByteVec>,
+        /// Time in nanoseconds since the UNIX epoch at which the client created the transfer.
+        #[n(9)]
+        created_at_time: Option<u64>,
+        /// Index of the block recording the transfer, returned to duplicates of the transfer.
+        /// Missing for transfers recorded before duplicates were rejected.
+        #[n(10)]
+        block_index: Option<u64>,
+    },
+    /// The admin replaced the RPC providers queried by the minter.
+    #[n(12)]
+    UpdatedRpcProviders {
+        /// Providers in the order in which they are queried.
+        #[n(0)]
+        providers: Vec<RpcEndpoint>,
+        #[n(1)]
+        consensus_threshold: Option<u8>,
+    },
+    /// The minter found that an accepted deposit is no longer part of the canonical chain
+    /// when verifying it before minting. The deposit is not minted.
+    #[n(13)]
+    ReorgedDeposit {
+        /// The unique identifier of the deposit on the Ethereum network.
+        #[n(0)]
+        event_source: EventSource,
+        /// Hash of the block now including the deposit transaction, if any.
+        #[n(1)]
+        canonical_block_hash: Option<Hash>,
+    },
+    /// The admin resolved a quarantined deposit.
+    #[n(30)]
+    ResolvedQuarantinedDeposit {
+        /// The unique identifier of the deposit on the Ethereum network.
+        #[n(0)]
+        event_source: EventSource,
+        #[n(1)]
+        resolution: QuarantineResolution,
+        /// Why the admin took this decision.
+        #[n(2)]
+        reason: String,
+    },
+    /// The admin resolved a quarantined reimbursement.
+    #[n(31)]
+    ResolvedQuarantinedReimbursement {
+        /// The unique identifier of the reimbursement.
+        #[n(0)]
+        index: ReimbursementIndex,
+        #[n(1)]
+        resolution: QuarantineResolution,
+        /// Why the admin took this decision.
+        #[n(2)]
+        reason: String,
+    },
+    /// The minter scraped a block previously skipped for a specific helper contract,
+    /// recording all of its deposits.
+    #[n(32)]
+    RetriedSkippedBlock {
+        #[n(0)]
+        contract_address: Address,
+        #[n(1)]
+        block_number: BlockNumber,
+    },
+    /// The admin paused or resumed a flow of funds.
+    #[n(33)]
+    UpdatedPause {
+        #[n(0)]
+        flow: VaultFlow,
+        #[n(1)]
+        paused: bool,
+    },
+    /// A role was granted to a principal.
+    #[n(34)]
+    GrantedRole {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        principal: Principal,
+        #[n(1)]
+        role: Role,
+    },
+    /// A role was revoked from a principal.
+    #[n(35)]
+    RevokedRole {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        principal: Principal,
+        #[n(1)]
+        role: Role,
+    },
+    /// The admin handed over to a new admin, who also receives the withdrawal fees from now on.
+    #[n(36)]
+    UpdatedAdmin {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        admin: Principal,
+    },
+    /// An approver proposed a change of sensitive configuration, which counts as its approval.
+    #[n(37)]
+    CreatedProposal {
+        #[n(0)]
+        id: u64,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        proposer: Principal,
+        #[n(2)]
+        action: ProposalAction,
+        /// Time from which the proposal can be executed, in nanoseconds since the UNIX epoch.
+        #[n(3)]
+        executable_at: u64,
+    },
+    #[n(38)]
+    ApprovedProposal {
+        #[n(0)]
+        id: u64,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        approver: Principal,
+    },
+    /// The proposed change was applied.
+    #[n(39)]
+    ExecutedProposal {
+        #[n(0)]
+        id: u64,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        executed_by: Principal,
+    },
+    /// The proposal was withdrawn, by its proposer or once enough approvers approved cancelling it.
+    #[n(40)]
+    CancelledProposal {
+        #[n(0)]
+        id: u64,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        cancelled_by: Principal,
+    },
+    /// Addresses were added to the runtime blocklist, which is kept in stable memory.
+    #[n(41)]
+    BlockedAddresses {
+        #[n(0)]
+        addresses: Vec<Address>,
+        #[n(1)]
+        reason: String,
+    },
+    /// Addresses were removed from the runtime blocklist.
+    #[n(42)]
+    UnblockedAddresses {
+        #[n(0)]
+        addresses: Vec<Address>,
+        #[n(1)]
+        reason: String,
+    },
+    /// Native coins were moved from an account to the gas reserve paying for ERC-20 withdrawals.
+    #[n(43)]
+    FundedGasReserve {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        from: Principal,
+        #[n(1)]
+        from_subaccount: Option<Subaccount>,
+        #[n(2)]
+        amount: Wei,
+    },
+    /// The minter gave back the amount of a pending withdrawal request instead of sending it,
+    /// because its destination was blocked after the request was accepted.
+    /// The withdrawal fee is not refunded.
+    #[n(44)]
+    RejectedWithdrawalRequest {
+        #[cbor(n(0), with = "crate::cbor::nat")]
+        withdrawal_id: Nat,
+        #[n(1)]
+        destination: Address,
+    },
+    /// An approver other than the proposer approved cancelling the proposal.
+    #[n(45)]
+    ApprovedProposalCancellation {
+        #[n(0)]
+        id: u64,
+        #[cbor(n(1), with = "crate::cbor::principal")]
+        approver: Principal,
     },
 }
 
 impl ReceivedEvent {
     pub fn into_deposit(self) -> EventType {
         match self {
+            ReceivedEvent::Eth(event) => EventType::AcceptedDeposit(event),
             ReceivedEvent::Erc20(event) => EventType::AcceptedErc20Deposit(event),
         }
     }
@@ -0,0 +1,50 @@
+use crate::numeric::Erc20Value;
+use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc1::account::Account;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+/// Amounts reserved on the vault balances by flows that need to await
+/// before they can debit them, e.g. a withdrawal waiting for a gas fee estimate.
+/// Holds are transient: they do not survive an upgrade and are not part of the event log.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Erc20Holds {
+    held_by_account: BTreeMap<(Address, Account), Erc20Value>,
+}
+
+impl Erc20Holds {
+    pub fn held(&self, token: &Address, account: &Account) -> Erc20Value {
+        self.held_by_account
+            .get(&(*token, *account))
+            .copied()
+            .unwrap_or(Erc20Value::ZERO)
+    }
+
+    pub fn hold(&mut self, token: Address, account: Account, amount: Erc20Value) {
+        if amount == Erc20Value::ZERO {
+            return;
+        }
+        let held = self.held(&token, &account);
+        let new_value = held.checked_add(amount).unwrap_or_else(|| {
+            panic!("BUG: overflow when holding {} on top of {}", amount, held)
+        });
+        self.held_by_account.insert((token, account), new_value);
+    }
+
+    pub fn release(&mut self, token: &Address, account: &Account, amount: Erc20Value) {
+        if amount == Erc20Value::ZERO {
+            return;
+        }
+        let held = self.held(token, account);
+        let new_value = held.checked_sub(amount).unwrap_or_else(|| {
+            panic!("BUG: underflow when releasing {} from {}", amount, held)
+        });
+        if new_value == Erc20Value::ZERO {
+            self.held_by_account.remove(&(*token, *account));
+        } else {
+            self.held_by_account.insert((*token, *account), new_value);
+        }
+    }
+}
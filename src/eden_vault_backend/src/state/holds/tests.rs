@@ -0,0 +1,68 @@
+use crate::numeric::Erc20Value;
+use crate::state::holds::Erc20Holds;
+use candid::Principal;
+use ic_ethereum_types::Address;
+use icrc_ledger_types::icrc1::account::Account;
+use std::str::FromStr;
+
+fn token() -> Address {
+    Address::from_str("0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238").unwrap()
+}
+
+fn other_token() -> Address {
+    Address::from_str("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48").unwrap()
+}
+
+fn account() -> Account {
+    Account::from(Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap())
+}
+
+#[test]
+fn should_accumulate_holds() {
+    let mut holds = Erc20Holds::default();
+
+    holds.hold(token(), account(), Erc20Value::from(100_u64));
+    holds.hold(token(), account(), Erc20Value::from(50_u64));
+
+    assert_eq!(holds.held(&token(), &account()), Erc20Value::from(150_u64));
+}
+
+#[test]
+fn should_keep_holds_separate_per_token() {
+    let mut holds = Erc20Holds::default();
+
+    holds.hold(token(), account(), Erc20Value::from(100_u64));
+
+    assert_eq!(holds.held(&other_token(), &account()), Erc20Value::ZERO);
+}
+
+#[test]
+fn should_forget_account_when_fully_released() {
+    let mut holds = Erc20Holds::default();
+    holds.hold(token(), account(), Erc20Value::from(100_u64));
+
+    holds.release(&token(), &account(), Erc20Value::from(40_u64));
+    assert_eq!(holds.held(&token(), &account()), Erc20Value::from(60_u64));
+
+    holds.release(&token(), &account(), Erc20Value::from(60_u64));
+    assert_eq!(holds, Erc20Holds::default());
+}
+
+#[test]
+fn should_ignore_zero_amounts() {
+    let mut holds = Erc20Holds::default();
+
+    holds.hold(token(), account(), Erc20Value::ZERO);
+    holds.release(&token(), &account(), Erc20Value::ZERO);
+
+    assert_eq!(holds, Erc20Holds::default());
+}
+
+#[test]
+#[should_panic(expected = "BUG: underflow")]
+fn should_panic_when_releasing_more_than_held() {
+    let mut holds = Erc20Holds::default();
+    holds.hold(token(), account(), Erc20Value::from(10_u64));
+
+    holds.release(&token(), &account(), Erc20Value::from(11_u64));
+}
@@ -0,0 +1,204 @@
+use crate::endpoints::ckerc20::WithdrawalLimitWindow;
+use crate::lifecycle::upgrade::WithdrawalLimitsArg;
+use crate::numeric::Erc20Value;
+use candid::{Nat, Principal};
+use ic_ethereum_types::Address;
+use std::collections::{BTreeMap, VecDeque};
+
+#[cfg(test)]
+mod tests;
+
+/// Length of the rolling windows over which withdrawals are capped.
+pub const ROLLING_WINDOW_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Caps on the withdrawals of a token, in units of the token.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct TokenWithdrawalLimits {
+    pub max_per_withdrawal: Option<Erc20Value>,
+    /// Cap on the withdrawals of a principal over the rolling window.
+    pub max_per_principal: Option<Erc20Value>,
+    /// Cap on the withdrawals of every principal over the rolling window.
+    pub max_global: Option<Erc20Value>,
+}
+
+impl TokenWithdrawalLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_per_withdrawal.is_none()
+            && self.max_per_principal.is_none()
+            && self.max_global.is_none()
+    }
+}
+
+impl TryFrom<WithdrawalLimitsArg> for TokenWithdrawalLimits {
+    type Error = String;
+
+    fn try_from(arg: WithdrawalLimitsArg) -> Result<Self, Self::Error> {
+        let limit = |value: Option<Nat>| -> Result<Option<Erc20Value>, String> {
+            value
+                .map(|value| {
+                    let limit = Erc20Value::try_from(value)?;
+                    if limit == Erc20Value::ZERO {
+                        return Err(
+                            "withdrawal limits must be positive, pause withdrawals instead"
+                                .to_string(),
+                        );
+                    }
+                    Ok(limit)
+                })
+                .transpose()
+        };
+        Ok(Self {
+            max_per_withdrawal: limit(arg.max_per_withdrawal)?,
+            max_per_principal: limit(arg.max_per_principal_per_day)?,
+            max_global: limit(arg.max_global_per_day)?,
+        })
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LimitExceeded {
+    pub limit: Erc20Value,
+    pub window: WithdrawalLimitWindow,
+    pub remaining: Erc20Value,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Outflow {
+    withdrawal_id: Nat,
+    created_at: u64,
+    principal: Principal,
+    token: Address,
+    amount: Erc20Value,
+}
+
+/// Configured caps together with the withdrawals accepted within the rolling window.
+/// Withdrawals are recorded as their requests are accepted, so replaying the event log
+/// yields the same outflow as before an upgrade.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct WithdrawalLimits {
+    limits: BTreeMap<Address, TokenWithdrawalLimits>,
+    /// Accepted withdrawals, in the order in which they were created.
+    outflows: VecDeque<Outflow>,
+}
+
+impl WithdrawalLimits {
+    pub fn get(&self, token: &Address) -> Option<&TokenWithdrawalLimits> {
+        self.limits.get(token)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, &TokenWithdrawalLimits)> {
+        self.limits.iter()
+    }
+
+    /// Replaces the caps of the token, removing them when none is set.
+    pub fn set(&mut self, token: Address, limits: TokenWithdrawalLimits) {
+        if limits.is_empty() {
+            self.limits.remove(&token);
+        } else {
+            self.limits.insert(token, limits);
+        }
+    }
+
+    pub fn record_outflow(
+        &mut self,
+        withdrawal_id: Nat,
+        created_at: u64,
+        principal: Principal,
+        token: Address,
+        amount: Erc20Value,
+    ) {
+        while self
+            .outflows
+            .front()
+            .is_some_and(|outflow| !is_within_window(outflow.created_at, created_at))
+        {
+            self.outflows.pop_front();
+        }
+        self.outflows.push_back(Outflow {
+            withdrawal_id,
+            created_at,
+            principal,
+            token,
+            amount,
+        });
+    }
+
+    /// Gives back the allowance used by a withdrawal that was cancelled before being sent,
+    /// or whose transaction failed and was reimbursed.
+    pub fn release_outflow(&mut self, withdrawal_id: &Nat) {
+        self.outflows
+            .retain(|outflow| &outflow.withdrawal_id != withdrawal_id);
+    }
+
+    fn withdrawn(&self, token: &Address, principal: Option<&Principal>, now: u64) -> Erc20Value {
+        self.outflows
+            .iter()
+            .filter(|outflow| {
+                &outflow.token == token
+                    && principal.map_or(true, |principal| &outflow.principal == principal)
+                    && is_within_window(outflow.created_at, now)
+            })
+            .fold(Erc20Value::ZERO, |total, outflow| {
+                total.checked_add(outflow.amount).unwrap_or(Erc20Value::MAX)
+            })
+    }
+
+    /// The configured caps of the token with what the principal can still withdraw under each of them.
+    pub fn remaining(
+        &self,
+        token: &Address,
+        principal: &Principal,
+        now: u64,
+    ) -> Vec<(WithdrawalLimitWindow, Erc20Value, Erc20Value)> {
+        let Some(limits) = self.limits.get(token) else {
+            return vec![];
+        };
+        let mut remaining = vec![];
+        if let Some(limit) = limits.max_per_withdrawal {
+            remaining.push((WithdrawalLimitWindow::PerWithdrawal, limit, limit));
+        }
+        if let Some(limit) = limits.max_per_principal {
+            let withdrawn = self.withdrawn(token, Some(principal), now);
+            remaining.push((
+                WithdrawalLimitWindow::PrincipalRolling24h,
+                limit,
+                limit.checked_sub(withdrawn).unwrap_or(Erc20Value::ZERO),
+            ));
+        }
+        if let Some(limit) = limits.max_global {
+            let withdrawn = self.withdrawn(token, None, now);
+            remaining.push((
+                WithdrawalLimitWindow::GlobalRolling24h,
+                limit,
+                limit.checked_sub(withdrawn).unwrap_or(Erc20Value::ZERO),
+            ));
+        }
+        remaining
+    }
+
+    /// Checks that withdrawing the amount keeps the principal within every cap of the token.
+    pub fn check(
+        &self,
+        token: &Address,
+        principal: &Principal,
+        amount: Erc20Value,
+        now: u64,
+    ) -> Result<(), LimitExceeded> {
+        match self
+            .remaining(token, principal, now)
+            .into_iter()
+            .find(|(_, _, remaining)| amount > *remaining)
+        {
+            Some((window, limit, remaining)) => Err(LimitExceeded {
+                limit,
+                window,
+                remaining,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_within_window(created_at: u64, now: u64) -> bool {
+    now.saturating_sub(created_at) < ROLLING_WINDOW_NANOS
+}
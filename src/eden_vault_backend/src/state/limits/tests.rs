@@ -0,0 +1,111 @@
+use crate::endpoints::ckerc20::WithdrawalLimitWindow;
+use crate::numeric::Erc20Value;
+use crate::state::limits::{
+    LimitExceeded, TokenWithdrawalLimits, WithdrawalLimits, ROLLING_WINDOW_NANOS,
+};
+use candid::{Nat, Principal};
+use ic_ethereum_types::Address;
+use std::str::FromStr;
+
+fn token() -> Address {
+    Address::from_str("0x1c7d4b196cb0c7b01d743fbc6116a902379c7238").unwrap()
+}
+
+fn principal(id: u8) -> Principal {
+    Principal::from_slice(&[id; 29])
+}
+
+fn limits() -> WithdrawalLimits {
+    let mut limits = WithdrawalLimits::default();
+    limits.set(
+        token(),
+        TokenWithdrawalLimits {
+            max_per_withdrawal: Some(Erc20Value::new(100)),
+            max_per_principal: Some(Erc20Value::new(150)),
+            max_global: Some(Erc20Value::new(250)),
+        },
+    );
+    limits
+}
+
+#[test]
+fn should_not_limit_token_without_caps() {
+    let limits = WithdrawalLimits::default();
+
+    assert_eq!(
+        limits.check(&token(), &principal(1), Erc20Value::MAX, 0),
+        Ok(())
+    );
+    assert!(limits.remaining(&token(), &principal(1), 0).is_empty());
+}
+
+#[test]
+fn should_cap_single_withdrawal() {
+    assert_eq!(
+        limits().check(&token(), &principal(1), Erc20Value::new(101), 0),
+        Err(LimitExceeded {
+            limit: Erc20Value::new(100),
+            window: WithdrawalLimitWindow::PerWithdrawal,
+            remaining: Erc20Value::new(100),
+        })
+    );
+}
+
+#[test]
+fn should_cap_withdrawals_over_rolling_window() {
+    let mut limits = limits();
+    limits.record_outflow(Nat::from(1_u8), 0, principal(1), token(), Erc20Value::new(100));
+
+    assert_eq!(
+        limits.check(&token(), &principal(1), Erc20Value::new(51), 1),
+        Err(LimitExceeded {
+            limit: Erc20Value::new(150),
+            window: WithdrawalLimitWindow::PrincipalRolling24h,
+            remaining: Erc20Value::new(50),
+        })
+    );
+
+    limits.record_outflow(Nat::from(2_u8), 1, principal(2), token(), Erc20Value::new(100));
+    assert_eq!(
+        limits.check(&token(), &principal(3), Erc20Value::new(51), 2),
+        Err(LimitExceeded {
+            limit: Erc20Value::new(250),
+            window: WithdrawalLimitWindow::GlobalRolling24h,
+            remaining: Erc20Value::new(50),
+        })
+    );
+
+    assert_eq!(
+        limits.check(&token(), &principal(1), Erc20Value::new(100), ROLLING_WINDOW_NANOS),
+        Ok(())
+    );
+}
+
+#[test]
+fn should_give_back_allowance_of_cancelled_withdrawal() {
+    let mut limits = limits();
+    limits.record_outflow(Nat::from(1_u8), 0, principal(1), token(), Erc20Value::new(100));
+
+    limits.release_outflow(&Nat::from(1_u8));
+
+    assert_eq!(
+        limits.remaining(&token(), &principal(1), 1),
+        vec![
+            (
+                WithdrawalLimitWindow::PerWithdrawal,
+                Erc20Value::new(100),
+                Erc20Value::new(100)
+            ),
+            (
+                WithdrawalLimitWindow::PrincipalRolling24h,
+                Erc20Value::new(150),
+                Erc20Value::new(150)
+            ),
+            (
+                WithdrawalLimitWindow::GlobalRolling24h,
+                Erc20Value::new(250),
+                Erc20Value::new(250)
+            ),
+        ]
+    );
+}
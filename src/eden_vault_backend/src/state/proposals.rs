@@ -0,0 +1,226 @@
+use crate::endpoints::Role;
+use crate::erc20::CkErc20Token;
+use crate::lifecycle::network::RpcEndpoint;
+use crate::lifecycle::upgrade::UpgradeArg;
+use candid::Principal;
+use minicbor::{Decode, Encode};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(test)]
+mod tests;
+
+/// Change of sensitive configuration that is only applied once approved by enough admins.
+#[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
+pub enum ProposalAction {
+    /// Fees, token and helper contract addresses and limits, as set at upgrade time.
+    #[n(0)]
+    UpdateConfig(#[n(0)] UpgradeArg),
+    #[n(1)]
+    UpdateRpcProviders {
+        #[n(0)]
+        providers: Vec<RpcEndpoint>,
+        #[n(1)]
+        consensus_threshold: Option<u8>,
+    },
+    #[n(2)]
+    SetAdmin(#[cbor(n(0), with = "crate::cbor::principal")] Principal),
+    /// Replaces the admins approving proposals.
+    #[n(3)]
+    UpdatePolicy(#[n(0)] ProposalPolicy),
+    #[n(4)]
+    GrantRole {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        principal: Principal,
+        #[n(1)]
+        role: Role,
+    },
+    #[n(5)]
+    RevokeRole {
+        #[cbor(n(0), with = "crate::cbor::principal")]
+        principal: Principal,
+        #[n(1)]
+        role: Role,
+    },
+    #[n(6)]
+    AddErc20Token(#[n(0)] CkErc20Token),
+}
+
+impl ProposalAction {
+    /// What the action changes, as shown when it is attempted outside of a proposal.
+    pub fn target(&self) -> &'static str {
+        match self {
+            ProposalAction::UpdateConfig(_) => "the configuration",
+            ProposalAction::UpdateRpcProviders { .. } => "the RPC providers",
+            ProposalAction::SetAdmin(_) => "the admin",
+            ProposalAction::UpdatePolicy(_) => "the proposal policy",
+            ProposalAction::GrantRole { .. } | ProposalAction::RevokeRole { .. } => "roles",
+            ProposalAction::AddErc20Token(_) => "the supported ERC-20 tokens",
+        }
+    }
+}
+
+/// Admins approving proposals, and how many of them must approve a proposal before it is executed.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Decode, Encode)]
+pub struct ProposalPolicy {
+    #[cbor(n(0), with = "crate::cbor::principal::vec")]
+    pub approvers: Vec<Principal>,
+    #[n(1)]
+    pub threshold: u8,
+    /// Time between the creation of a proposal and its execution.
+    #[n(2)]
+    pub delay_seconds: u64,
+}
+
+impl ProposalPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.approvers.is_empty() {
+            return Err("at least one approver is required".to_string());
+        }
+        if self.approvers.contains(&Principal::anonymous()) {
+            return Err("the anonymous principal cannot approve proposals".to_string());
+        }
+        let distinct: BTreeSet<_> = self.approvers.iter().collect();
+        if distinct.len() != self.approvers.len() {
+            return Err("duplicate approvers".to_string());
+        }
+        if self.threshold == 0 || usize::from(self.threshold) > self.approvers.len() {
+            return Err(format!(
+                "threshold must be between 1 and the number of approvers {}",
+                self.approvers.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Proposal {
+    pub proposer: Principal,
+    pub action: ProposalAction,
+    /// Time from which the proposal can be executed, in nanoseconds since the UNIX epoch.
+    pub executable_at: u64,
+    pub approvals: BTreeSet<Principal>,
+    /// Approvers who approved cancelling the proposal, which they can only do together.
+    pub cancellation_approvals: BTreeSet<Principal>,
+}
+
+/// Pending proposals, numbered in the order in which they were created.
+///
+/// Until a policy is set, the admin is the only approver and proposals can be executed right away.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Proposals {
+    policy: Option<ProposalPolicy>,
+    pending: BTreeMap<u64, Proposal>,
+    next_id: u64,
+}
+
+impl Proposals {
+    pub fn policy(&self) -> Option<&ProposalPolicy> {
+        self.policy.as_ref()
+    }
+
+    /// Whether sensitive configuration can only be changed through proposals.
+    pub fn is_enabled(&self) -> bool {
+        self.policy.is_some()
+    }
+
+    pub fn next_id(&self) -> u64 {
+        self.next_id
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Proposal> {
+        self.pending.get(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&u64, &Proposal)> {
+        self.pending.iter()
+    }
+
+    /// Number of approvals of the proposal given by principals that are still approvers.
+    pub fn count_approvals(&self, proposal: &Proposal, admin: &Principal) -> usize {
+        self.count_current_approvers(&proposal.approvals, admin)
+    }
+
+    /// Number of approvals of cancelling the proposal given by principals that are still approvers.
+    pub fn count_cancellation_approvals(&self, proposal: &Proposal, admin: &Principal) -> usize {
+        self.count_current_approvers(&proposal.cancellation_approvals, admin)
+    }
+
+    fn count_current_approvers(
+        &self,
+        principals: &BTreeSet<Principal>,
+        admin: &Principal,
+    ) -> usize {
+        principals
+            .iter()
+            .filter(|approver| self.is_approver(approver, admin))
+            .count()
+    }
+
+    pub fn is_approver(&self, principal: &Principal, admin: &Principal) -> bool {
+        match &self.policy {
+            Some(policy) => policy.approvers.contains(principal),
+            None => principal == admin,
+        }
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.policy.as_ref().map(|policy| policy.threshold).unwrap_or(1)
+    }
+
+    pub fn delay_seconds(&self) -> u64 {
+        self.policy
+            .as_ref()
+            .map(|policy| policy.delay_seconds)
+            .unwrap_or_default()
+    }
+
+    /// Records a new proposal, approved by its proposer.
+    pub fn record_created(
+        &mut self,
+        id: u64,
+        proposer: Principal,
+        action: ProposalAction,
+        executable_at: u64,
+    ) {
+        assert_eq!(id, self.next_id, "BUG: unexpected proposal id");
+        self.pending.insert(
+            id,
+            Proposal {
+                proposer,
+                action,
+                executable_at,
+                approvals: BTreeSet::from([proposer]),
+                cancellation_approvals: BTreeSet::new(),
+            },
+        );
+        self.next_id += 1;
+    }
+
+    pub fn record_approved(&mut self, id: u64, approver: Principal) {
+        self.pending
+            .get_mut(&id)
+            .unwrap_or_else(|| panic!("BUG: approved unknown proposal {id}"))
+            .approvals
+            .insert(approver);
+    }
+
+    pub fn record_cancellation_approved(&mut self, id: u64, approver: Principal) {
+        self.pending
+            .get_mut(&id)
+            .unwrap_or_else(|| panic!("BUG: approved cancelling unknown proposal {id}"))
+            .cancellation_approvals
+            .insert(approver);
+    }
+
+    /// Removes a proposal that was executed or cancelled.
+    pub fn take(&mut self, id: u64) -> Proposal {
+        self.pending
+            .remove(&id)
+            .unwrap_or_else(|| panic!("BUG: unknown proposal {id}"))
+    }
+
+    pub fn set_policy(&mut self, policy: ProposalPolicy) {
+        self.policy = Some(policy);
+    }
+}
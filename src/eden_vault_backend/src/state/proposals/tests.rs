@@ -0,0 +1,89 @@
+use crate::state::proposals::{ProposalAction, ProposalPolicy, Proposals};
+use candid::Principal;
+
+fn admin() -> Principal {
+    Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap()
+}
+
+fn approver(id: u8) -> Principal {
+    Principal::from_slice(&[id; 29])
+}
+
+fn policy(approvers: Vec<Principal>, threshold: u8) -> ProposalPolicy {
+    ProposalPolicy {
+        approvers,
+        threshold,
+        delay_seconds: 86_400,
+    }
+}
+
+#[test]
+fn should_reject_invalid_policy() {
+    assert!(policy(vec![], 1).validate().is_err());
+    assert!(policy(vec![approver(1), approver(2)], 0).validate().is_err());
+    assert!(policy(vec![approver(1), approver(2)], 3).validate().is_err());
+    assert!(policy(vec![approver(1), approver(1)], 1).validate().is_err());
+    assert!(policy(vec![approver(1), Principal::anonymous()], 1)
+        .validate()
+        .is_err());
+    assert_eq!(policy(vec![approver(1), approver(2)], 2).validate(), Ok(()));
+}
+
+#[test]
+fn should_only_let_admin_approve_until_policy_is_set() {
+    let mut proposals = Proposals::default();
+    assert!(!proposals.is_enabled());
+    assert!(proposals.is_approver(&admin(), &admin()));
+    assert!(!proposals.is_approver(&approver(1), &admin()));
+    assert_eq!(proposals.threshold(), 1);
+    assert_eq!(proposals.delay_seconds(), 0);
+
+    proposals.set_policy(policy(vec![approver(1), approver(2)], 2));
+
+    assert!(proposals.is_enabled());
+    assert!(!proposals.is_approver(&admin(), &admin()));
+    assert!(proposals.is_approver(&approver(1), &admin()));
+    assert_eq!(proposals.threshold(), 2);
+    assert_eq!(proposals.delay_seconds(), 86_400);
+}
+
+#[test]
+fn should_count_approvals_of_current_approvers() {
+    let mut proposals = Proposals::default();
+    proposals.set_policy(policy(vec![approver(1), approver(2), approver(3)], 2));
+
+    proposals.record_created(0, approver(1), ProposalAction::SetAdmin(approver(4)), 0);
+    proposals.record_approved(0, approver(2));
+    let proposal = proposals.get(0).unwrap().clone();
+    assert_eq!(proposals.count_approvals(&proposal, &admin()), 2);
+    assert_eq!(proposals.next_id(), 1);
+
+    proposals.set_policy(policy(vec![approver(2), approver(3)], 2));
+    assert_eq!(proposals.count_approvals(&proposal, &admin()), 1);
+
+    assert_eq!(proposals.take(0), proposal);
+    assert_eq!(proposals.iter().count(), 0);
+}
+
+#[test]
+fn should_count_cancellation_approvals_of_current_approvers() {
+    let mut proposals = Proposals::default();
+    proposals.set_policy(policy(vec![approver(1), approver(2), approver(3)], 2));
+
+    proposals.record_created(0, approver(1), ProposalAction::SetAdmin(approver(4)), 0);
+    proposals.record_cancellation_approved(0, approver(2));
+    proposals.record_cancellation_approved(0, approver(3));
+    let proposal = proposals.get(0).unwrap().clone();
+    assert_eq!(
+        proposals.count_cancellation_approvals(&proposal, &admin()),
+        2
+    );
+    // Approving the cancellation does not withdraw the proposer's approval.
+    assert_eq!(proposals.count_approvals(&proposal, &admin()), 1);
+
+    proposals.set_policy(policy(vec![approver(1), approver(2)], 2));
+    assert_eq!(
+        proposals.count_cancellation_approvals(&proposal, &admin()),
+        1
+    );
+}
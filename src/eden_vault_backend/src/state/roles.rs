@@ -0,0 +1,108 @@
+use crate::endpoints::Role;
+use candid::Principal;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+#[cfg(test)]
+mod tests;
+
+/// Action over the admin endpoints that requires a role.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Permission {
+    /// Granting or revoking roles. Only the admin can hand over the admin itself.
+    ManageRoles,
+    /// Adding ERC-20 tokens to the vault.
+    ManageTokens,
+    /// Replacing the RPC providers queried by the vault.
+    ManageProviders,
+    /// Retrying skipped blocks and rescraping blocks.
+    ManageScraping,
+    /// Pausing and resuming flows of funds.
+    Pause,
+    /// Resolving quarantined deposits and reimbursements.
+    ResolveQuarantine,
+    /// Blocking and unblocking addresses.
+    ManageBlocklist,
+    /// Reading the quarantined funds, which expose the accounts involved in incidents.
+    ReadAudit,
+}
+
+impl Permission {
+    /// The permission matrix: the owner may do anything, the operator runs the vault,
+    /// compliance handles incidents and the auditor only reads. Every role can read the audit data.
+    pub fn is_granted_to(&self, role: Role) -> bool {
+        match role {
+            Role::Owner => true,
+            Role::Operator => matches!(
+                self,
+                Permission::ManageTokens
+                    | Permission::ManageProviders
+                    | Permission::ManageScraping
+                    | Permission::Pause
+                    | Permission::ReadAudit
+            ),
+            Role::Compliance => matches!(
+                self,
+                Permission::Pause
+                    | Permission::ResolveQuarantine
+                    | Permission::ManageBlocklist
+                    | Permission::ReadAudit
+            ),
+            Role::Auditor => matches!(self, Permission::ReadAudit),
+        }
+    }
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Permission::ManageRoles => write!(f, "manage roles"),
+            Permission::ManageTokens => write!(f, "manage tokens"),
+            Permission::ManageProviders => write!(f, "manage RPC providers"),
+            Permission::ManageScraping => write!(f, "manage scraping"),
+            Permission::Pause => write!(f, "pause the vault"),
+            Permission::ResolveQuarantine => write!(f, "resolve quarantined funds"),
+            Permission::ManageBlocklist => write!(f, "manage the blocklist"),
+            Permission::ReadAudit => write!(f, "read the audit data"),
+        }
+    }
+}
+
+/// Roles granted by the admin, who implicitly holds the [`Role::Owner`] role.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Roles {
+    holders: BTreeMap<Principal, BTreeSet<Role>>,
+}
+
+impl Roles {
+    pub fn has_role(&self, principal: &Principal, role: Role) -> bool {
+        self.holders
+            .get(principal)
+            .is_some_and(|roles| roles.contains(&role))
+    }
+
+    pub fn has_permission(&self, principal: &Principal, permission: Permission) -> bool {
+        self.holders.get(principal).is_some_and(|roles| {
+            roles
+                .iter()
+                .any(|role| permission.is_granted_to(*role))
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Principal, &BTreeSet<Role>)> {
+        self.holders.iter()
+    }
+
+    pub fn grant(&mut self, principal: Principal, role: Role) {
+        self.holders.entry(principal).or_default().insert(role);
+    }
+
+    pub fn revoke(&mut self, principal: &Principal, role: Role) {
+        if let Some(roles) = self.holders.get_mut(principal) {
+            roles.remove(&role);
+            if roles.is_empty() {
+                self.holders.remove(principal);
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::endpoints::Role;
+use crate::state::roles::{Permission, Roles};
+use candid::Principal;
+
+fn principal() -> Principal {
+    Principal::from_text("apia6-jaaaa-aaaar-qabma-cai").unwrap()
+}
+
+#[test]
+fn should_grant_permissions_of_role() {
+    let mut roles = Roles::default();
+
+    roles.grant(principal(), Role::Compliance);
+
+    assert!(roles.has_permission(&principal(), Permission::Pause));
+    assert!(roles.has_permission(&principal(), Permission::ResolveQuarantine));
+    assert!(roles.has_permission(&principal(), Permission::ManageBlocklist));
+    assert!(!roles.has_permission(&principal(), Permission::ManageProviders));
+    assert!(!roles.has_permission(&principal(), Permission::ManageRoles));
+}
+
+#[test]
+fn should_only_grant_read_access_to_auditor() {
+    let mut roles = Roles::default();
+
+    roles.grant(principal(), Role::Auditor);
+
+    assert!(roles.has_role(&principal(), Role::Auditor));
+    assert!(roles.has_permission(&principal(), Permission::ReadAudit));
+    for permission in [
+        Permission::ManageRoles,
+        Permission::ManageTokens,
+        Permission::ManageProviders,
+        Permission::ManageScraping,
+        Permission::Pause,
+        Permission::ResolveQuarantine,
+        Permission::ManageBlocklist,
+    ] {
+        assert!(!roles.has_permission(&principal(), permission));
+    }
+}
+
+#[test]
+fn should_grant_read_access_to_every_role() {
+    for role in [Role::Owner, Role::Operator, Role::Compliance, Role::Auditor] {
+        assert!(Permission::ReadAudit.is_granted_to(role), "{role:?}");
+    }
+}
+
+#[test]
+fn should_forget_principal_when_last_role_revoked() {
+    let mut roles = Roles::default();
+    roles.grant(principal(), Role::Operator);
+    roles.grant(principal(), Role::Auditor);
+
+    roles.revoke(&principal(), Role::Operator);
+    assert!(!roles.has_permission(&principal(), Permission::ManageTokens));
+    assert_eq!(roles.iter().count(), 1);
+
+    roles.revoke(&principal(), Role::Auditor);
+    assert_eq!(roles.iter().count(), 0);
+}
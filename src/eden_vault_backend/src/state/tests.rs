@@ -69,6 +69,7 @@ mod mint_transaction {
     use crate::eth_logs::{EventSourceError, ReceivedEthEvent};
     use crate::lifecycle::EthereumNetwork;
     use crate::numeric::{LedgerMintIndex, LogIndex};
+    use crate::endpoints::QuarantineResolution;
     use crate::state::tests::{initial_state, received_erc20_event, received_eth_event};
     use crate::state::{InvalidEventReason, MintedEvent};
 
@@ -102,7 +103,7 @@ mod mint_transaction {
     #[test]
     fn should_record_erc20_mint_task_from_event() {
         let mut state = initial_state();
-        state.ethereum_network = EthereumNetwork::Sepolia;
+        state.evm_network = EthereumNetwork::Sepolia.into();
         let token = super::erc20::record_add_ckerc20_token::cksepolia_usdc();
         state.record_add_ckerc20_token(token.clone());
 
@@ -224,6 +225,47 @@ mod mint_transaction {
         assert!(state.invalid_events.contains_key(&event.source()));
     }
 
+    #[test]
+    fn should_release_quarantined_deposit() {
+        let mut state = initial_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(&event.clone().into());
+        state.record_quarantined_deposit(event.source());
+
+        state.record_resolved_quarantined_deposit(
+            event.source(),
+            QuarantineResolution::Release,
+            "ledger was not called".to_string(),
+        );
+
+        assert!(state.invalid_events.is_empty());
+        assert!(state.quarantined_deposits.is_empty());
+        assert_eq!(state.events_to_mint(), vec![event.into()]);
+    }
+
+    #[test]
+    fn should_reject_quarantined_deposit() {
+        let mut state = initial_state();
+        let event = received_eth_event();
+        state.record_event_to_mint(&event.clone().into());
+        state.record_quarantined_deposit(event.source());
+
+        state.record_resolved_quarantined_deposit(
+            event.source(),
+            QuarantineResolution::Reject,
+            "refunded on-chain".to_string(),
+        );
+
+        assert!(state.events_to_mint.is_empty());
+        assert!(state.quarantined_deposits.is_empty());
+        assert_eq!(
+            state.invalid_events.get(&event.source()),
+            Some(&InvalidEventReason::RejectedDeposit(
+                "refunded on-chain".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn should_have_readable_eth_debug_representation() {
         let expected = "ReceivedEthEvent { \
@@ -326,7 +368,7 @@ mod upgrade {
         );
 
         let mut state = initial_state();
-        state.ethereum_network = EthereumNetwork::Mainnet;
+        state.evm_network = EthereumNetwork::Mainnet.into();
         assert_matches!(
             state.upgrade(UpgradeArg {
                 minimum_withdrawal_amount: Some(Nat::from(2_000_000_000_000_u64 - 1)),
@@ -336,7 +378,7 @@ mod upgrade {
         );
 
         let mut state = initial_state();
-        state.ethereum_network = EthereumNetwork::Sepolia;
+        state.evm_network = EthereumNetwork::Sepolia.into();
         assert_matches!(
             state.upgrade(UpgradeArg {
                 minimum_withdrawal_amount: Some(Nat::from(10_000_000_000_u64 - 1)),
@@ -396,6 +438,344 @@ mod upgrade {
         );
         assert_eq!(state.ethereum_block_height, BlockTag::Safe);
     }
+
+    #[test]
+    fn should_set_erc20_minimum_withdrawal_amount_of_supported_token() {
+        use crate::lifecycle::upgrade::Erc20MinimumWithdrawalAmountArg;
+        use crate::numeric::Erc20Value;
+
+        let mut state = initial_state();
+        let token = state.default_erc20_token;
+        let minimum = |address: String, amount: u64| UpgradeArg {
+            erc20_minimum_withdrawal_amounts: Some(vec![Erc20MinimumWithdrawalAmountArg {
+                erc20_contract_address: address,
+                minimum_withdrawal_amount: Nat::from(amount),
+            }]),
+            ..Default::default()
+        };
+
+        assert_matches!(
+            state.clone().upgrade(minimum(
+                "0x0000000000000000000000000000000000000001".to_string(),
+                1_000
+            )),
+            Err(InvalidStateError::InvalidErc20MinimumWithdrawalAmount(_))
+        );
+
+        state
+            .upgrade(minimum(token.to_string(), 1_000))
+            .expect("valid minimum withdrawal amount");
+        assert_eq!(
+            state.erc20_minimum_withdrawal_amount(&token),
+            Erc20Value::new(1_000)
+        );
+
+        state
+            .upgrade(minimum(token.to_string(), 0))
+            .expect("removing the minimum withdrawal amount");
+        assert_eq!(state.erc20_minimum_withdrawal_amount(&token), Erc20Value::ZERO);
+    }
+
+    #[test]
+    fn should_top_up_gas_reserve() {
+        let mut state = initial_state();
+        let balance_before = state.eth_balance().eth_balance();
+        let reserve_before = state.eth_balance().available_gas_reserve();
+
+        state
+            .upgrade(UpgradeArg {
+                gas_reserve_top_up: Some(Nat::from(1_000_000_u64)),
+                ..Default::default()
+            })
+            .expect("valid gas reserve top up");
+
+        assert_eq!(
+            state.eth_balance().eth_balance(),
+            balance_before.checked_add(Wei::new(1_000_000)).unwrap()
+        );
+        assert_eq!(
+            state.eth_balance().available_gas_reserve(),
+            reserve_before.checked_add(Wei::new(1_000_000)).unwrap()
+        );
+        assert_matches!(
+            state.upgrade(UpgradeArg {
+                gas_reserve_top_up: Some(Nat(BigUint::from_bytes_be(
+                    &ethnum::u256::MAX.to_be_bytes(),
+                ) + 1_u8)),
+                ..Default::default()
+            }),
+            Err(InvalidStateError::InvalidGasReserveTopUp(_))
+        );
+    }
+
+    #[test]
+    fn should_apply_proposed_config_once_executed() {
+        use crate::state::audit::{apply_state_transition, EventType};
+        use crate::numeric::Erc20Value;
+        use crate::state::proposals::ProposalAction;
+        use candid::Principal;
+
+        let mut state = initial_state();
+        let proposer = state.admin;
+        let action = ProposalAction::UpdateConfig(UpgradeArg {
+            withdraw_fee_value: Some(Nat::from(1_000_u64)),
+            ..Default::default()
+        });
+        assert_eq!(state.validate_proposal_action(&action), Ok(()));
+        assert!(state
+            .validate_proposal_action(&ProposalAction::SetAdmin(Principal::anonymous()))
+            .is_err());
+
+        apply_state_transition(
+            &mut state,
+            &EventType::CreatedProposal {
+                id: 0,
+                proposer,
+                action,
+                executable_at: 0,
+            },
+        );
+        assert_ne!(state.withdraw_fee_value, Erc20Value::new(1_000));
+
+        apply_state_transition(
+            &mut state,
+            &EventType::ExecutedProposal {
+                id: 0,
+                executed_by: proposer,
+            },
+        );
+        assert_eq!(state.withdraw_fee_value, Erc20Value::new(1_000));
+        assert_eq!(state.proposals.iter().count(), 0);
+    }
+
+    #[test]
+    fn should_require_proposal_for_sensitive_upgrade_once_policy_set() {
+        use crate::endpoints::CandidBlockTag;
+        use crate::state::proposals::{ProposalAction, ProposalPolicy};
+
+        let mut state = initial_state();
+        let fee = UpgradeArg {
+            withdraw_fee_value: Some(Nat::from(1_000_u64)),
+            ..Default::default()
+        };
+        let block_height = UpgradeArg {
+            ethereum_block_height: Some(CandidBlockTag::Finalized),
+            ..Default::default()
+        };
+        assert_eq!(state.validate_upgrade_without_proposal(&fee), Ok(()));
+
+        state.proposals.set_policy(ProposalPolicy {
+            approvers: vec![state.admin],
+            threshold: 1,
+            delay_seconds: 0,
+        });
+
+        assert_matches!(
+            state.validate_upgrade_without_proposal(&fee),
+            Err(InvalidStateError::RequiresProposal(_))
+        );
+        assert_eq!(state.validate_upgrade_without_proposal(&block_height), Ok(()));
+        assert_eq!(
+            state.validate_proposal_action(&ProposalAction::UpdateConfig(fee)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn should_refuse_direct_changes_once_policy_set() {
+        use crate::endpoints::Role;
+        use crate::erc20::CkErc20Token;
+        use crate::state::audit::{apply_state_transition, EventType};
+        use crate::state::proposals::{ProposalAction, ProposalPolicy};
+        use candid::Principal;
+
+        let mut state = initial_state();
+        let admin = state.admin;
+        let operator = Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap();
+        let grant = ProposalAction::GrantRole {
+            principal: operator,
+            role: Role::Operator,
+        };
+        let add_token = ProposalAction::AddErc20Token(CkErc20Token {
+            erc20_chain_id: state.evm_network.chain_id,
+            erc20_contract_address: "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
+                .parse()
+                .unwrap(),
+            ckerc20_token_symbol: "ckSepoliaUSDC".parse().unwrap(),
+            ckerc20_ledger_id: Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap(),
+        });
+        assert_eq!(state.validate_direct_change(&grant), Ok(()));
+        assert_eq!(state.validate_direct_change(&add_token), Ok(()));
+
+        state.proposals.set_policy(ProposalPolicy {
+            approvers: vec![admin],
+            threshold: 1,
+            delay_seconds: 0,
+        });
+        state.roles.grant(operator, Role::Compliance);
+
+        for action in [
+            grant.clone(),
+            ProposalAction::RevokeRole {
+                principal: operator,
+                role: Role::Compliance,
+            },
+            add_token.clone(),
+            ProposalAction::SetAdmin(operator),
+            ProposalAction::UpdateRpcProviders {
+                providers: vec![],
+                consensus_threshold: None,
+            },
+        ] {
+            let error = state.validate_direct_change(&action).unwrap_err();
+            assert!(error.contains("through a proposal"), "{error}");
+            assert_eq!(state.validate_proposal_action(&action), Ok(()));
+        }
+
+        for (id, action) in [grant, add_token].into_iter().enumerate() {
+            apply_state_transition(
+                &mut state,
+                &EventType::CreatedProposal {
+                    id: id as u64,
+                    proposer: admin,
+                    action,
+                    executable_at: 0,
+                },
+            );
+            apply_state_transition(
+                &mut state,
+                &EventType::ExecutedProposal {
+                    id: id as u64,
+                    executed_by: admin,
+                },
+            );
+        }
+        assert!(state.roles.has_role(&operator, Role::Operator));
+        assert_eq!(state.ckerc20_tokens.len(), 2);
+    }
+
+    mod rpc_providers {
+        use crate::lifecycle::network::{EvmNetworkArg, RpcEndpoint, RpcHeader};
+        use crate::lifecycle::upgrade::UpgradeArg;
+        use crate::state::audit::{apply_state_transition, EventType};
+        use crate::state::tests::initial_state;
+        use crate::state::{InvalidStateError, State};
+        use assert_matches::assert_matches;
+
+        fn network_arg(state: &State, rpc_endpoints: Vec<String>) -> EvmNetworkArg {
+            EvmNetworkArg {
+                chain_id: state.evm_network.chain_id,
+                name: state.evm_network.name.clone(),
+                rpc_endpoints,
+                consensus_threshold: None,
+                finality: None,
+                native_token_symbol: None,
+                fee_policy: None,
+                deposit_confirmations: Some(12),
+                optimistic_deposits: None,
+                pending_deposits_scraping_interval_seconds: None,
+            }
+        }
+
+        fn providers_set_by_admin() -> Vec<RpcEndpoint> {
+            vec![
+                RpcEndpoint {
+                    url: "https://eth.example.com/v2".to_string(),
+                    headers: vec![RpcHeader {
+                        name: "Authorization".to_string(),
+                        value: "Bearer secret".to_string(),
+                    }],
+                },
+                RpcEndpoint::from("https://rpc.example.org".to_string()),
+            ]
+        }
+
+        fn update_rpc_providers(state: &mut State) {
+            apply_state_transition(
+                state,
+                &EventType::UpdatedRpcProviders {
+                    providers: providers_set_by_admin(),
+                    consensus_threshold: Some(1),
+                },
+            );
+        }
+
+        #[test]
+        fn should_keep_providers_set_by_admin_when_upgrading_network() {
+            let mut state = initial_state();
+            update_rpc_providers(&mut state);
+
+            let upgrade_arg = UpgradeArg {
+                network: Some(network_arg(&state, vec![])),
+                ..Default::default()
+            };
+            assert_eq!(
+                state.validate_upgrade_without_proposal(&upgrade_arg),
+                Ok(())
+            );
+            state.upgrade(upgrade_arg).expect("valid upgrade");
+
+            assert_eq!(state.evm_network.rpc_endpoints, providers_set_by_admin());
+            assert_eq!(state.evm_network.consensus_threshold, 1);
+            assert_eq!(state.evm_network.deposit_confirmations, 12);
+        }
+
+        #[test]
+        fn should_refuse_upgrade_replacing_providers_set_by_admin() {
+            let mut state = initial_state();
+            let upgrade_arg = UpgradeArg {
+                network: Some(network_arg(
+                    &state,
+                    vec!["https://other.example.com".to_string()],
+                )),
+                ..Default::default()
+            };
+            assert_eq!(state.validate_upgrade(&upgrade_arg), Ok(()));
+
+            update_rpc_providers(&mut state);
+
+            assert_matches!(
+                state.validate_upgrade(&upgrade_arg),
+                Err(InvalidStateError::InvalidEvmNetwork(_))
+            );
+            assert_eq!(state.evm_network.rpc_endpoints, providers_set_by_admin());
+        }
+
+        #[test]
+        fn should_allow_upgrade_setting_providers_once_admin_reverted_to_defaults() {
+            let mut state = initial_state();
+            update_rpc_providers(&mut state);
+            apply_state_transition(
+                &mut state,
+                &EventType::UpdatedRpcProviders {
+                    providers: vec![],
+                    consensus_threshold: None,
+                },
+            );
+
+            let upgrade_arg = UpgradeArg {
+                network: Some(network_arg(
+                    &state,
+                    vec!["https://other.example.com".to_string()],
+                )),
+                ..Default::default()
+            };
+            assert_eq!(state.validate_upgrade(&upgrade_arg), Ok(()));
+        }
+
+        #[test]
+        fn should_not_show_header_values_in_debug() {
+            let event = EventType::UpdatedRpcProviders {
+                providers: providers_set_by_admin(),
+                consensus_threshold: Some(1),
+            };
+
+            let debug = format!("{event:?}");
+
+            assert!(debug.contains("Authorization"), "{debug}");
+            assert!(!debug.contains("secret"), "{debug}");
+        }
+    }
 }
 
 mod erc20 {
@@ -408,14 +788,14 @@ mod erc20 {
         #[test]
         fn should_panic_when_ethereum_network_mismatch() {
             let mut state = initial_state();
-            state.ethereum_network = EthereumNetwork::Sepolia;
+            state.evm_network = EthereumNetwork::Sepolia.into();
 
             expect_panic_with_message(
                 || state.record_add_ckerc20_token(ckusdc()),
                 "ERROR: Expected Ethereum Testnet Sepolia",
             );
 
-            state.ethereum_network = EthereumNetwork::Mainnet;
+            state.evm_network = EthereumNetwork::Mainnet.into();
             expect_panic_with_message(
                 || state.record_add_ckerc20_token(cksepolia_usdc()),
                 "ERROR: Expected Ethereum Mainnet",
@@ -425,7 +805,7 @@ mod erc20 {
         #[test]
         fn should_record_ckerc20_token() {
             let mut state = initial_state();
-            state.ethereum_network = EthereumNetwork::Mainnet;
+            state.evm_network = EthereumNetwork::Mainnet.into();
             let ckerc20 = ckusdc();
 
             state.record_add_ckerc20_token(ckerc20.clone());
@@ -433,7 +813,7 @@ mod erc20 {
             assert_eq!(
                 state.supported_ck_erc20_tokens().collect::<Vec<_>>(),
                 vec![CkErc20Token {
-                    erc20_ethereum_network: EthereumNetwork::Mainnet,
+                    erc20_chain_id: EthereumNetwork::Mainnet.chain_id(),
                     erc20_contract_address: ckerc20.erc20_contract_address,
                     ckerc20_token_symbol: ckerc20.ckerc20_token_symbol,
                     ckerc20_ledger_id: ckerc20.ckerc20_ledger_id,
@@ -444,7 +824,7 @@ mod erc20 {
         #[test]
         fn should_panic_when_duplicate_ledger_id() {
             let mut state = initial_state();
-            state.ethereum_network = EthereumNetwork::Mainnet;
+            state.evm_network = EthereumNetwork::Mainnet.into();
             let ckusdc = ckusdc();
             state.record_add_ckerc20_token(ckusdc.clone());
 
@@ -461,7 +841,7 @@ mod erc20 {
         #[test]
         fn should_panic_when_duplicate_erc20_smart_contract_address() {
             let mut state = initial_state();
-            state.ethereum_network = EthereumNetwork::Mainnet;
+            state.evm_network = EthereumNetwork::Mainnet.into();
             let ckusdc = ckusdc();
             state.record_add_ckerc20_token(ckusdc.clone());
 
@@ -478,7 +858,7 @@ mod erc20 {
         #[test]
         fn should_panic_when_duplicate_ckerc20_token_symbol() {
             let mut state = initial_state();
-            state.ethereum_network = EthereumNetwork::Mainnet;
+            state.evm_network = EthereumNetwork::Mainnet.into();
             let ckusdc = ckusdc();
             state.record_add_ckerc20_token(ckusdc.clone());
 
@@ -494,7 +874,7 @@ mod erc20 {
 
         fn ckusdc() -> CkErc20Token {
             CkErc20Token {
-                erc20_ethereum_network: EthereumNetwork::Mainnet,
+                erc20_chain_id: EthereumNetwork::Mainnet.chain_id(),
                 erc20_contract_address: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
                     .parse()
                     .unwrap(),
@@ -505,7 +885,7 @@ mod erc20 {
 
         pub fn cksepolia_usdc() -> CkErc20Token {
             CkErc20Token {
-                erc20_ethereum_network: EthereumNetwork::Sepolia,
+                erc20_chain_id: EthereumNetwork::Sepolia.chain_id(),
                 erc20_contract_address: "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238"
                     .parse()
                     .unwrap(),
@@ -516,7 +896,7 @@ mod erc20 {
 
         fn ckusdt() -> CkErc20Token {
             CkErc20Token {
-                erc20_ethereum_network: EthereumNetwork::Mainnet,
+                erc20_chain_id: EthereumNetwork::Mainnet.chain_id(),
                 erc20_contract_address: "0xdac17f958d2ee523a2206206994597c13d831ec7"
                     .parse()
                     .unwrap(),
@@ -1000,6 +1380,7 @@ fn state_equivalence() {
             chain_code: vec![2; 32],
         }),
         cketh_minimum_withdrawal_amount: Wei::new(1_000_000_000_000_000),
+        erc20_minimum_withdrawal_amounts: Default::default(),
         ethereum_block_height: BlockTag::Finalized,
         first_scraped_block_number: BlockNumber::new(1_000_001),
         last_scraped_block_number: BlockNumber::new(1_000_000),
@@ -1033,14 +1414,26 @@ fn state_equivalence() {
         invalid_events: btreemap! {
             source("0x05c6ec45699c9a6a4b1a4ea2058b0cee852ea2f19b18fb8313c04bf8156efde4", 11) => InvalidEventReason::InvalidDeposit("failed to decode principal from bytes 0x00333c125dc9f41abaf2b8b85d49fdc7ff75b2a4000000000000000000000000".to_string()),
         },
+        reorged_events: Default::default(),
+        pending_deposits: Default::default(),
+        quarantined_deposits: Default::default(),
         eth_transactions: eth_transactions.clone(),
         pending_withdrawal_principals: Default::default(),
         active_tasks: Default::default(),
+        last_pending_deposits_head: None,
         http_request_counter: 100,
         eth_balance: Default::default(),
         erc20_balances: Default::default(),
+        recent_erc20_transfers: Default::default(),
         skipped_blocks: Default::default(),
+        paused_flows: Default::default(),
+        rpc_providers_set_by_admin: false,
+        roles: Default::default(),
+        proposals: Default::default(),
+        withdrawal_limits: Default::default(),
         last_transaction_price_estimate: None,
+        provider_health: Default::default(),
+        rescrape: None,
         ledger_suite_orchestrator_id: Some("2s5qh-7aaaa-aaaar-qadya-cai".parse().unwrap()),
         evm_rpc_id: Some("7hfb6-caaaa-aaaar-qadga-cai".parse().unwrap()),
         ckerc20_tokens,
@@ -1461,6 +1854,75 @@ mod eth_balance {
         );
     }
 
+    #[test]
+    fn should_reimburse_failed_withdrawal_once_across_replay() {
+        use crate::state::transactions::{ReimbursementIndex, Reimbursed};
+        use crate::test_fixtures::expect_panic_with_message;
+        use candid::Nat;
+        use icrc_ledger_types::icrc1::account::Account;
+
+        let deposit = received_eth_event();
+        let account = Account::from(deposit.principal);
+        let withdrawal_request = EthWithdrawalRequest {
+            withdrawal_amount: deposit.value,
+            destination: "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34"
+                .parse()
+                .unwrap(),
+            ledger_burn_index: Nat::from(1_u8),
+            from: deposit.principal,
+            from_subaccount: None,
+            created_at: Some(1699527697000000000),
+        };
+        let replay = || {
+            let mut state = initial_state();
+            apply_state_transition(&mut state, &EventType::AcceptedDeposit(deposit.clone()));
+            WithdrawalFlow {
+                tx_status: TransactionStatus::Failure,
+                ..WithdrawalFlow::for_request(withdrawal_request.clone())
+            }
+            .apply(&mut state);
+            state
+        };
+
+        let mut state = replay();
+        assert_eq!(state.eth_balance.balance_of(&account), Wei::ZERO);
+        let (index, request) = state
+            .eth_transactions
+            .reimbursement_requests_iter()
+            .map(|(index, request)| (index.clone(), request.clone()))
+            .next()
+            .expect("failed withdrawal should be reimbursed");
+        assert_eq!(
+            index,
+            ReimbursementIndex::CkEth {
+                ledger_burn_index: Nat::from(1_u8)
+            }
+        );
+        let reimbursed = EventType::ReimbursedEthWithdrawal(Reimbursed {
+            reimbursed_in_block: LedgerMintIndex::new(7),
+            burn_in_block: Nat::from(1_u8),
+            reimbursed_amount: request.reimbursed_amount,
+            transaction_hash: request.transaction_hash,
+        });
+        apply_state_transition(&mut state, &reimbursed);
+        let reimbursed_balance: Wei = request.reimbursed_amount.change_units();
+        assert_eq!(state.eth_balance.balance_of(&account), reimbursed_balance);
+        assert!(!state.eth_transactions.has_reimbursement_requests());
+
+        let mut replayed_state = replay();
+        apply_state_transition(&mut replayed_state, &reimbursed);
+        assert_eq!(replayed_state.is_equivalent_to(&state), Ok(()));
+        assert_eq!(
+            replayed_state.eth_balance.balance_of(&account),
+            reimbursed_balance
+        );
+
+        expect_panic_with_message(
+            || apply_state_transition(&mut replayed_state, &reimbursed),
+            "BUG: missing reimbursement request",
+        );
+    }
+
     #[test]
     fn should_update_after_successful_and_failed_erc20_withdrawal() {
         let mut state_before_withdrawal = initial_erc20_state();
@@ -1552,52 +2014,207 @@ mod eth_balance {
         );
     }
 
-    #[derive(Clone)]
-    struct WithdrawalFlow {
-        withdrawal_request: WithdrawalRequest,
-        nonce: TransactionNonce,
-        tx_fee: GasFeeEstimate,
-        gas_limit: GasAmount,
-        effective_gas_price: WeiPerGas,
-        effective_gas_used: GasAmount,
-        tx_status: TransactionStatus,
-    }
+    #[test]
+    fn should_release_withdrawal_limit_once_failed_withdrawal_reimbursed() {
+        use crate::endpoints::ckerc20::WithdrawalLimitWindow;
+        use crate::state::limits::TokenWithdrawalLimits;
+        use crate::state::transactions::{Reimbursed, ReimbursementIndex};
 
-    impl WithdrawalFlow {
-        fn for_request<T: Into<WithdrawalRequest>>(withdrawal_request: T) -> Self {
-            Self {
-                withdrawal_request: withdrawal_request.into(),
-                nonce: TransactionNonce::ZERO,
-                tx_fee: GasFeeEstimate {
-                    base_fee_per_gas: WeiPerGas::ONE,
-                    max_priority_fee_per_gas: WeiPerGas::ONE,
-                },
-                gas_limit: GasAmount::from(21_000_u32),
-                effective_gas_price: WeiPerGas::ONE,
-                effective_gas_used: GasAmount::from(21_000_u32),
-                tx_status: TransactionStatus::Success,
-            }
+        let mut state = initial_erc20_state();
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedErc20Deposit(received_erc20_event()),
+        );
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedDeposit(received_eth_event()),
+        );
+        let withdrawal_request = erc20_withdrawal_request();
+        let token = withdrawal_request.erc20_contract_address;
+        let limit = Erc20Value::from(10_000_000_u64);
+        state.withdrawal_limits.set(
+            token,
+            TokenWithdrawalLimits {
+                max_per_principal: Some(limit),
+                ..Default::default()
+            },
+        );
+        let remaining = |state: &State| {
+            state.withdrawal_limits.remaining(
+                &token,
+                &withdrawal_request.from,
+                withdrawal_request.created_at,
+            )
+        };
+
+        WithdrawalFlow {
+            tx_status: TransactionStatus::Failure,
+            ..WithdrawalFlow::for_request(withdrawal_request.clone())
         }
+        .apply(&mut state);
+        assert_eq!(
+            remaining(&state),
+            vec![(
+                WithdrawalLimitWindow::PrincipalRolling24h,
+                limit,
+                limit
+                    .checked_sub(withdrawal_request.withdrawal_amount)
+                    .unwrap()
+            )]
+        );
 
-        fn apply(self, state: &mut State) -> TransactionReceipt {
-            let accepted_withdrawal_request_event = match &self.withdrawal_request {
-                WithdrawalRequest::CkEth(eth_request) => {
-                    EventType::AcceptedEthWithdrawalRequest(eth_request.clone())
-                }
-                WithdrawalRequest::CkErc20(erc20_request) => {
-                    EventType::AcceptedErc20WithdrawalRequest(erc20_request.clone())
+        let (withdrawal_id, request) = state
+            .eth_transactions
+            .reimbursement_requests_iter()
+            .find_map(|(index, request)| match index {
+                ReimbursementIndex::CkErc20 { withdrawal_id } => {
+                    Some((withdrawal_id.clone(), request.clone()))
                 }
-            };
-            apply_state_transition(state, &accepted_withdrawal_request_event);
-
-            let transaction = create_transaction(
-                &self.withdrawal_request,
-                self.nonce,
-                self.tx_fee,
-                self.gas_limit,
-                EthereumNetwork::Sepolia,
-            )
-            .expect("BUG: failed to create transaction");
+                ReimbursementIndex::CkEth { .. } => None,
+            })
+            .expect("failed withdrawal should be reimbursed");
+        apply_state_transition(
+            &mut state,
+            &EventType::ReimbursedErc20Withdrawal {
+                withdrawal_id,
+                reimbursed: Reimbursed {
+                    reimbursed_in_block: LedgerMintIndex::new(7),
+                    burn_in_block: request.ledger_burn_index,
+                    reimbursed_amount: request.reimbursed_amount,
+                    transaction_hash: request.transaction_hash,
+                },
+            },
+        );
+        assert_eq!(
+            remaining(&state),
+            vec![(WithdrawalLimitWindow::PrincipalRolling24h, limit, limit)]
+        );
+    }
+
+    #[test]
+    fn should_pay_erc20_transaction_fee_out_of_gas_reserve() {
+        use icrc_ledger_types::icrc1::account::Account;
+
+        let mut state = initial_erc20_state();
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedErc20Deposit(received_erc20_event()),
+        );
+        let deposit = received_eth_event();
+        apply_state_transition(&mut state, &EventType::AcceptedDeposit(deposit.clone()));
+        let withdrawal_request = erc20_withdrawal_request();
+        let max_transaction_fee = withdrawal_request.max_transaction_fee;
+        apply_state_transition(
+            &mut state,
+            &EventType::FundedGasReserve {
+                from: deposit.principal,
+                from_subaccount: None,
+                amount: max_transaction_fee,
+            },
+        );
+        let depositor_balance = deposit.value.checked_sub(max_transaction_fee).unwrap();
+        assert_eq!(state.eth_balance.gas_reserve(), max_transaction_fee);
+        assert_eq!(
+            state.eth_balance.balance_of(&Account::from(deposit.principal)),
+            depositor_balance
+        );
+        // Native withdrawals cannot use the reserve.
+        assert_eq!(state.eth_balance.withdrawable_balance(), depositor_balance);
+
+        let mut state_with_accepted_withdrawal = state.clone();
+        apply_state_transition(
+            &mut state_with_accepted_withdrawal,
+            &EventType::AcceptedErc20WithdrawalRequest(withdrawal_request.clone()),
+        );
+        assert_eq!(
+            state_with_accepted_withdrawal
+                .eth_balance
+                .available_gas_reserve(),
+            Wei::ZERO
+        );
+
+        let eth_balance_before_withdrawal = state.eth_balance().eth_balance();
+        let effective_gas_price = WeiPerGas::from(0x596cfd9a_u64);
+        let effective_gas_used = GasAmount::from(0xb003_u32);
+        WithdrawalFlow {
+            gas_limit: GasAmount::from(65_000_u64),
+            effective_gas_price,
+            effective_gas_used,
+            ..WithdrawalFlow::for_request(withdrawal_request)
+        }
+        .apply(&mut state);
+        let effective_transaction_fee = effective_gas_price
+            .transaction_cost(effective_gas_used)
+            .unwrap();
+
+        let remaining_reserve = max_transaction_fee
+            .checked_sub(effective_transaction_fee)
+            .unwrap();
+        assert_eq!(state.eth_balance.gas_reserve(), remaining_reserve);
+        assert_eq!(state.eth_balance.available_gas_reserve(), remaining_reserve);
+        assert_eq!(
+            state.eth_balance.total_erc20_tx_fees(),
+            effective_transaction_fee
+        );
+        assert_eq!(
+            state.eth_balance.eth_balance(),
+            eth_balance_before_withdrawal
+                .checked_sub(effective_transaction_fee)
+                .unwrap()
+        );
+        assert_eq!(
+            state.eth_balance.balance_of(&Account::from(deposit.principal)),
+            depositor_balance
+        );
+        assert_eq!(state.eth_balance.withdrawable_balance(), depositor_balance);
+    }
+
+    #[derive(Clone)]
+    struct WithdrawalFlow {
+        withdrawal_request: WithdrawalRequest,
+        nonce: TransactionNonce,
+        tx_fee: GasFeeEstimate,
+        gas_limit: GasAmount,
+        effective_gas_price: WeiPerGas,
+        effective_gas_used: GasAmount,
+        tx_status: TransactionStatus,
+    }
+
+    impl WithdrawalFlow {
+        fn for_request<T: Into<WithdrawalRequest>>(withdrawal_request: T) -> Self {
+            Self {
+                withdrawal_request: withdrawal_request.into(),
+                nonce: TransactionNonce::ZERO,
+                tx_fee: GasFeeEstimate {
+                    base_fee_per_gas: WeiPerGas::ONE,
+                    max_priority_fee_per_gas: WeiPerGas::ONE,
+                },
+                gas_limit: GasAmount::from(21_000_u32),
+                effective_gas_price: WeiPerGas::ONE,
+                effective_gas_used: GasAmount::from(21_000_u32),
+                tx_status: TransactionStatus::Success,
+            }
+        }
+
+        fn apply(self, state: &mut State) -> TransactionReceipt {
+            let accepted_withdrawal_request_event = match &self.withdrawal_request {
+                WithdrawalRequest::CkEth(eth_request) => {
+                    EventType::AcceptedEthWithdrawalRequest(eth_request.clone())
+                }
+                WithdrawalRequest::CkErc20(erc20_request) => {
+                    EventType::AcceptedErc20WithdrawalRequest(erc20_request.clone())
+                }
+            };
+            apply_state_transition(state, &accepted_withdrawal_request_event);
+
+            let transaction = create_transaction(
+                &self.withdrawal_request,
+                self.nonce,
+                self.tx_fee,
+                self.gas_limit,
+                EthereumNetwork::Sepolia.chain_id(),
+            )
+            .expect("BUG: failed to create transaction");
             apply_state_transition(
                 state,
                 &EventType::CreatedTransaction {
@@ -1653,7 +2270,7 @@ mod eth_balance {
         apply_state_transition(
             state,
             &EventType::AddedCkErc20Token(CkErc20Token {
-                erc20_ethereum_network: Default::default(),
+                erc20_chain_id: EthereumNetwork::default().chain_id(),
                 erc20_contract_address: "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
                     .parse()
                     .unwrap(),
@@ -1770,7 +2387,794 @@ mod erc20_balance {
 
         assert_eq!(balance_after, balance_before);
     }
+
+    #[test]
+    fn should_replay_transfers_and_approvals_in_their_token() {
+        use crate::lifecycle::upgrade::UpgradeArg;
+        use crate::numeric::Erc20Value;
+        use candid::Principal;
+        use icrc_ledger_types::icrc1::account::Account;
+
+        let mut state = initial_erc20_state();
+        let default_token = state.default_erc20_token;
+        let other_token: Address = "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
+            .parse()
+            .unwrap();
+        assert_ne!(default_token, other_token);
+        let owner = Principal::from_text("hkroy-sm7vs-yyjs7-ekppe-qqnwx-hm4zf-n7ybs-titsi-k6e3k-ucuiu-uqe").unwrap();
+        let spender = Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap();
+        for token in [default_token, other_token] {
+            state.erc20_balances.account_erc20_add(
+                token,
+                Account::from(owner),
+                Erc20Value::from(1_000_u64),
+            );
+        }
+        let initial = state.clone();
+        let events = vec![
+            EventType::Erc20Approved {
+                owner,
+                spender,
+                amount: Erc20Value::from(500_u64),
+                expires_at: None,
+                owner_subaccount: None,
+                spender_subaccount: None,
+                token: Some(default_token),
+                memo: None,
+                created_at_time: None,
+                block_index: None,
+            },
+            EventType::Erc20TransferFromCompleted {
+                spender,
+                from: owner,
+                to: spender,
+                amount: Erc20Value::from(200_u64),
+                spender_subaccount: None,
+                from_subaccount: None,
+                to_subaccount: None,
+                token: Some(default_token),
+                memo: None,
+                created_at_time: None,
+                block_index: None,
+            },
+            // Recorded before transfers carried their token.
+            EventType::Erc20TransferCompleted {
+                from: owner,
+                to: spender,
+                amount: Erc20Value::from(100_u64),
+                from_subaccount: None,
+                to_subaccount: None,
+                token: None,
+                memo: None,
+                created_at_time: None,
+                block_index: None,
+            },
+        ];
+        for event in &events {
+            apply_state_transition(&mut state, event);
+        }
+
+        let balance = |state: &State, token: &Address, owner: Principal| {
+            state.erc20_balances.balance_of(token, &Account::from(owner))
+        };
+        assert_eq!(balance(&state, &default_token, owner), Erc20Value::from(700_u64));
+        assert_eq!(balance(&state, &default_token, spender), Erc20Value::from(300_u64));
+        assert_eq!(balance(&state, &other_token, owner), Erc20Value::from(1_000_u64));
+        assert_eq!(balance(&state, &other_token, spender), Erc20Value::ZERO);
+
+        // The default token cannot be changed, so replaying yields the same balances and allowances.
+        assert!(state
+            .validate_upgrade(&UpgradeArg {
+                ckerc20_token_address: Some(other_token.to_string()),
+                ckerc20_token_symbol: Some("ckOther".to_string()),
+                ..Default::default()
+            })
+            .is_err());
+        assert!(state
+            .validate_upgrade(&UpgradeArg {
+                ckerc20_token_address: Some(default_token.to_string()),
+                ckerc20_token_symbol: Some("ckSepoliaUSDC".to_string()),
+                ..Default::default()
+            })
+            .is_err());
+        let mut replayed = initial;
+        for event in &events {
+            apply_state_transition(&mut replayed, event);
+        }
+        assert_eq!(replayed.is_equivalent_to(&state), Ok(()));
+
+        expect_panic_with_message(
+            || {
+                apply_state_transition(
+                    &mut state,
+                    &EventType::Erc20TransferCompleted {
+                        from: owner,
+                        to: spender,
+                        amount: Erc20Value::from(100_u64),
+                        from_subaccount: None,
+                        to_subaccount: None,
+                        token: Some(other_token),
+                        memo: None,
+                        created_at_time: None,
+                        block_index: None,
+                    },
+                )
+            },
+            "only supported for the default token",
+        );
+    }
+
+    #[test]
+    fn should_rebuild_recent_transfers_when_replaying_transfers() {
+        use crate::numeric::Erc20Value;
+        use crate::state::dedup::{TransferKey, TransferKind};
+        use candid::Principal;
+        use icrc_ledger_types::icrc1::account::Account;
+        use minicbor::bytes::ByteVec;
+
+        let mut state = initial_erc20_state();
+        let token = state.default_erc20_token;
+        let from = Account::from(Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap());
+        let to = Account::from(Principal::from_slice(&[7; 29]));
+        state
+            .erc20_balances
+            .account_erc20_add(token, from, Erc20Value::from(1_000_u64));
+        let transfer = |created_at_time: Option<u64>, block_index: Option<u64>| {
+            EventType::Erc20TransferCompleted {
+                from: from.owner,
+                to: to.owner,
+                amount: Erc20Value::from(100_u64),
+                from_subaccount: None,
+                to_subaccount: None,
+                token: Some(token),
+                memo: Some(ByteVec::from(vec![1, 2, 3])),
+                created_at_time,
+                block_index,
+            }
+        };
+        let key = |created_at_time: u64| TransferKey {
+            created_at_time,
+            from,
+            kind: TransferKind::Transfer { to, spender: None },
+            amount: Erc20Value::from(100_u64),
+            memo: Some(vec![1, 2, 3]),
+        };
+
+        // Transfers without a creation time cannot be duplicated.
+        apply_state_transition(&mut state, &transfer(None, Some(3)));
+        // Recorded before duplicates were rejected.
+        apply_state_transition(&mut state, &transfer(Some(1_000), None));
+        apply_state_transition(&mut state, &transfer(Some(2_000), Some(5)));
+
+        assert_eq!(state.recent_erc20_transfers.find(&key(1_000)), None);
+        assert_eq!(state.recent_erc20_transfers.find(&key(2_000)), Some(5));
+        assert_eq!(
+            state.erc20_balances.balance_of(&token, &to),
+            Erc20Value::from(300_u64)
+        );
+    }
+
+    #[test]
+    fn should_rebuild_recent_approvals_and_transfers_from_when_replaying() {
+        use crate::numeric::Erc20Value;
+        use crate::state::dedup::{TransferKey, TransferKind};
+        use candid::Principal;
+        use icrc_ledger_types::icrc1::account::Account;
+
+        let mut state = initial_erc20_state();
+        let token = state.default_erc20_token;
+        let owner = Account::from(Principal::from_text("3sgad-taaaa-aaaar-qaedq-cai").unwrap());
+        let spender = Account::from(Principal::from_slice(&[7; 29]));
+        state
+            .erc20_balances
+            .account_erc20_add(token, owner, Erc20Value::from(1_000_u64));
+
+        for event in [
+            EventType::Erc20Approved {
+                owner: owner.owner,
+                spender: spender.owner,
+                amount: Erc20Value::from(500_u64),
+                expires_at: None,
+                owner_subaccount: None,
+                spender_subaccount: None,
+                token: Some(token),
+                memo: None,
+                created_at_time: Some(1_000),
+                block_index: Some(3),
+            },
+            EventType::Erc20TransferFromCompleted {
+                spender: spender.owner,
+                from: owner.owner,
+                to: spender.owner,
+                amount: Erc20Value::from(200_u64),
+                spender_subaccount: None,
+                from_subaccount: None,
+                to_subaccount: None,
+                token: Some(token),
+                memo: None,
+                created_at_time: Some(2_000),
+                block_index: Some(4),
+            },
+        ] {
+            apply_state_transition(&mut state, &event);
+        }
+
+        let approval = TransferKey {
+            created_at_time: 1_000,
+            from: owner,
+            kind: TransferKind::Approve {
+                spender,
+                expires_at: None,
+            },
+            amount: Erc20Value::from(500_u64),
+            memo: None,
+        };
+        let transfer_from = TransferKey {
+            created_at_time: 2_000,
+            from: owner,
+            kind: TransferKind::Transfer {
+                to: spender,
+                spender: Some(spender),
+            },
+            amount: Erc20Value::from(200_u64),
+            memo: None,
+        };
+        assert_eq!(state.recent_erc20_transfers.find(&approval), Some(3));
+        assert_eq!(state.recent_erc20_transfers.find(&transfer_from), Some(4));
+        assert_eq!(
+            state.recent_erc20_transfers.find(&TransferKey {
+                kind: TransferKind::Transfer {
+                    to: spender,
+                    spender: None,
+                },
+                ..transfer_from
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn should_keep_withdrawal_fee_with_recipient_when_admin_changes() {
+        use crate::numeric::Erc20Value;
+        use candid::Principal;
+        use icrc_ledger_types::icrc1::account::Account;
+
+        let mut state = initial_erc20_state();
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedErc20Deposit(received_erc20_event()),
+        );
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedDeposit(received_eth_event()),
+        );
+        let former_admin = Account::from(state.admin);
+        let new_admin = Account::from(Principal::from_slice(&[7; 29]));
+        let fee = Erc20Value::from(1_000_u64);
+        let request = Erc20WithdrawalRequest {
+            withdrawal_fee: Some(fee),
+            fee_recipient: None,
+            ..erc20_withdrawal_request()
+        };
+        let token = request.erc20_contract_address.unwrap();
+        let withdrawal_id = request.id.clone();
+        let events = [
+            AcceptedErc20WithdrawalRequest(request),
+            EventType::UpdatedAdmin {
+                admin: new_admin.owner,
+            },
+        ];
+        for event in &events {
+            apply_state_transition(&mut state, event);
+        }
+
+        assert_eq!(state.erc20_balances.balance_of(&token, &former_admin), fee);
+        assert_eq!(
+            state.erc20_balances.balance_of(&token, &new_admin),
+            Erc20Value::ZERO
+        );
+        let mut replayed = initial_erc20_state();
+        apply_state_transition(
+            &mut replayed,
+            &EventType::AcceptedErc20Deposit(received_erc20_event()),
+        );
+        apply_state_transition(
+            &mut replayed,
+            &EventType::AcceptedDeposit(received_eth_event()),
+        );
+        for event in &events {
+            apply_state_transition(&mut replayed, event);
+        }
+        assert_eq!(replayed.erc20_balances, state.erc20_balances);
+
+        apply_state_transition(
+            &mut state,
+            &EventType::CancelledWithdrawalRequest {
+                withdrawal_id,
+                refunded_fee: Some(fee),
+            },
+        );
+        assert_eq!(
+            state.erc20_balances.balance_of(&token, &former_admin),
+            Erc20Value::ZERO
+        );
+        assert_eq!(
+            state.erc20_balances.balance_of(&token, &new_admin),
+            Erc20Value::ZERO
+        );
+    }
+
+    #[test]
+    fn should_give_back_amount_of_rejected_withdrawal_request() {
+        use crate::numeric::Erc20Value;
+        use icrc_ledger_types::icrc1::account::Account;
+
+        let mut state = initial_erc20_state();
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedErc20Deposit(received_erc20_event()),
+        );
+        apply_state_transition(
+            &mut state,
+            &EventType::AcceptedDeposit(received_eth_event()),
+        );
+        let fee = Erc20Value::from(1_000_u64);
+        let request = Erc20WithdrawalRequest {
+            withdrawal_fee: Some(fee),
+            fee_recipient: None,
+            ..erc20_withdrawal_request()
+        };
+        let token = request.erc20_contract_address.unwrap();
+        let from = request.from_account();
+        // Requests accepted without a fee recipient pay the fee to the admin.
+        let fee_recipient = Account::from(state.admin);
+        let balance_before = state.erc20_balances.balance_of(&token, &from);
+        apply_state_transition(&mut state, &AcceptedErc20WithdrawalRequest(request.clone()));
+
+        apply_state_transition(
+            &mut state,
+            &EventType::RejectedWithdrawalRequest {
+                withdrawal_id: request.id.clone(),
+                destination: request.destination,
+            },
+        );
+
+        assert_eq!(
+            state
+                .eth_transactions
+                .find_pending_withdrawal_request(&request.id),
+            None
+        );
+        assert_eq!(
+            state.erc20_balances.balance_of(&token, &from),
+            balance_before.checked_sub(fee).unwrap()
+        );
+        assert_eq!(state.erc20_balances.balance_of(&token, &fee_recipient), fee);
+    }
 }
+mod pending_deposits {
+    use crate::eth_logs::ReceivedEvent;
+    use crate::numeric::{BlockNumber, Erc20Value, Wei};
+    use crate::state::audit::{apply_state_transition, EventType};
+    use crate::state::tests::{
+        initial_erc20_state, initial_state, received_erc20_event, received_eth_event,
+    };
+    use crate::test_fixtures::expect_panic_with_message;
+    use icrc_ledger_types::icrc1::account::Account;
+
+    #[test]
+    fn should_record_pending_credit() {
+        let mut state = initial_state();
+        let deposit = received_eth_event();
+        let beneficiary = Account::from(deposit.principal);
+
+        state.record_pending_deposit(&deposit.clone().into());
+
+        assert!(state.is_known_deposit(&deposit.source()));
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), deposit.value);
+        assert_eq!(state.eth_balance.balance_of(&beneficiary), Wei::ZERO);
+        assert_eq!(state.eth_balance.eth_balance(), Wei::ZERO);
+        expect_panic_with_message(
+            || state.record_pending_deposit(&deposit.clone().into()),
+            "as pending",
+        );
+    }
+
+    #[test]
+    fn should_promote_pending_deposit_once_final_and_minted() {
+        let mut state = initial_state();
+        let deposit = received_eth_event();
+        let beneficiary = Account::from(deposit.principal);
+        state.record_pending_deposit(&deposit.clone().into());
+
+        apply_state_transition(&mut state, &EventType::AcceptedDeposit(deposit.clone()));
+
+        assert!(state.pending_deposits.is_empty());
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), deposit.value);
+        assert_eq!(state.eth_balance.balance_of(&beneficiary), Wei::ZERO);
+
+        apply_state_transition(
+            &mut state,
+            &EventType::MintedCkEth {
+                event_source: deposit.source(),
+                principal: deposit.principal,
+                amount: deposit.value,
+            },
+        );
+
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), Wei::ZERO);
+        assert_eq!(state.eth_balance.balance_of(&beneficiary), deposit.value);
+    }
+
+    #[test]
+    fn should_drop_pending_deposit_whose_log_disappeared() {
+        let mut state = initial_state();
+        let deposit = received_eth_event();
+        let beneficiary = Account::from(deposit.principal);
+        state.record_pending_deposit(&deposit.clone().into());
+
+        state.record_dropped_pending_deposit(deposit.source());
+
+        assert!(!state.is_known_deposit(&deposit.source()));
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), Wei::ZERO);
+        expect_panic_with_message(
+            || state.record_dropped_pending_deposit(deposit.source()),
+            "unknown pending deposit",
+        );
+    }
+
+    #[test]
+    fn should_drop_pending_deposit_not_accepted_once_final() {
+        let mut state = initial_state();
+        let deposit = received_eth_event();
+        state.record_pending_deposit(&deposit.clone().into());
+        state.last_scraped_block_number = deposit
+            .block_number
+            .checked_sub(BlockNumber::ONE)
+            .unwrap();
+        assert_eq!(state.stale_pending_deposits(), vec![]);
+
+        state.last_scraped_block_number = deposit.block_number;
+
+        assert_eq!(state.stale_pending_deposits(), vec![deposit.source()]);
+    }
+
+    #[test]
+    fn should_separate_pending_from_spendable_erc20_balance() {
+        let mut state = initial_erc20_state();
+        let minted = received_erc20_event();
+        let token = minted.erc20_contract_address;
+        let beneficiary = Account::from(minted.principal);
+        apply_state_transition(&mut state, &EventType::AcceptedErc20Deposit(minted.clone()));
+        apply_state_transition(
+            &mut state,
+            &EventType::MintedCkErc20 {
+                event_source: minted.source(),
+                principal: minted.principal,
+                amount: minted.value,
+            },
+        );
+        let pending = crate::eth_logs::ReceivedErc20Event {
+            transaction_hash: "0x705f826861c802b407843e99af986cfde8749b669e5e0a5a150f4350bcaa9bc3"
+                .parse()
+                .unwrap(),
+            value: Erc20Value::from(1_000_000_u64),
+            ..minted.clone()
+        };
+        state.record_pending_deposit(&ReceivedEvent::from(pending.clone()));
+
+        assert_eq!(
+            state.erc20_balances.balance_of(&token, &beneficiary),
+            minted.value
+        );
+        assert_eq!(
+            state.pending_erc20_balance_of(&token, &beneficiary),
+            pending.value
+        );
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), Wei::ZERO);
+    }
+
+    #[test]
+    fn should_scrape_pending_deposits_only_when_needed() {
+        let mut state = initial_state();
+        let head = BlockNumber::new(100);
+        assert!(state.should_scrape_pending_deposits(head));
+
+        state.last_pending_deposits_head = Some(head);
+        assert!(!state.should_scrape_pending_deposits(head));
+        assert!(state.should_scrape_pending_deposits(BlockNumber::new(101)));
+
+        state.record_pending_deposit(&received_eth_event().into());
+        assert!(state.should_scrape_pending_deposits(head));
+    }
+}
+
+mod reorged_deposit {
+    use crate::eth_logs::{EventSource, ReceivedEthEvent};
+    use crate::eth_rpc::Hash;
+    use crate::numeric::{BlockNumber, LogIndex, Wei};
+    use crate::state::audit::{apply_state_transition, EventType};
+    use crate::state::tests::{initial_state, received_eth_event};
+    use crate::state::State;
+    use ic_ethereum_types::Address;
+    use icrc_ledger_types::icrc1::account::Account;
+
+    fn deposit_in_block(block_number: u64, block_hash: [u8; 32]) -> ReceivedEthEvent {
+        ReceivedEthEvent {
+            block_number: BlockNumber::from(block_number),
+            block_hash: Some(Hash(block_hash)),
+            log_index: LogIndex::from(29_u8),
+            ..received_eth_event()
+        }
+    }
+
+    fn helper_contract_address() -> Address {
+        "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34".parse().unwrap()
+    }
+
+    fn apply_all(state: &mut State, events: &[EventType]) {
+        for event in events {
+            apply_state_transition(state, event);
+        }
+    }
+
+    #[test]
+    fn should_subtract_pending_credit_of_reorged_deposit() {
+        let mut state = initial_state();
+        let deposit = deposit_in_block(100, [1; 32]);
+        let beneficiary = Account::from(deposit.principal);
+        let eth_balance_before = state.eth_balance.clone();
+
+        state.record_pending_deposit(&deposit.clone().into());
+        apply_all(&mut state, &[EventType::AcceptedDeposit(deposit.clone())]);
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), deposit.value);
+
+        apply_state_transition(
+            &mut state,
+            &EventType::ReorgedDeposit {
+                event_source: deposit.source(),
+                canonical_block_hash: None,
+            },
+        );
+
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), Wei::ZERO);
+        assert_eq!(state.eth_balance, eth_balance_before);
+        assert!(state.reorged_events.contains_key(&deposit.source()));
+        assert!(!state.is_known_deposit(&deposit.source()));
+    }
+
+    #[test]
+    fn should_credit_deposit_found_in_another_block() {
+        let initial = initial_state();
+        let deposit = deposit_in_block(100, [1; 32]);
+        let remined = deposit_in_block(101, [2; 32]);
+        let source: EventSource = deposit.source();
+        assert_eq!(remined.source(), source);
+        let beneficiary = Account::from(deposit.principal);
+        let events = vec![
+            EventType::AcceptedDeposit(deposit.clone()),
+            EventType::ReorgedDeposit {
+                event_source: source,
+                canonical_block_hash: Some(Hash([2; 32])),
+            },
+            EventType::SkippedBlockForContract {
+                contract_address: helper_contract_address(),
+                block_number: BlockNumber::from(101_u64),
+            },
+            EventType::AcceptedDeposit(remined.clone()),
+            EventType::RetriedSkippedBlock {
+                contract_address: helper_contract_address(),
+                block_number: BlockNumber::from(101_u64),
+            },
+            EventType::MintedCkEth {
+                event_source: source,
+                principal: remined.principal,
+                amount: remined.value,
+            },
+        ];
+
+        let mut state = initial.clone();
+        apply_all(&mut state, &events[..3]);
+        assert!(state.skipped_blocks[&helper_contract_address()]
+            .contains(&BlockNumber::from(101_u64)));
+        apply_all(&mut state, &events[3..]);
+
+        assert!(state.reorged_events.is_empty());
+        assert!(state.skipped_blocks.is_empty());
+        assert_eq!(state.pending_eth_balance_of(&beneficiary), Wei::ZERO);
+        assert_eq!(
+            state.eth_balance.balance_of(&beneficiary),
+            remined.value
+        );
+        assert!(state.minted_events.contains_key(&source));
+
+        let mut replayed = initial;
+        apply_all(&mut replayed, &events);
+        assert_eq!(replayed.is_equivalent_to(&state), Ok(()));
+    }
+}
+
+mod skipped_blocks {
+    use crate::numeric::BlockNumber;
+    use crate::state::audit::{apply_state_transition, EventType};
+    use crate::state::tests::initial_state;
+    use ic_ethereum_types::Address;
+    use std::collections::BTreeSet;
+
+    fn helper_contract_address() -> Address {
+        "0xb44B5e756A894775FC32EDdf3314Bb1B1944dC34".parse().unwrap()
+    }
+
+    fn skipped(block_number: u64) -> EventType {
+        EventType::SkippedBlockForContract {
+            contract_address: helper_contract_address(),
+            block_number: BlockNumber::from(block_number),
+        }
+    }
+
+    fn retried(block_number: u64) -> EventType {
+        EventType::RetriedSkippedBlock {
+            contract_address: helper_contract_address(),
+            block_number: BlockNumber::from(block_number),
+        }
+    }
+
+    #[test]
+    fn should_remove_block_once_retried() {
+        let mut state = initial_state();
+        apply_state_transition(&mut state, &skipped(100));
+        apply_state_transition(&mut state, &skipped(101));
+
+        apply_state_transition(&mut state, &retried(100));
+        assert_eq!(
+            state.skipped_blocks.get(&helper_contract_address()),
+            Some(&BTreeSet::from([BlockNumber::from(101_u64)]))
+        );
+
+        apply_state_transition(&mut state, &retried(101));
+        assert!(state.skipped_blocks.is_empty());
+    }
+
+    #[test]
+    fn should_remove_retried_block_on_replay() {
+        let events = [skipped(100), skipped(101), retried(100)];
+        let mut state = initial_state();
+        for event in &events {
+            apply_state_transition(&mut state, event);
+        }
+
+        let mut replayed = initial_state();
+        for event in &events {
+            apply_state_transition(&mut replayed, event);
+        }
+
+        assert_eq!(replayed.skipped_blocks, state.skipped_blocks);
+        assert_eq!(
+            replayed.skipped_blocks[&helper_contract_address()],
+            BTreeSet::from([BlockNumber::from(101_u64)])
+        );
+        assert_eq!(replayed.is_equivalent_to(&state), Ok(()));
+    }
+
+    #[test]
+    fn should_not_retry_block_that_was_not_skipped() {
+        let mut state = initial_state();
+        apply_state_transition(&mut state, &skipped(100));
+
+        crate::test_fixtures::expect_panic_with_message(
+            || apply_state_transition(&mut state, &retried(101)),
+            "was not skipped",
+        );
+    }
+}
+
+mod pause {
+    use crate::endpoints::VaultFlow;
+    use crate::state::audit::{apply_state_transition, EventType};
+    use crate::state::event::Event;
+    use crate::state::tests::initial_state;
+    use ic_stable_structures::storable::Storable;
+    use std::collections::BTreeSet;
+
+    fn updated_pause(flow: VaultFlow, paused: bool) -> EventType {
+        EventType::UpdatedPause { flow, paused }
+    }
+
+    #[test]
+    fn should_replay_pause_events() {
+        let events = vec![
+            updated_pause(VaultFlow::Deposits, true),
+            updated_pause(VaultFlow::Transfers, true),
+            updated_pause(VaultFlow::Transactions, true),
+            updated_pause(VaultFlow::Deposits, false),
+        ];
+        let mut state = initial_state();
+        for event in &events {
+            apply_state_transition(&mut state, event);
+        }
+        assert_eq!(
+            state.paused_flows,
+            BTreeSet::from([VaultFlow::Transfers, VaultFlow::Transactions])
+        );
+        assert!(!state.is_paused(VaultFlow::Deposits));
+        assert!(!state.is_paused(VaultFlow::Withdrawals));
+
+        // Replay the events as they are read back from the stable event log after an upgrade.
+        let mut replayed = initial_state();
+        for (timestamp, payload) in events.into_iter().enumerate() {
+            let event = Event {
+                timestamp: timestamp as u64,
+                payload,
+            };
+            let decoded = Event::from_bytes(event.to_bytes());
+            assert_eq!(decoded, event);
+            apply_state_transition(&mut replayed, &decoded.payload);
+        }
+        assert_eq!(replayed.paused_flows, state.paused_flows);
+        assert_eq!(replayed.is_equivalent_to(&state), Ok(()));
+    }
+
+    #[test]
+    fn should_not_be_equivalent_with_different_paused_flows() {
+        let mut state = initial_state();
+        apply_state_transition(&mut state, &updated_pause(VaultFlow::Withdrawals, true));
+
+        assert!(initial_state().is_equivalent_to(&state).is_err());
+    }
+}
+
+mod rescrape {
+    use crate::eth_logs::ReceivedEthEvent;
+    use crate::numeric::LogIndex;
+    use crate::state::audit::{apply_state_transition, EventType};
+    use crate::state::tests::{initial_state, received_eth_event};
+
+    fn deposit_with_log_index(log_index: u8) -> ReceivedEthEvent {
+        ReceivedEthEvent {
+            log_index: LogIndex::from(log_index),
+            ..received_eth_event()
+        }
+    }
+
+    #[test]
+    fn should_only_record_deposits_not_seen_before() {
+        let mut state = initial_state();
+        let to_mint = deposit_with_log_index(1);
+        let minted = deposit_with_log_index(2);
+        let invalid = deposit_with_log_index(3);
+        let pending = deposit_with_log_index(4);
+        let missed = deposit_with_log_index(5);
+
+        apply_state_transition(&mut state, &EventType::AcceptedDeposit(to_mint.clone()));
+        apply_state_transition(&mut state, &EventType::AcceptedDeposit(minted.clone()));
+        apply_state_transition(
+            &mut state,
+            &EventType::MintedCkEth {
+                event_source: minted.source(),
+                principal: minted.principal,
+                amount: minted.value,
+            },
+        );
+        apply_state_transition(
+            &mut state,
+            &EventType::InvalidDeposit {
+                event_source: invalid.source(),
+                reason: "invalid principal".to_string(),
+            },
+        );
+        state.record_pending_deposit(&pending.clone().into());
+
+        assert!(state.events_to_mint.contains_key(&to_mint.source()));
+        assert!(state.minted_events.contains_key(&minted.source()));
+        assert!(state.invalid_events.contains_key(&invalid.source()));
+        assert!(!state.is_new_deposit(&to_mint.source()));
+        assert!(!state.is_new_deposit(&minted.source()));
+        assert!(!state.is_new_deposit(&invalid.source()));
+        assert!(state.is_new_deposit(&pending.source()));
+        assert!(state.is_new_deposit(&missed.source()));
+    }
+}
+
 fn initial_erc20_state() -> State {
     let mut state = initial_state();
     add_erc20_token(&mut state);
@@ -1782,7 +3186,7 @@ fn add_erc20_token(state: &mut State) {
     apply_state_transition(
         state,
         &EventType::AddedCkErc20Token(CkErc20Token {
-            erc20_ethereum_network: Default::default(),
+            erc20_chain_id: EthereumNetwork::default().chain_id(),
             erc20_contract_address: "0x1c7d4b196cb0c7b01d743fbc6116a902379c7238"
                 .parse()
                 .unwrap(),
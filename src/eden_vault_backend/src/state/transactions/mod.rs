@@ -1,17 +1,17 @@
 #[cfg(test)]
 mod tests;
 
-use crate::endpoints::{EthTransaction, RetrieveEthStatus, TxFinalizedStatus, WithdrawalStatus};
+use crate::endpoints::{
+    EthTransaction, QuarantineResolution, RetrieveEthStatus, TxFinalizedStatus, WithdrawalStatus,
+};
 use crate::eth_rpc::Hash;
 use crate::eth_rpc_client::responses::TransactionReceipt;
 use crate::eth_rpc_client::responses::TransactionStatus;
-use crate::lifecycle::EthereumNetwork;
 use crate::map::MultiKeyMap;
 use crate::numeric::{
     CkTokenAmount, Erc20Value, GasAmount, LedgerMintIndex, TransactionCount, TransactionNonce, Wei,
 };
 use crate::state::event::EventType;
-use crate::state::read_state;
 use crate::tx::{
     Eip1559TransactionRequest, FinalizedEip1559Transaction, GasFeeEstimate, ResubmissionStrategy,
     SignedEip1559TransactionRequest, SignedTransactionRequest, TransactionRequest,
@@ -33,17 +33,20 @@ pub enum WithdrawalSearchParameter {
 
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum WithdrawalRequest {
+    CkEth(EthWithdrawalRequest),
     CkErc20(Erc20WithdrawalRequest),
 }
 
 impl WithdrawalRequest {
     pub fn get_withdrawal_id(&self) -> Nat {
         match self {
+            WithdrawalRequest::CkEth(request) => request.ledger_burn_index.clone(),
             WithdrawalRequest::CkErc20(request) => request.id.clone(),
         }
     }
     pub fn created_at(&self) -> Option<u64> {
         match self {
+            WithdrawalRequest::CkEth(request) => request.created_at,
             WithdrawalRequest::CkErc20(request) => Some(request.created_at),
         }
     }
@@ -51,24 +54,33 @@ impl WithdrawalRequest {
     /// Address to which the funds are to be sent to.
     pub fn payee(&self) -> Address {
         match self {
+            WithdrawalRequest::CkEth(request) => request.destination,
             WithdrawalRequest::CkErc20(request) => request.destination,
         }
     }
 
     pub fn from(&self) -> Principal {
         match self {
+            WithdrawalRequest::CkEth(request) => request.from,
             WithdrawalRequest::CkErc20(request) => request.from,
         }
     }
 
     pub fn from_subaccount(&self) -> &Option<Subaccount> {
         match self {
+            WithdrawalRequest::CkEth(request) => &request.from_subaccount,
             WithdrawalRequest::CkErc20(request) => &request.from_subaccount,
         }
     }
 
+    /// Account from which the withdrawn funds were debited.
+    pub fn from_account(&self) -> Account {
+        to_account(self.from(), self.from_subaccount().clone())
+    }
+
     pub fn into_accepted_withdrawal_request_event(self) -> EventType {
         match self {
+            WithdrawalRequest::CkEth(request) => EventType::AcceptedEthWithdrawalRequest(request),
             WithdrawalRequest::CkErc20(request) => {
                 EventType::AcceptedErc20WithdrawalRequest(request)
             }
@@ -80,35 +92,40 @@ impl WithdrawalRequest {
         match parameter {
             ByWithdrawalId(index) => &self.get_withdrawal_id() == index,
             ByRecipient(address) => &self.payee() == address,
-            BySenderAccount(Account { owner, subaccount }) => {
-                &self.from() == owner && self.from_subaccount() == &subaccount.map(Subaccount)
-            }
+            BySenderAccount(account) => &self.from_account() == account,
         }
     }
 }
 
+impl From<EthWithdrawalRequest> for WithdrawalRequest {
+    fn from(value: EthWithdrawalRequest) -> Self {
+        WithdrawalRequest::CkEth(value)
+    }
+}
+
 impl From<Erc20WithdrawalRequest> for WithdrawalRequest {
     fn from(value: Erc20WithdrawalRequest) -> Self {
         WithdrawalRequest::CkErc20(value)
     }
 }
 
-/// Ethereum withdrawal request issued by the user.
+/// Native coin withdrawal request issued by the user.
 #[derive(Clone, Eq, PartialEq, Decode, Encode)]
 pub struct EthWithdrawalRequest {
-    /// The ETH amount that the receiver will get, not accounting for the Ethereum transaction fees.
+    /// The amount debited from the vault balance.
+    /// The receiver gets this amount minus the transaction fees.
     #[n(0)]
     pub withdrawal_amount: Wei,
     /// The address to which the minter will send ETH.
     #[n(1)]
     pub destination: Address,
-    /// The transaction ID of the ckETH burn operation.
+    /// The withdrawal identifier, shared with ERC-20 withdrawals.
     #[cbor(n(2), with = "crate::cbor::nat")]
     pub ledger_burn_index: Nat,
-    /// The owner of the account from which the minter burned ckETH.
+    /// The owner of the account from which the withdrawn amount was debited.
     #[cbor(n(3), with = "crate::cbor::principal")]
     pub from: Principal,
-    /// The subaccount from which the minter burned ckETH.
+    /// The subaccount from which the withdrawn amount was debited.
     #[n(4)]
     pub from_subaccount: Option<Subaccount>,
     /// The IC time at which the withdrawal request arrived.
@@ -141,11 +158,47 @@ pub struct Erc20WithdrawalRequest {
     /// The transaction ID.
     #[cbor(n(6), with = "crate::cbor::nat")]
     pub id: Nat,
+    /// The withdrawal fee debited from the account and credited to the fee recipient.
+    /// Missing for requests accepted before the fee was recorded.
+    #[n(7)]
+    pub withdrawal_fee: Option<Erc20Value>,
+    /// Address of the withdrawn ERC-20 token.
+    /// Missing for requests accepted before the vault held several tokens,
+    /// it is then filled with the default token when the request is recorded.
+    #[n(8)]
+    pub erc20_contract_address: Option<Address>,
+    /// Account credited with the withdrawal fee, the admin when the request was accepted.
+    /// Missing for requests accepted before the recipient was recorded,
+    /// it is then filled with the admin when the request is recorded.
+    #[cbor(n(9), with = "crate::cbor::account::option", has_nil)]
+    pub fee_recipient: Option<Account>,
+}
+
+impl Erc20WithdrawalRequest {
+    /// Account from which the withdrawn funds were debited.
+    pub fn from_account(&self) -> Account {
+        to_account(self.from, self.from_subaccount.clone())
+    }
+
+    /// Address of the withdrawn ERC-20 token, once the request was recorded.
+    pub fn token(&self) -> Address {
+        self.erc20_contract_address
+            .expect("BUG: recorded withdrawal request without ERC-20 token")
+    }
+
+    /// Account credited with the withdrawal fee, once the request was recorded.
+    pub fn fee_recipient(&self) -> Account {
+        self.fee_recipient
+            .expect("BUG: recorded withdrawal request without fee recipient")
+    }
 }
 
 impl From<&WithdrawalRequest> for ReimbursementIndex {
     fn from(value: &WithdrawalRequest) -> Self {
         match value {
+            WithdrawalRequest::CkEth(request) => ReimbursementIndex::CkEth {
+                ledger_burn_index: request.ledger_burn_index.clone(),
+            },
             WithdrawalRequest::CkErc20(request) => ReimbursementIndex::CkErc20 {
                 withdrawal_id: request.id.clone(),
             },
@@ -155,6 +208,11 @@ impl From<&WithdrawalRequest> for ReimbursementIndex {
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Decode, Encode)]
 pub enum ReimbursementIndex {
+    #[n(0)]
+    CkEth {
+        #[cbor(n(0), with = "crate::cbor::nat")]
+        ledger_burn_index: Nat,
+    },
     #[n(1)]
     CkErc20 {
         #[cbor(n(0), with = "crate::cbor::nat")]
@@ -165,6 +223,7 @@ pub enum ReimbursementIndex {
 impl ReimbursementIndex {
     pub fn id(&self) -> Nat {
         match self {
+            Self::CkEth { ledger_burn_index } => ledger_burn_index.clone(),
             Self::CkErc20 { withdrawal_id } => withdrawal_id.clone(),
         }
     }
@@ -187,10 +246,16 @@ pub struct ReimbursementRequest {
     /// on the ledger with the failed ETH transaction.
     #[n(4)]
     pub transaction_hash: Option<Hash>,
+    /// Address of the ERC-20 token to reimburse, not set for native coins.
+    #[n(5)]
+    pub erc20_contract_address: Option<Address>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Decode, Encode)]
 pub struct Reimbursed {
+    /// Index of the block appended when the reimbursement was recorded.
+    /// Only ERC-20 reimbursements are recorded in the block log: native coins are not,
+    /// so for them the index only tells when the reimbursement happened.
     #[cbor(n(0), with = "crate::cbor::id")]
     pub reimbursed_in_block: LedgerMintIndex,
     #[cbor(n(1), with = "crate::cbor::nat")]
@@ -211,9 +276,13 @@ pub enum ReimbursedError {
     /// The reimbursement request is quarantined to avoid any double minting and
     /// will not be further processed without manual intervention.
     Quarantined,
+    /// The admin marked the quarantined reimbursement as already credited, for the given reason.
+    MarkedReimbursed(String),
+    /// The admin rejected the quarantined reimbursement, for the given reason.
+    Rejected(String),
 }
 
-#[derive(Clone, Eq, PartialEq, Decode, Encode)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Decode, Encode)]
 #[cbor(transparent)]
 pub struct Subaccount(#[cbor(n(0), with = "minicbor::bytes")] pub [u8; 32]);
 
@@ -221,6 +290,21 @@ impl Subaccount {
     pub fn to_bytes(self) -> [u8; 32] {
         self.0
     }
+
+    /// Converts an ICRC subaccount, representing the default subaccount as `None`.
+    pub fn from_icrc(subaccount: Option<[u8; 32]>) -> Option<Self> {
+        subaccount
+            .filter(|subaccount| subaccount != &[0; 32])
+            .map(Subaccount)
+    }
+}
+
+/// Builds the ICRC account of `owner` identified by the given subaccount.
+pub fn to_account(owner: Principal, subaccount: Option<Subaccount>) -> Account {
+    Account {
+        owner,
+        subaccount: subaccount.map(Subaccount::to_bytes),
+    }
 }
 
 impl fmt::Debug for Subaccount {
@@ -268,6 +352,9 @@ impl fmt::Debug for Erc20WithdrawalRequest {
             from_subaccount,
             created_at,
             id,
+            withdrawal_fee,
+            erc20_contract_address,
+            fee_recipient,
         } = self;
         f.debug_struct("Erc20WithdrawalRequest")
             .field("max_transaction_fee", max_transaction_fee)
@@ -277,6 +364,9 @@ impl fmt::Debug for Erc20WithdrawalRequest {
             .field("from_subaccount", from_subaccount)
             .field("created_at", created_at)
             .field("id", id)
+            .field("withdrawal_fee", withdrawal_fee)
+            .field("erc20_contract_address", erc20_contract_address)
+            .field("fee_recipient", fee_recipient)
             .finish()
     }
 }
@@ -309,6 +399,9 @@ pub struct EthTransactions {
     pub(in crate::state) maybe_reimburse: BTreeSet<Nat>,
     pub(in crate::state) reimbursement_requests: BTreeMap<ReimbursementIndex, ReimbursementRequest>,
     pub(in crate::state) reimbursed: BTreeMap<ReimbursementIndex, ReimbursedResult>,
+    /// Requests of the quarantined reimbursements, kept until the admin resolves them.
+    pub(in crate::state) quarantined_reimbursements:
+        BTreeMap<ReimbursementIndex, ReimbursementRequest>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -318,6 +411,11 @@ pub enum CreateTransactionError {
         allowed_max_transaction_fee: Wei,
         actual_max_transaction_fee: Wei,
     },
+    /// The destination was blocked after the withdrawal request was accepted.
+    BlockedDestination {
+        withdrawal_id: Nat,
+        destination: Address,
+    },
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -342,6 +440,7 @@ impl EthTransactions {
             maybe_reimburse: Default::default(),
             reimbursement_requests: Default::default(),
             reimbursed: Default::default(),
+            quarantined_reimbursements: Default::default(),
         }
     }
 
@@ -359,6 +458,12 @@ impl EthTransactions {
         self.reimbursement_requests.iter()
     }
 
+    pub fn quarantined_reimbursements_iter(
+        &self,
+    ) -> impl Iterator<Item = (&ReimbursementIndex, &ReimbursementRequest)> {
+        self.quarantined_reimbursements.iter()
+    }
+
     pub fn reimbursed_transactions_iter(
         &self,
     ) -> impl Iterator<Item = (&ReimbursementIndex, &ReimbursedResult)> {
@@ -371,14 +476,7 @@ impl EthTransactions {
     ) -> Option<&ReimbursedResult> {
         self.reimbursed
             .iter()
-            .find_map(|(index, value)| match index {
-                ReimbursementIndex::CkErc20 { withdrawal_id, .. }
-                    if withdrawal_id == searched_burn_index =>
-                {
-                    Some(value)
-                }
-                _ => None,
-            })
+            .find_map(|(index, value)| (&index.id() == searched_burn_index).then_some(value))
     }
 
     pub fn record_withdrawal_request<R: Into<WithdrawalRequest>>(&mut self, request: R) {
@@ -397,6 +495,24 @@ impl EthTransactions {
         self.pending_withdrawal_requests.push_back(request);
     }
 
+    /// Returns the withdrawal request with the given id if it is still waiting in the queue,
+    /// i.e. no transaction was created for it yet.
+    pub fn find_pending_withdrawal_request(&self, withdrawal_id: &Nat) -> Option<&WithdrawalRequest> {
+        self.pending_withdrawal_requests
+            .iter()
+            .find(|r| &r.get_withdrawal_id() == withdrawal_id)
+    }
+
+    /// Remove a withdrawal request from the queue before any transaction was created for it.
+    pub fn record_cancelled_withdrawal_request(&mut self, withdrawal_id: &Nat) -> WithdrawalRequest {
+        let request = self
+            .find_pending_withdrawal_request(withdrawal_id)
+            .cloned()
+            .unwrap_or_else(|| panic!("BUG: pending withdrawal request {withdrawal_id} not found"));
+        self.remove_withdrawal_request(&request);
+        request
+    }
+
     /// Move an existing withdrawal request to the back of the queue.
     pub fn reschedule_withdrawal_request<R: Into<WithdrawalRequest>>(&mut self, request: R) {
         let request = request.into();
@@ -430,6 +546,16 @@ impl EthTransactions {
             "BUG: withdrawal request not found"
         );
         match &withdrawal_request {
+            WithdrawalRequest::CkEth(req) => {
+                assert_eq!(
+                    req.destination, transaction.destination,
+                    "BUG: withdrawal request and transaction destination mismatch"
+                );
+                assert!(
+                    req.withdrawal_amount >= transaction.amount,
+                    "BUG: transaction amount should be at most the withdrawal amount"
+                );
+            }
             WithdrawalRequest::CkErc20(_req) => {
                 assert_eq!(
                     Wei::ZERO,
@@ -448,6 +574,9 @@ impl EthTransactions {
         let transaction_request = TransactionRequest {
             transaction,
             resubmission: match &withdrawal_request {
+                WithdrawalRequest::CkEth(cketh) => ResubmissionStrategy::ReduceEthAmount {
+                    withdrawal_amount: cketh.withdrawal_amount,
+                },
                 WithdrawalRequest::CkErc20(ckerc20) => ResubmissionStrategy::GuaranteeEthAmount {
                     allowed_max_transaction_fee: ckerc20.max_transaction_fee,
                 },
@@ -629,6 +758,21 @@ impl EthTransactions {
             .expect("failed to find entry from processed_withdrawal_requests with block index: {ledger_burn_index}");
         let index = ReimbursementIndex::from(request);
         match &request {
+            WithdrawalRequest::CkEth(request) => {
+                if receipt.status == TransactionStatus::Failure {
+                    self.record_reimbursement_request(
+                        index,
+                        ReimbursementRequest {
+                            ledger_burn_index: request.ledger_burn_index.clone(),
+                            reimbursed_amount: finalized_tx.transaction_amount().change_units(),
+                            to: request.from,
+                            to_subaccount: request.from_subaccount.clone(),
+                            transaction_hash: Some(receipt.transaction_hash),
+                            erc20_contract_address: None,
+                        },
+                    );
+                }
+            }
             WithdrawalRequest::CkErc20(request) => {
                 if receipt.status == TransactionStatus::Failure {
                     self.record_reimbursement_request(
@@ -639,6 +783,7 @@ impl EthTransactions {
                             to: request.from,
                             to_subaccount: request.from_subaccount.clone(),
                             transaction_hash: Some(receipt.transaction_hash),
+                            erc20_contract_address: request.erc20_contract_address,
                         },
                     );
                 }
@@ -668,15 +813,76 @@ impl EthTransactions {
         );
     }
 
+    /// Marks the reimbursement request identified by its index as processed
+    /// and returns the request that was reimbursed.
+    pub fn record_finalized_reimbursement(
+        &mut self,
+        index: ReimbursementIndex,
+        reimbursed: Reimbursed,
+    ) -> ReimbursementRequest {
+        let request = self
+            .reimbursement_requests
+            .remove(&index)
+            .unwrap_or_else(|| panic!("BUG: missing reimbursement request for {index:?}"));
+        assert_eq!(
+            self.reimbursed.insert(index.clone(), Ok(reimbursed)),
+            None,
+            "BUG: reimbursement for withdrawal {index:?} was already processed"
+        );
+        request
+    }
+
     /// Quarantine the reimbursement request identified by its index to prevent double minting.
     /// WARNING!: It's crucial that this method does not panic,
     /// since it's called inside the clean-up callback, when an unexpected panic did occur before.
     pub fn record_quarantined_reimbursement(&mut self, index: ReimbursementIndex) {
-        self.reimbursement_requests.remove(&index);
+        if let Some(request) = self.reimbursement_requests.remove(&index) {
+            self.quarantined_reimbursements.insert(index.clone(), request);
+        }
         self.reimbursed
             .insert(index, Err(ReimbursedError::Quarantined));
     }
 
+    pub fn is_quarantined_reimbursement(&self, index: &ReimbursementIndex) -> bool {
+        matches!(self.reimbursed.get(index), Some(Err(ReimbursedError::Quarantined)))
+    }
+
+    /// Resolves a quarantined reimbursement as decided by the admin.
+    /// A released reimbursement is processed again, otherwise it is never credited by the minter.
+    pub fn record_resolved_quarantined_reimbursement(
+        &mut self,
+        index: ReimbursementIndex,
+        resolution: QuarantineResolution,
+        reason: String,
+    ) {
+        assert!(
+            self.is_quarantined_reimbursement(&index),
+            "BUG: reimbursement {index:?} is not quarantined"
+        );
+        let request = self.quarantined_reimbursements.remove(&index);
+        match resolution {
+            QuarantineResolution::Release => {
+                let request = request.unwrap_or_else(|| {
+                    panic!("BUG: cannot release reimbursement {index:?} without its request")
+                });
+                self.reimbursed.remove(&index);
+                assert_eq!(
+                    self.reimbursement_requests.insert(index.clone(), request),
+                    None,
+                    "BUG: reimbursement request for withdrawal {index:?} already exists"
+                );
+            }
+            QuarantineResolution::MarkProcessed => {
+                self.reimbursed
+                    .insert(index, Err(ReimbursedError::MarkedReimbursed(reason)));
+            }
+            QuarantineResolution::Reject => {
+                self.reimbursed
+                    .insert(index, Err(ReimbursedError::Rejected(reason)));
+            }
+        }
+    }
+
     pub fn withdrawal_status(
         &self,
         parameter: &WithdrawalSearchParameter,
@@ -889,6 +1095,10 @@ impl EthTransactions {
         self.sent_tx.is_empty()
     }
 
+    pub fn has_reimbursement_requests(&self) -> bool {
+        !self.reimbursement_requests.is_empty()
+    }
+
     pub fn has_pending_requests(&self) -> bool {
         !self.pending_withdrawal_requests.is_empty()
             || !self.created_tx.is_empty()
@@ -946,6 +1156,10 @@ impl EthTransactions {
         ensure_eq!(self.maybe_reimburse, other.maybe_reimburse);
         ensure_eq!(self.reimbursement_requests, other.reimbursement_requests);
         ensure_eq!(self.reimbursed, other.reimbursed);
+        ensure_eq!(
+            self.quarantined_reimbursements,
+            other.quarantined_reimbursements
+        );
 
         Ok(())
     }
@@ -959,23 +1173,56 @@ impl EthTransactions {
 }
 
 /// Creates an EIP-1559 transaction for the given withdrawal request.
-/// The transaction fees are paid by the beneficiary,
+/// For native coin withdrawals, the transaction fees are paid by the beneficiary,
 /// meaning that the fees will be deducted from the withdrawal amount.
 ///
 /// # Errors
 /// * `CreateTransactionError::InsufficientTransactionFee` if the ETH withdrawal amount does not cover the transaction fee.
+/// * `CreateTransactionError::BlockedDestination` if the destination is blocked.
 pub fn create_transaction(
     withdrawal_request: &WithdrawalRequest,
     nonce: TransactionNonce,
     gas_fee_estimate: GasFeeEstimate,
     gas_limit: GasAmount,
-    ethereum_network: EthereumNetwork,
+    chain_id: u64,
 ) -> Result<Eip1559TransactionRequest, CreateTransactionError> {
     assert!(
         gas_limit > GasAmount::ZERO,
         "BUG: gas limit should be non-zero"
     );
+    let destination = withdrawal_request.payee();
+    if crate::blocklist::is_blocked(&destination) {
+        return Err(CreateTransactionError::BlockedDestination {
+            withdrawal_id: withdrawal_request.get_withdrawal_id(),
+            destination,
+        });
+    }
     match withdrawal_request {
+        WithdrawalRequest::CkEth(request) => {
+            let transaction_price = gas_fee_estimate.to_price(gas_limit);
+            let max_transaction_fee = transaction_price.max_transaction_fee();
+            let tx_amount = match request.withdrawal_amount.checked_sub(max_transaction_fee) {
+                Some(tx_amount) => tx_amount,
+                None => {
+                    return Err(CreateTransactionError::InsufficientTransactionFee {
+                        withdrawal_id: request.ledger_burn_index.clone(),
+                        allowed_max_transaction_fee: request.withdrawal_amount,
+                        actual_max_transaction_fee: max_transaction_fee,
+                    });
+                }
+            };
+            Ok(Eip1559TransactionRequest {
+                chain_id,
+                nonce,
+                max_priority_fee_per_gas: transaction_price.max_priority_fee_per_gas,
+                max_fee_per_gas: transaction_price.max_fee_per_gas,
+                gas_limit: transaction_price.gas_limit,
+                destination: request.destination,
+                amount: tx_amount,
+                data: Vec::new(),
+                access_list: Default::default(),
+            })
+        }
         WithdrawalRequest::CkErc20(request) => {
             // The transaction fee is already paid and must be at most
             // the `max_transaction_fee` in the withdrawal request, which, given a gas limit, gives us an upper bound on
@@ -999,15 +1246,13 @@ pub fn create_transaction(
                 });
             }
 
-            let erc20_contract_address = read_state(|s| s.ckerc20_tokens.0);
-
             Ok(Eip1559TransactionRequest {
-                chain_id: ethereum_network.chain_id(),
+                chain_id,
                 nonce,
                 max_priority_fee_per_gas: gas_fee_estimate.max_priority_fee_per_gas,
                 max_fee_per_gas: request_max_fee_per_gas,
                 gas_limit,
-                destination: erc20_contract_address,
+                destination: request.token(),
                 amount: Wei::ZERO,
                 data: TransactionCallData::Erc20Transfer {
                     to: request.destination,
@@ -324,7 +324,7 @@ mod eth_transactions {
                 TransactionNonce::ZERO,
                 gas_fee_estimate(),
                 estimate_gas_limit(&withdrawal_request),
-                EthereumNetwork::Sepolia,
+                EthereumNetwork::Sepolia.chain_id(),
             )
             .unwrap();
 
@@ -345,7 +345,7 @@ mod eth_transactions {
                 TransactionNonce::ZERO,
                 gas_fee_estimate(),
                 estimate_gas_limit(&withdrawal_request.clone().into()),
-                EthereumNetwork::Sepolia,
+                EthereumNetwork::Sepolia.chain_id(),
             )
             .unwrap();
 
@@ -396,7 +396,7 @@ mod eth_transactions {
                 TransactionNonce::ZERO,
                 gas_fee_estimate(),
                 estimate_gas_limit(&withdrawal_request.clone().into()),
-                EthereumNetwork::Sepolia,
+                EthereumNetwork::Sepolia.chain_id(),
             )
             .unwrap();
             let tx_mixing_payee_address_with_erc20_address = Eip1559TransactionRequest {
@@ -444,7 +444,7 @@ mod eth_transactions {
                     wrong_nonce,
                     gas_fee_estimate(),
                     CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
-                    EthereumNetwork::Sepolia,
+                    EthereumNetwork::Sepolia.chain_id(),
                 )
                 .unwrap();
 
@@ -1810,6 +1810,7 @@ mod eth_transactions {
         use crate::numeric::TransactionNonce;
         use crate::state::transactions::tests::create_ck_withdrawal_requests;
         use crate::state::transactions::tests::eth_transactions::withdrawal_flow;
+        use crate::endpoints::QuarantineResolution;
         use crate::state::transactions::{EthTransactions, ReimbursedError, ReimbursementIndex};
         use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
         use maplit::{btreemap, btreeset};
@@ -1837,6 +1838,31 @@ mod eth_transactions {
                 }
             )
         }
+
+        #[test]
+        fn should_release_quarantined_reimbursement() {
+            let mut transactions = EthTransactions::new(TransactionNonce::ZERO);
+            let mut rng = reproducible_rng();
+            let [withdrawal_request] = create_ck_withdrawal_requests(&mut rng);
+            let reimbursement_index = ReimbursementIndex::from(&withdrawal_request);
+            let _eth_transaction = withdrawal_flow(
+                &mut transactions,
+                withdrawal_request,
+                TransactionStatus::Failure,
+            );
+            let reimbursement_requests = transactions.reimbursement_requests.clone();
+            transactions.record_quarantined_reimbursement(reimbursement_index.clone());
+
+            transactions.record_resolved_quarantined_reimbursement(
+                reimbursement_index,
+                QuarantineResolution::Release,
+                "ledger was not called".to_string(),
+            );
+
+            assert_eq!(transactions.reimbursement_requests, reimbursement_requests);
+            assert_eq!(transactions.quarantined_reimbursements, btreemap! {});
+            assert_eq!(transactions.reimbursed, btreemap! {});
+        }
     }
 
     mod transaction_status {
@@ -2287,7 +2313,7 @@ mod create_transaction {
                 TransactionNonce::TWO,
                 gas_fee.clone(),
                 gas_limit,
-                EthereumNetwork::Sepolia,
+                EthereumNetwork::Sepolia.chain_id(),
             );
             prop_assert_eq!(
                 result,
@@ -2309,7 +2335,7 @@ mod create_transaction {
                 TransactionNonce::TWO,
                 gas_fee,
                 gas_limit,
-                EthereumNetwork::Sepolia,
+                EthereumNetwork::Sepolia.chain_id(),
             );
             prop_assert_eq!(
                 result,
@@ -2345,7 +2371,7 @@ mod create_transaction {
                 TransactionNonce::TWO,
                 gas_fee,
                 gas_limit,
-                EthereumNetwork::Sepolia,
+                EthereumNetwork::Sepolia.chain_id(),
             );
 
             prop_assert_eq!(result, Ok(Eip1559TransactionRequest {
@@ -2399,7 +2425,7 @@ mod create_transaction {
                 TransactionNonce::from(0x57_u32),
                 gas_fee.clone(),
                 gas_limit,
-                EthereumNetwork::Mainnet,
+                EthereumNetwork::Mainnet.chain_id(),
             ).unwrap();
             let tx_max_fee_per_gas = result.max_fee_per_gas;
             let max_tx_fee = tx_max_fee_per_gas.transaction_cost(gas_limit).unwrap();
@@ -2470,7 +2496,7 @@ mod withdrawal_flow {
                     nonce,
                     gas_fee_estimate.clone(),
                     estimate_gas_limit(&request),
-                    EthereumNetwork::Sepolia,
+                    EthereumNetwork::Sepolia.chain_id(),
                 ){
                     wrapped_txs.borrow_mut().record_created_transaction(request.cketh_ledger_burn_index(), created_tx);
                 }
@@ -2853,7 +2879,7 @@ fn create_and_record_transaction<R: Into<WithdrawalRequest>>(
         transactions.next_transaction_nonce(),
         gas_fee_estimate,
         estimate_gas_limit(&withdrawal_request),
-        EthereumNetwork::Sepolia,
+        EthereumNetwork::Sepolia.chain_id(),
     )
     .expect("failed to create transaction");
     transactions.record_created_transaction(withdrawal_request.cketh_ledger_burn_index(), tx);
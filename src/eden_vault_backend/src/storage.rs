@@ -1,18 +1,25 @@
+use crate::icrc::blocks::{Block, Operation};
 use crate::state::event::{Event, EventType};
+use ic_ethereum_types::Address;
 use ic_stable_structures::{
     log::Log as StableLog,
     memory_manager::{MemoryId, MemoryManager, VirtualMemory},
     storable::{Bound, Storable},
-    DefaultMemoryImpl,
+    DefaultMemoryImpl, StableBTreeMap,
 };
 use std::borrow::Cow;
 use std::cell::RefCell;
 
 const LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(0);
 const LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(1);
+const BLOCKS_INDEX_MEMORY_ID: MemoryId = MemoryId::new(2);
+const BLOCKS_DATA_MEMORY_ID: MemoryId = MemoryId::new(3);
+const BLOCKLIST_MEMORY_ID: MemoryId = MemoryId::new(4);
 
 type VMem = VirtualMemory<DefaultMemoryImpl>;
 type EventLog = StableLog<Event, VMem, VMem>;
+type BlockLog = StableLog<Block, VMem, VMem>;
+type Blocklist = StableBTreeMap<[u8; 20], (), VMem>;
 
 impl Storable for Event {
     fn to_bytes(&self) -> Cow<[u8]> {
@@ -29,6 +36,21 @@ impl Storable for Event {
     const BOUND: Bound = Bound::Unbounded;
 }
 
+impl Storable for Block {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        minicbor::encode(self, &mut buf).expect("block encoding should always succeed");
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        minicbor::decode(bytes.as_ref())
+            .unwrap_or_else(|e| panic!("failed to decode block bytes {}: {e}", hex::encode(bytes)))
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
 thread_local! {
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(
         MemoryManager::init(DefaultMemoryImpl::default())
@@ -44,6 +66,21 @@ thread_local! {
                   ).expect("failed to initialize stable log")
               )
         );
+
+    /// The ICRC-3 log of the movements of the vault balances.
+    static BLOCKS: RefCell<BlockLog> = MEMORY_MANAGER
+        .with(|m|
+              RefCell::new(
+                  StableLog::init(
+                      m.borrow().get(BLOCKS_INDEX_MEMORY_ID),
+                      m.borrow().get(BLOCKS_DATA_MEMORY_ID)
+                  ).expect("failed to initialize stable block log")
+              )
+        );
+
+    /// Addresses blocked at runtime, on top of the static blocklist.
+    static BLOCKLIST: RefCell<Blocklist> = MEMORY_MANAGER
+        .with(|m| RefCell::new(StableBTreeMap::init(m.borrow().get(BLOCKLIST_MEMORY_ID))));
 }
 
 /// Appends the event to the event log.
@@ -69,3 +106,56 @@ where
 {
     EVENTS.with(|events| f(Box::new(events.borrow().iter())))
 }
+
+/// Appends a block with the given operation to the block log, chaining it to the previous block.
+/// Returns the index of the new block.
+pub fn append_block(operation: Operation, timestamp: u64) -> u64 {
+    let parent_hash = last_block().map(|(_, block)| block.hash());
+    BLOCKS
+        .with(|blocks| {
+            blocks.borrow().append(&Block {
+                parent_hash,
+                timestamp,
+                operation,
+            })
+        })
+        .expect("recording a block should succeed")
+}
+
+/// Returns the total number of blocks in the block log.
+pub fn total_block_count() -> u64 {
+    BLOCKS.with(|blocks| blocks.borrow().len())
+}
+
+pub fn get_block(index: u64) -> Option<Block> {
+    BLOCKS.with(|blocks| blocks.borrow().get(index))
+}
+
+/// Returns the last block together with its index.
+pub fn last_block() -> Option<(u64, Block)> {
+    let index = total_block_count().checked_sub(1)?;
+    get_block(index).map(|block| (index, block))
+}
+
+pub fn block_address(address: Address) {
+    BLOCKLIST.with(|blocklist| blocklist.borrow_mut().insert(address.into_bytes(), ()));
+}
+
+pub fn unblock_address(address: Address) {
+    BLOCKLIST.with(|blocklist| blocklist.borrow_mut().remove(&address.into_bytes()));
+}
+
+pub fn is_address_in_blocklist(address: &Address) -> bool {
+    BLOCKLIST.with(|blocklist| blocklist.borrow().contains_key(&address.into_bytes()))
+}
+
+/// Returns the addresses blocked at runtime, in increasing order.
+pub fn blocklist() -> Vec<Address> {
+    BLOCKLIST.with(|blocklist| {
+        blocklist
+            .borrow()
+            .iter()
+            .map(|(address, ())| Address::new(address))
+            .collect()
+    })
+}
@@ -51,9 +51,11 @@ mod eth_get_logs {
     use crate::eth_logs::{ReceivedErc20Event, ReceivedEthEvent, ReceivedEvent};
     use crate::eth_rpc::LogEntry;
     use crate::numeric::{BlockNumber, Erc20Value, LogIndex, Wei};
+    use assert_matches::assert_matches;
     use candid::Principal;
     use ic_ethereum_types::Address;
     use ic_sha3::Keccak256;
+    use icrc_ledger_types::icrc1::account::Account;
     use std::str::FromStr;
 
     #[test]
@@ -136,6 +138,11 @@ mod eth_get_logs {
                 .unwrap(),
             value: Wei::from(10_000_000_000_000_000_u128),
             principal: Principal::from_str("2chl6-4hpzw-vqaaa-aaaaa-c").unwrap(),
+            block_hash: Some(
+                "0x8436209a391f7bc076123616ecb229602124eb6c1007f5eae84df8e098885d3c"
+                    .parse()
+                    .unwrap(),
+            ),
         }
         .into();
 
@@ -179,12 +186,71 @@ mod eth_get_logs {
             erc20_contract_address: "0x7439e9bb6d8a84dd3a23fe621a30f95403f87fb9"
                 .parse()
                 .unwrap(),
+            subaccount: None,
+            block_hash: Some(
+                "0x0cbfb260e2e589ef110e63314279eb3ef2e307e46fa5409f08c101976858f80a"
+                    .parse()
+                    .unwrap(),
+            ),
         }
         .into();
 
         assert_eq!(parsed_event, expected_event);
     }
 
+    #[test]
+    fn should_have_correct_erc20_with_subaccount_topic() {
+        use crate::deposit::RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC;
+
+        //must match event signature in ERC20DepositHelper.sol
+        let event_signature = "ReceivedErc20WithSubaccount(address,address,uint256,bytes32,bytes32)";
+        let topic = Keccak256::hash(event_signature);
+        assert_eq!(topic, RECEIVED_ERC20_WITH_SUBACCOUNT_EVENT_TOPIC)
+    }
+
+    #[test]
+    fn should_parse_received_erc20_with_subaccount_event() {
+        use crate::state::transactions::Subaccount;
+
+        let event = r#"{
+            "address": "0xE1788E4834c896F1932188645cc36c54d1b80AC1",
+            "topics": [
+                "0x67a41ea6545cbed2c1a471aa538745d7eddb5fef74274cfb21264f86896fb32a",
+                "0x0000000000000000000000007439e9bb6d8a84dd3a23fe621a30f95403f87fb9",
+                "0x000000000000000000000000dd2851cdd40ae6536831558dd46db62fac7a844d",
+                "0x1d9facb184cbe453de4841b6b9d9cc95bfc065344e485789b550544529020000"
+            ],
+            "data": "0x0000000000000000000000000000000000000000000000008ac7230489e80000ff00000000000000000000000000000000000000000000000000000000000001",
+            "blockNumber": "0x5146a4",
+            "transactionHash": "0x44d8e93a8f4bbc89ad35fc4fbbdb12cb597b4832da09c0b2300777be180fde87",
+            "transactionIndex": "0x22",
+            "blockHash": "0x0cbfb260e2e589ef110e63314279eb3ef2e307e46fa5409f08c101976858f80a",
+            "logIndex": "0x27",
+            "removed": false
+        }"#;
+        let parsed_event =
+            ReceivedEvent::try_from(serde_json::from_str::<LogEntry>(event).unwrap()).unwrap();
+        let mut subaccount = [0_u8; 32];
+        subaccount[0] = 0xff;
+        subaccount[31] = 0x01;
+
+        assert_matches!(
+            parsed_event,
+            ReceivedEvent::Erc20(ref event) if event.subaccount == Some(Subaccount(subaccount))
+                && event.value == Erc20Value::from(10_000_000_000_000_000_000_u128)
+        );
+        assert_eq!(
+            parsed_event.beneficiary(),
+            Account {
+                owner: Principal::from_str(
+                    "hkroy-sm7vs-yyjs7-ekppe-qqnwx-hm4zf-n7ybs-titsi-k6e3k-ucuiu-uqe",
+                )
+                .unwrap(),
+                subaccount: Some(subaccount),
+            }
+        );
+    }
+
     #[test]
     fn should_not_parse_removed_event() {
         use crate::eth_logs::{EventSource, EventSourceError, ReceivedEventError};
@@ -841,3 +907,166 @@ mod eth_fee_history {
         )
     }
 }
+
+mod evm_network {
+    use crate::endpoints::CandidBlockTag;
+    use crate::lifecycle::network::{EvmNetwork, EvmNetworkArg, FeePolicyArg, RpcEndpoint};
+    use crate::lifecycle::EthereumNetwork;
+    use crate::numeric::{BlockNumber, Wei, WeiPerGas};
+    use assert_matches::assert_matches;
+    use candid::Nat;
+
+    #[test]
+    fn should_have_correct_bsc_chain_ids() {
+        assert_eq!(EthereumNetwork::BSC.chain_id(), 56);
+        assert_eq!(EthereumNetwork::BSCTestnet.chain_id(), 97);
+        assert_eq!(EthereumNetwork::try_from(56), Ok(EthereumNetwork::BSC));
+        assert_eq!(EthereumNetwork::try_from(97), Ok(EthereumNetwork::BSCTestnet));
+    }
+
+    #[test]
+    fn should_configure_unknown_network() {
+        let network = EvmNetwork::try_from(EvmNetworkArg {
+            fee_policy: Some(FeePolicyArg {
+                min_max_priority_fee_per_gas: Some(Nat::from(30_000_000_000_u64)),
+                native_transfer_fee: None,
+            }),
+            ..polygon_arg()
+        })
+        .unwrap();
+
+        assert_eq!(network.chain_id, 137);
+        assert_eq!(network.native_token_symbol, "POL");
+        assert_eq!(network.consensus_threshold, 2);
+        assert_eq!(
+            network.fee_policy.min_max_priority_fee_per_gas,
+            WeiPerGas::new(30_000_000_000)
+        );
+        assert_eq!(network.fee_policy.native_transfer_fee, Wei::new(1_000_000_000));
+        assert_eq!(network.deposit_confirmations, 64);
+        assert!(network.optimistic_deposits);
+        assert_eq!(network.pending_deposits_scraping_interval_seconds, 5);
+    }
+
+    #[test]
+    fn should_default_to_known_network() {
+        let network = EvmNetwork::try_from(EvmNetworkArg {
+            chain_id: 56,
+            name: "BNB Smart Chain".to_string(),
+            rpc_endpoints: vec![],
+            consensus_threshold: None,
+            finality: None,
+            native_token_symbol: None,
+            fee_policy: None,
+            deposit_confirmations: None,
+            optimistic_deposits: None,
+            pending_deposits_scraping_interval_seconds: None,
+        })
+        .unwrap();
+
+        assert_eq!(
+            network,
+            EvmNetwork {
+                name: "BNB Smart Chain".to_string(),
+                ..EvmNetwork::from(EthereumNetwork::BSC)
+            }
+        );
+    }
+
+    #[test]
+    fn should_wait_for_deposit_confirmations() {
+        let network = EvmNetwork::from(EthereumNetwork::Mainnet);
+        assert_eq!(network.deposit_confirmations, 12);
+
+        let deposit_block = BlockNumber::new(100);
+        assert!(!network.is_deposit_confirmed(deposit_block, BlockNumber::new(100)));
+        assert!(!network.is_deposit_confirmed(deposit_block, BlockNumber::new(111)));
+        assert!(network.is_deposit_confirmed(deposit_block, BlockNumber::new(112)));
+
+        let local = EvmNetwork::from(EthereumNetwork::Local);
+        assert!(local.is_deposit_confirmed(deposit_block, deposit_block));
+    }
+
+    #[test]
+    fn should_reject_invalid_network() {
+        assert_matches!(
+            EvmNetwork::try_from(EvmNetworkArg {
+                rpc_endpoints: vec![],
+                ..polygon_arg()
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            EvmNetwork::try_from(EvmNetworkArg {
+                consensus_threshold: Some(4),
+                ..polygon_arg()
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            EvmNetwork::try_from(EvmNetworkArg {
+                rpc_endpoints: vec!["polygon-rpc.com".to_string()],
+                ..polygon_arg()
+            }),
+            Err(_)
+        );
+        assert_matches!(
+            EvmNetwork::try_from(EvmNetworkArg {
+                pending_deposits_scraping_interval_seconds: Some(0),
+                ..polygon_arg()
+            }),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn should_keep_current_providers_when_upgrading_without_endpoints() {
+        let network = EvmNetwork::try_from(EvmNetworkArg {
+            consensus_threshold: Some(3),
+            ..polygon_arg()
+        })
+        .unwrap();
+
+        let upgraded = network
+            .upgraded(EvmNetworkArg {
+                rpc_endpoints: vec![],
+                deposit_confirmations: Some(128),
+                ..polygon_arg()
+            })
+            .unwrap();
+        assert_eq!(upgraded.rpc_endpoints, network.rpc_endpoints);
+        assert_eq!(upgraded.consensus_threshold, 3);
+        assert_eq!(upgraded.deposit_confirmations, 128);
+
+        let upgraded = network
+            .upgraded(EvmNetworkArg {
+                rpc_endpoints: vec!["https://polygon-rpc.com".to_string()],
+                ..polygon_arg()
+            })
+            .unwrap();
+        assert_eq!(
+            upgraded.rpc_endpoints,
+            vec![RpcEndpoint::from("https://polygon-rpc.com".to_string())]
+        );
+        assert_eq!(upgraded.consensus_threshold, 1);
+    }
+
+    fn polygon_arg() -> EvmNetworkArg {
+        EvmNetworkArg {
+            chain_id: 137,
+            name: "Polygon".to_string(),
+            rpc_endpoints: vec![
+                "https://polygon-rpc.com".to_string(),
+                "https://polygon.drpc.org".to_string(),
+                "https://polygon-bor-rpc.publicnode.com".to_string(),
+            ],
+            consensus_threshold: None,
+            finality: Some(CandidBlockTag::Finalized),
+            native_token_symbol: Some("POL".to_string()),
+            fee_policy: None,
+            deposit_confirmations: Some(64),
+            optimistic_deposits: Some(true),
+            pending_deposits_scraping_interval_seconds: Some(5),
+        }
+    }
+}
@@ -622,7 +622,12 @@ pub async fn lazy_refresh_gas_fee_estimate() -> Option<GasFeeEstimate> {
             }
         };
 
-        let gas_fee_estimate = match estimate_transaction_fee(&fee_history) {
+        let min_max_priority_fee_per_gas =
+            read_state(|s| s.evm_network().fee_policy.min_max_priority_fee_per_gas);
+        let gas_fee_estimate = match estimate_transaction_fee(
+            &fee_history,
+            min_max_priority_fee_per_gas,
+        ) {
             Ok(estimate) => {
                 mutate_state(|s| {
                     s.last_transaction_price_estimate =
@@ -677,13 +682,11 @@ pub enum TransactionFeeEstimationError {
 /// From the fee history, the current base fee per gas and the max priority fee per gas are determined.
 /// Then, the max fee per gas is computed as `2 * base_fee_per_gas + max_priority_fee_per_gas` to ensure that
 /// the estimate remains valid for the next few blocks, see `<https://www.blocknative.com/blog/eip-1559-fees>`.
+/// The max priority fee per gas is at least `min_max_priority_fee_per_gas`, as set by the network's fee policy.
 pub fn estimate_transaction_fee(
     fee_history: &FeeHistory,
+    min_max_priority_fee_per_gas: WeiPerGas,
 ) -> Result<GasFeeEstimate, TransactionFeeEstimationError> {
-    // average value between the `minSuggestedMaxPriorityFeePerGas`
-    // used by Metamask, see
-    // https://github.com/MetaMask/core/blob/f5a4f52e17f407c6411e4ef9bd6685aab184b91d/packages/gas-fee-controller/src/fetchGasEstimatesViaEthFeeHistory/calculateGasFeeEstimatesForPriorityLevels.ts#L14
-    const MIN_MAX_PRIORITY_FEE_PER_GAS: WeiPerGas = WeiPerGas::new(1_500_000_000); //1.5 gwei
     let base_fee_per_gas_next_block = *fee_history.base_fee_per_gas.last().ok_or(
         TransactionFeeEstimationError::InvalidFeeHistory(
             "base_fee_per_gas should not be empty to be able to evaluate transaction price"
@@ -696,7 +699,7 @@ pub fn estimate_transaction_fee(
             **median(&mut rewards).ok_or(TransactionFeeEstimationError::InvalidFeeHistory(
                 "should be non-empty with rewards of the last 5 blocks".to_string(),
             ))?;
-        historic_max_priority_fee_per_gas.max(MIN_MAX_PRIORITY_FEE_PER_GAS)
+        historic_max_priority_fee_per_gas.max(min_max_priority_fee_per_gas)
     };
     let gas_fee_estimate = GasFeeEstimate {
         base_fee_per_gas: base_fee_per_gas_next_block,
@@ -11,6 +11,8 @@ mod estimate_transaction_price {
     use proptest::{prop_assert_eq, proptest};
     use std::cmp::max;
 
+    const MIN_MAX_PRIORITY_FEE_PER_GAS: WeiPerGas = WeiPerGas::new(1_500_000_000);
+
     proptest! {
         #[test]
         fn should_estimate_transaction_price(
@@ -26,7 +28,7 @@ mod estimate_transaction_price {
             };
             let fee_history = fee_history(base_fee_per_gas, reward);
 
-            let result = estimate_transaction_fee(&fee_history);
+            let result = estimate_transaction_fee(&fee_history, MIN_MAX_PRIORITY_FEE_PER_GAS);
 
             prop_assert_eq!(
                 result,
@@ -52,7 +54,7 @@ mod estimate_transaction_price {
             vec![0_u8, 0, 0, 0, 0],
         );
 
-        let result = estimate_transaction_fee(&fee_history);
+        let result = estimate_transaction_fee(&fee_history, MIN_MAX_PRIORITY_FEE_PER_GAS);
 
         assert_matches!(result, Err(TransactionFeeEstimationError::Overflow(_)));
     }
@@ -60,7 +62,7 @@ mod estimate_transaction_price {
     #[test]
     fn should_fail_when_max_priority_fee_per_gas_overflows() {
         let fee_history = fee_history(vec![0_u8, 0, 0, 0, 0, 1], [WeiPerGas::MAX; 5].to_vec());
-        let result = estimate_transaction_fee(&fee_history);
+        let result = estimate_transaction_fee(&fee_history, MIN_MAX_PRIORITY_FEE_PER_GAS);
         assert_matches!(result, Err(TransactionFeeEstimationError::Overflow(_)));
     }
 
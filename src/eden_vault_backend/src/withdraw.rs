@@ -4,21 +4,28 @@ use crate::eth_rpc_client::EthRpcClient;
 use crate::eth_rpc_client::MultiCallError;
 use crate::guard::TimerGuard;
 use crate::logs::{DEBUG, INFO};
-use crate::numeric::{GasAmount, TransactionCount};
+use crate::numeric::{GasAmount, LedgerMintIndex, TransactionCount};
 use crate::state::audit::{process_event, EventType};
-use crate::state::transactions::{create_transaction, CreateTransactionError, WithdrawalRequest};
-use crate::state::{mutate_state, read_state, State, TaskType};
+use crate::state::transactions::{
+    create_transaction, to_account, CreateTransactionError, Reimbursed, ReimbursementIndex,
+    ReimbursementRequest, WithdrawalRequest,
+};
+use crate::state::{mutate_state, read_state, TaskType};
+use crate::storage;
 use crate::tx::{lazy_refresh_gas_fee_estimate, GasFeeEstimate};
 use candid::Nat;
 use futures::future::join_all;
 use ic_canister_log::log;
+use scopeguard::ScopeGuard;
 use std::collections::{BTreeMap, BTreeSet};
 use std::iter::zip;
+use std::time::Duration;
 
 const WITHDRAWAL_REQUESTS_BATCH_SIZE: usize = 5;
 const TRANSACTIONS_TO_SIGN_BATCH_SIZE: usize = 5;
 const TRANSACTIONS_TO_SEND_BATCH_SIZE: usize = 5;
 
+pub const CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT: GasAmount = GasAmount::new(21_000);
 pub const CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT: GasAmount = GasAmount::new(65_000);
 
 pub async fn process_retrieve_eth_requests() {
@@ -55,6 +62,11 @@ pub async fn process_retrieve_eth_requests() {
     send_transactions_batch(latest_transaction_count).await;
     finalize_transactions_batch().await;
 
+    if read_state(|s| s.eth_transactions.has_reimbursement_requests()) {
+        ic_cdk_timers::set_timer(Duration::from_secs(0), || {
+            ic_cdk::spawn(process_reimbursement())
+        });
+    }
     if read_state(|s| s.eth_transactions.has_pending_requests()) {
         ic_cdk_timers::set_timer(
             crate::PROCESS_ETH_RETRIEVE_TRANSACTIONS_RETRY_INTERVAL,
@@ -63,6 +75,75 @@ pub async fn process_retrieve_eth_requests() {
     }
 }
 
+pub async fn process_reimbursement() {
+    let _guard = match TimerGuard::new(TaskType::Reimbursement) {
+        Ok(guard) => guard,
+        Err(e) => {
+            log!(
+                DEBUG,
+                "Failed retrieving reimbursement guard: {e:?}",
+            );
+            return;
+        }
+    };
+
+    let reimbursements: Vec<(ReimbursementIndex, ReimbursementRequest)> = read_state(|s| {
+        s.eth_transactions
+            .reimbursement_requests_iter()
+            .map(|(index, request)| (index.clone(), request.clone()))
+            .collect()
+    });
+    if reimbursements.is_empty() {
+        return;
+    }
+
+    for (index, reimbursement_request) in reimbursements {
+        // Ensure that even if we were to panic in the callback, after having credited the user,
+        // this reimbursement will not be processed again.
+        let prevent_double_minting_guard = scopeguard::guard(index.clone(), |index| {
+            mutate_state(|s| process_event(s, EventType::QuarantinedReimbursement { index }));
+        });
+        // The reimbursement is recorded in the block appended for this event.
+        // Native coins are not recorded in the block log,
+        // the index then only tells when the reimbursement happened.
+        let reimbursed_in_block = LedgerMintIndex::new(storage::total_block_count());
+        let reimbursed = Reimbursed {
+            reimbursed_in_block,
+            burn_in_block: reimbursement_request.ledger_burn_index.clone(),
+            reimbursed_amount: reimbursement_request.reimbursed_amount,
+            transaction_hash: reimbursement_request.transaction_hash,
+        };
+        mutate_state(|s| {
+            process_event(
+                s,
+                match &index {
+                    ReimbursementIndex::CkEth { .. } => {
+                        EventType::ReimbursedEthWithdrawal(reimbursed)
+                    }
+                    ReimbursementIndex::CkErc20 { withdrawal_id } => {
+                        EventType::ReimbursedErc20Withdrawal {
+                            withdrawal_id: withdrawal_id.clone(),
+                            reimbursed,
+                        }
+                    }
+                },
+            )
+        });
+        log!(
+            INFO,
+            "[process_reimbursement]: reimbursed {} to {} for failed withdrawal {} in block {reimbursed_in_block}",
+            reimbursement_request.reimbursed_amount,
+            to_account(
+                reimbursement_request.to,
+                reimbursement_request.to_subaccount.clone()
+            ),
+            index.id(),
+        );
+        // reimbursement succeeded, defuse guard
+        ScopeGuard::into_inner(prevent_double_minting_guard);
+    }
+}
+
 async fn latest_transaction_count() -> Option<TransactionCount> {
     match read_state(EthRpcClient::from_state)
         .eth_get_latest_transaction_count(crate::state::minter_address().await)
@@ -122,7 +203,7 @@ fn create_transactions_batch(gas_fee_estimate: GasFeeEstimate) {
             .withdrawal_requests_batch(WITHDRAWAL_REQUESTS_BATCH_SIZE)
     }) {
         log!(DEBUG, "[create_transactions_batch]: processing {request:?}",);
-        let ethereum_network = read_state(State::ethereum_network);
+        let chain_id = read_state(|s| s.evm_network().chain_id);
         let nonce = read_state(|s| s.eth_transactions.next_transaction_nonce());
         let gas_limit = estimate_gas_limit(&request);
         match create_transaction(
@@ -130,7 +211,7 @@ fn create_transactions_batch(gas_fee_estimate: GasFeeEstimate) {
             nonce,
             gas_fee_estimate.clone(),
             gas_limit,
-            ethereum_network,
+            chain_id,
         ) {
             Ok(transaction) => {
                 log!(
@@ -159,12 +240,32 @@ fn create_transactions_batch(gas_fee_estimate: GasFeeEstimate) {
                 );
                 mutate_state(|s| s.eth_transactions.reschedule_withdrawal_request(request));
             }
+            Err(CreateTransactionError::BlockedDestination {
+                withdrawal_id,
+                destination,
+            }) => {
+                // Rescheduling the request would keep it in the queue until the destination is unblocked.
+                log!(
+                    INFO,
+                    "[create_transactions_batch]: Withdrawal request with id {withdrawal_id} has blocked destination {destination}. Request rejected and amount given back."
+                );
+                mutate_state(|s| {
+                    process_event(
+                        s,
+                        EventType::RejectedWithdrawalRequest {
+                            withdrawal_id,
+                            destination,
+                        },
+                    )
+                });
+            }
         };
     }
 }
 
 pub fn estimate_gas_limit(withdrawal_request: &WithdrawalRequest) -> GasAmount {
     match withdrawal_request {
+        WithdrawalRequest::CkEth(_) => CKETH_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
         WithdrawalRequest::CkErc20(_) => CKERC20_WITHDRAWAL_TRANSACTION_GAS_LIMIT,
     }
 }